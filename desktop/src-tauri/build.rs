@@ -1,3 +1,23 @@
 fn main() {
+  // Commit shown in the About panel; missing when building outside a checkout
+  if let Ok(output) = std::process::Command::new("git")
+    .args(["rev-parse", "--short", "HEAD"])
+    .output()
+  {
+    if output.status.success() {
+      let commit = String::from_utf8_lossy(&output.stdout);
+      println!("cargo:rustc-env=TIMEGRID_COMMIT={}", commit.trim());
+    }
+  }
+  // Rebuild when the checked-out commit changes: HEAD moves on checkout, the
+  // branch ref on commit, and packed-refs after a gc
+  let git_dir = std::path::Path::new("../../.git");
+  println!("cargo:rerun-if-changed={}", git_dir.join("HEAD").display());
+  println!("cargo:rerun-if-changed={}", git_dir.join("packed-refs").display());
+  if let Ok(head) = std::fs::read_to_string(git_dir.join("HEAD")) {
+    if let Some(branch) = head.trim().strip_prefix("ref: ") {
+      println!("cargo:rerun-if-changed={}", git_dir.join(branch).display());
+    }
+  }
   tauri_build::build()
 }
@@ -0,0 +1,27 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+// Environment details for bug reports
+#[derive(Debug, Clone, Serialize)]
+pub struct AppInfo {
+    pub version: String,
+    pub commit: Option<String>,
+    pub tauri_version: String,
+    pub os: String,
+    pub arch: String,
+    // None when the webview runtime can't be queried
+    pub webview_version: Option<String>,
+}
+
+pub fn info(app: &AppHandle) -> AppInfo {
+    AppInfo {
+        version: app.package_info().version.to_string(),
+        commit: option_env!("TIMEGRID_COMMIT").map(str::to_string),
+        tauri_version: tauri::VERSION.to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        webview_version: tauri::webview_version()
+            .map_err(|e| log::warn!("failed to read the webview version: {}", e))
+            .ok(),
+    }
+}
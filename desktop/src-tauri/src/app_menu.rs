@@ -0,0 +1,128 @@
+use tauri::{
+    menu::{Menu, MenuItem, PredefinedMenuItem, Submenu},
+    Manager, Runtime,
+};
+
+use crate::db::Database;
+use crate::i18n::I18n;
+use crate::shortcuts;
+
+// Create native application menu (macOS standard menus) in the current locale
+pub fn build<R: Runtime, M: Manager<R>>(app: &M) -> tauri::Result<Menu<R>> {
+    let i18n = app.state::<I18n>();
+    let t = |key: &str| i18n.t(key);
+    // Remapped accelerators; defaults while the database is locked
+    let bindings = app
+        .try_state::<Database>()
+        .and_then(|db| db.lock().ok().and_then(|conn| shortcuts::menu_shortcuts(&conn).ok()))
+        .unwrap_or_else(shortcuts::defaults);
+    let accel = |action: &str| bindings.get(action).cloned();
+    let app_name = "TimeGrid";
+
+    // App Menu (macOS standard first menu)
+    let about = PredefinedMenuItem::about(app, Some(app_name), None)?;
+    let check_updates = MenuItem::with_id(app, "check_updates", t("menu.check_updates"), true, None::<&str>)?;
+    let settings = MenuItem::with_id(app, "settings", t("menu.settings"), true, accel("settings"))?;
+    let hide = PredefinedMenuItem::hide(app, Some(&t("menu.hide")))?;
+    let hide_others = PredefinedMenuItem::hide_others(app, Some(&t("menu.hide_others")))?;
+    let show_all = PredefinedMenuItem::show_all(app, Some(&t("menu.show_all")))?;
+    let quit = PredefinedMenuItem::quit(app, Some(&t("menu.quit")))?;
+
+    let app_menu = Submenu::with_items(
+        app,
+        app_name,
+        true,
+        &[
+            &about,
+            &check_updates,
+            &settings,
+            &hide,
+            &hide_others,
+            &show_all,
+            &quit,
+        ],
+    )?;
+
+    // File Menu
+    let new_entry = MenuItem::with_id(app, "new_entry", t("menu.new_entry"), true, accel("new_entry"))?;
+    let close_window = PredefinedMenuItem::close_window(app, Some(&t("menu.close_window")))?;
+
+    let file_menu = Submenu::with_items(
+        app,
+        t("menu.file"),
+        true,
+        &[
+            &new_entry,
+            &close_window,
+        ],
+    )?;
+
+    // Edit Menu (standard macOS edit operations)
+    let undo = PredefinedMenuItem::undo(app, Some(&t("menu.undo")))?;
+    let redo = PredefinedMenuItem::redo(app, Some(&t("menu.redo")))?;
+    let cut = PredefinedMenuItem::cut(app, Some(&t("menu.cut")))?;
+    let copy = PredefinedMenuItem::copy(app, Some(&t("menu.copy")))?;
+    let paste = PredefinedMenuItem::paste(app, Some(&t("menu.paste")))?;
+    let select_all = PredefinedMenuItem::select_all(app, Some(&t("menu.select_all")))?;
+
+    let edit_menu = Submenu::with_items(
+        app,
+        t("menu.edit"),
+        true,
+        &[
+            &undo,
+            &redo,
+            &cut,
+            &copy,
+            &paste,
+            &select_all,
+        ],
+    )?;
+
+    // View Menu
+    let toggle_timer = MenuItem::with_id(app, "toggle_timer", t("menu.quick_timer"), true, accel("toggle_timer"))?;
+    let toggle_fullscreen = PredefinedMenuItem::fullscreen(app, Some(&t("menu.fullscreen")))?;
+
+    let view_menu = Submenu::with_items(
+        app,
+        t("menu.view"),
+        true,
+        &[
+            &toggle_timer,
+            &toggle_fullscreen,
+        ],
+    )?;
+
+    // Window Menu (standard macOS window management)
+    let minimize = PredefinedMenuItem::minimize(app, Some(&t("menu.minimize")))?;
+    let zoom = PredefinedMenuItem::maximize(app, Some(&t("menu.zoom")))?;
+
+    let window_menu = Submenu::with_items(
+        app,
+        t("menu.window"),
+        true,
+        &[
+            &minimize,
+            &zoom,
+        ],
+    )?;
+
+    // Build the native menu bar
+    Menu::with_items(
+        app,
+        &[
+            &app_menu,
+            &file_menu,
+            &edit_menu,
+            &view_menu,
+            &window_menu,
+        ],
+    )
+}
+
+// Rebuild both the application and tray menus, e.g. after the locale changes
+pub fn rebuild(app: &tauri::AppHandle) -> Result<(), String> {
+    let menu = build(app).map_err(|e| e.to_string())?;
+    app.set_menu(menu).map_err(|e| e.to_string())?;
+    crate::tray::refresh_menu(app)
+}
@@ -0,0 +1,30 @@
+use tauri::{AppHandle, Emitter, Manager, Theme};
+
+use crate::tray;
+use crate::widget::WIDGET_LABEL;
+
+pub const THEME_CHANGED_EVENT: &str = "theme-changed";
+
+pub fn theme_name(theme: Theme) -> &'static str {
+    match theme {
+        Theme::Dark => "dark",
+        _ => "light",
+    }
+}
+
+// The OS appearance. Read from the widget because the main window pins its
+// own theme in tauri.conf.json and would always report that instead.
+pub fn system_theme(app: &AppHandle) -> Result<Theme, String> {
+    let widget = app
+        .get_webview_window(WIDGET_LABEL)
+        .ok_or("timer widget window not found")?;
+    widget.theme().map_err(|e| e.to_string())
+}
+
+// Re-tint the tray icon for the new appearance and let the webviews follow
+pub fn on_theme_changed(app: &AppHandle, theme: Theme) {
+    if let Err(e) = tray::apply_theme(app, theme) {
+        log::warn!("failed to update tray icon for {} mode: {}", theme_name(theme), e);
+    }
+    let _ = app.emit(THEME_CHANGED_EVENT, theme_name(theme));
+}
@@ -0,0 +1,158 @@
+use std::fs;
+use std::path::Path;
+
+use rusqlite::{params, Connection, Transaction};
+use serde::{Deserialize, Serialize};
+
+use crate::entries::{self, Entry};
+use crate::settings;
+
+// Archives moved out of the active database, so stats and exports can say
+// which range they no longer cover
+pub const ARCHIVES_SETTING: &str = "archives";
+
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveRecord {
+    pub path: String,
+    pub before_ts: i64,
+    pub count: usize,
+}
+
+// On-disk JSON archive
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveFile {
+    version: u32,
+    before_ts: i64,
+    archived_ts: i64,
+    entries: Vec<Entry>,
+}
+
+pub fn archives(conn: &Connection) -> Result<Vec<ArchiveRecord>, String> {
+    Ok(settings::get(conn, ARCHIVES_SETTING)?.unwrap_or_default())
+}
+
+// Latest cutoff of any archive still out of the database; entries starting
+// before it may be missing from the active store
+pub fn archived_before(conn: &Connection) -> Result<Option<i64>, String> {
+    Ok(archives(conn)?.iter().map(|record| record.before_ts).max())
+}
+
+// Move finished entries starting before `before_ts` into a new archive file at
+// `path`. The file is written before the entries are deleted and the delete is
+// one transaction, so a failure leaves every entry in at least one place.
+// Running timers' open entries are never archived.
+pub fn archive_entries(
+    conn: &mut Connection,
+    before_ts: i64,
+    path: &Path,
+    now: i64,
+) -> Result<Vec<Entry>, String> {
+    if path.exists() {
+        return Err(format!("{} already exists; choose a new archive file", path.display()));
+    }
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let archived = entries::list_finished_before(&tx, before_ts)?;
+    if archived.is_empty() {
+        return Ok(archived);
+    }
+
+    let file = ArchiveFile {
+        version: FORMAT_VERSION,
+        before_ts,
+        archived_ts: now,
+        entries: archived,
+    };
+    let json = serde_json::to_vec_pretty(&file).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+
+    let result = delete_archived(tx, before_ts, path, file.entries.len());
+    if let Err(e) = result {
+        // Nothing was removed, so the file would only be a confusing duplicate
+        let _ = fs::remove_file(path);
+        return Err(e);
+    }
+    Ok(file.entries)
+}
+
+// Remove what was just written to the archive and record the archive
+fn delete_archived(tx: Transaction, before_ts: i64, path: &Path, count: usize) -> Result<(), String> {
+    tx.execute(
+        "DELETE FROM entries WHERE start_ts < ?1 AND end_ts IS NOT NULL",
+        params![before_ts],
+    )
+    .map_err(|e| e.to_string())?;
+    let mut records = archives(&tx)?;
+    records.push(ArchiveRecord {
+        path: path.to_string_lossy().into_owned(),
+        before_ts,
+        count,
+    });
+    settings::set(&tx, ARCHIVES_SETTING, &records)?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+// Re-import an archive file. Entries already present (e.g. from an earlier
+// restore) are skipped; returns the entries actually restored.
+pub fn restore_archive(conn: &mut Connection, path: &Path) -> Result<Vec<Entry>, String> {
+    let raw = fs::read(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    let file: ArchiveFile = serde_json::from_slice(&raw)
+        .map_err(|e| format!("{} is not a TimeGrid archive: {}", path.display(), e))?;
+    if file.version != FORMAT_VERSION {
+        return Err(format!("unsupported archive version {}", file.version));
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut restored = Vec::new();
+    for entry in file.entries {
+        let inserted = tx
+            .execute(
+                "INSERT OR IGNORE INTO entries (id, project, note, start_ts, end_ts)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![entry.id, entry.project, entry.note, entry.start_ts, entry.end_ts],
+            )
+            .map_err(|e| e.to_string())?;
+        if inserted > 0 {
+            restored.push(entry);
+        }
+    }
+    let path = path.to_string_lossy();
+    let mut records = archives(&tx)?;
+    records.retain(|record| record.path != path);
+    settings::set(&tx, ARCHIVES_SETTING, &records)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn archive_and_restore_round_trip() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let old = entries::insert_entry(&conn, "A", "old", 100, Some(200)).unwrap();
+        let recent = entries::insert_entry(&conn, "A", "recent", 5_000, Some(6_000)).unwrap();
+        let running = entries::insert_entry(&conn, "B", "", 150, None).unwrap();
+
+        let path = std::env::temp_dir().join(format!("timegrid-archive-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let archived = archive_entries(&mut conn, 1_000, &path, 7_000).unwrap();
+        assert_eq!(archived, vec![old.clone()]);
+        assert!(entries::get_entry(&conn, old.id).is_err());
+        assert!(entries::get_entry(&conn, recent.id).is_ok());
+        assert!(entries::get_entry(&conn, running.id).is_ok());
+        assert_eq!(archived_before(&conn).unwrap(), Some(1_000));
+        // Never overwrite an existing archive
+        assert!(archive_entries(&mut conn, 1_000, &path, 7_000).is_err());
+
+        assert_eq!(restore_archive(&mut conn, &path).unwrap(), vec![old.clone()]);
+        assert_eq!(entries::get_entry(&conn, old.id).unwrap(), old);
+        assert_eq!(archived_before(&conn).unwrap(), None);
+        assert!(restore_archive(&mut conn, &path).unwrap().is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+}
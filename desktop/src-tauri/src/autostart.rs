@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{async_runtime, AppHandle, Emitter, Manager};
+use user_idle::UserIdle;
+
+use crate::db::{self, Database};
+use crate::entries;
+use crate::i18n::I18n;
+use crate::notify;
+use crate::settings;
+use crate::timer::Timer;
+
+pub const ENABLED_SETTING: &str = "autostart_prompt_enabled";
+pub const MINUTES_SETTING: &str = "autostart_prompt_minutes";
+pub const DEFAULT_MINUTES: u32 = 10;
+
+// Carries the prompt's "start" action: desktop notifications can't have
+// buttons, so the UI offers to start `project` when it receives this
+pub const AUTOSTART_PROMPT_EVENT: &str = "autostart-prompt";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+// No input for this long ends a stretch of activity
+const BREAK_SECS: u64 = 5 * 60;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AutostartPrompt {
+    pub project: Option<String>,
+}
+
+// Follows one stretch of continuous activity without a running timer and
+// prompts at most once per stretch
+#[derive(Debug, Default)]
+struct Activity {
+    active_since: Option<i64>,
+    prompted: bool,
+}
+
+impl Activity {
+    // Record a poll; true when the user should be prompted now
+    fn observe(&mut self, idle_secs: u64, timer_running: bool, threshold_secs: i64, now: i64) -> bool {
+        if idle_secs >= BREAK_SECS || timer_running {
+            *self = Self::default();
+            return false;
+        }
+        let since = *self.active_since.get_or_insert(now - idle_secs as i64);
+        if self.prompted || now - since < threshold_secs {
+            return false;
+        }
+        self.prompted = true;
+        true
+    }
+}
+
+// Prompt threshold in seconds, or None while the prompt is off
+fn threshold_secs(app: &AppHandle) -> Option<i64> {
+    let db = app.state::<Database>();
+    let conn = db.lock().ok()?;
+    let enabled = settings::get(&conn, ENABLED_SETTING).ok().flatten().unwrap_or(false);
+    let minutes: u32 = settings::get(&conn, MINUTES_SETTING)
+        .ok()
+        .flatten()
+        .unwrap_or(DEFAULT_MINUTES);
+    enabled.then_some(minutes as i64 * 60)
+}
+
+fn prompt(app: &AppHandle) {
+    let db = app.state::<Database>();
+    let conn = db.lock().ok();
+    let project = conn
+        .as_ref()
+        .and_then(|conn| entries::last_project(conn).ok())
+        .flatten();
+    let i18n = app.state::<I18n>();
+    let body = match &project {
+        Some(project) => i18n.t("notify.autostart_body").replace("{project}", project),
+        None => i18n.t("notify.autostart_body_generic"),
+    };
+    notify::show(app, conn.as_deref(), &i18n.t("notify.autostart_title"), &body);
+    drop(conn);
+    let _ = app.emit(AUTOSTART_PROMPT_EVENT, AutostartPrompt { project });
+}
+
+// Poll the OS for time since the last keyboard or mouse input and nudge the
+// user after a configurable stretch of activity with no timer running
+pub fn spawn_watcher(app: AppHandle) {
+    async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        let mut activity = Activity::default();
+        loop {
+            interval.tick().await;
+            let Some(threshold) = threshold_secs(&app) else {
+                activity = Activity::default();
+                continue;
+            };
+            let idle_secs = match UserIdle::get_time() {
+                Ok(idle) => idle.as_seconds(),
+                Err(e) => {
+                    log::warn!("failed to read input idle time: {:?}", e);
+                    continue;
+                }
+            };
+            let running = app
+                .state::<Timer>()
+                .lock()
+                .map(|timers| !timers.is_empty())
+                .unwrap_or(true);
+            if activity.observe(idle_secs, running, threshold, db::now_ts()) {
+                prompt(&app);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompts_once_per_stretch_of_activity() {
+        let mut activity = Activity::default();
+        assert!(!activity.observe(5, false, 600, 1000));
+        assert!(!activity.observe(5, false, 600, 1500));
+        assert!(activity.observe(5, false, 600, 1600));
+        assert!(!activity.observe(5, false, 600, 2400));
+
+        // A break resets the stretch, and so does a running timer
+        assert!(!activity.observe(BREAK_SECS, false, 600, 3000));
+        assert!(!activity.observe(5, false, 600, 3100));
+        assert!(!activity.observe(5, true, 600, 3500));
+        assert!(!activity.observe(5, false, 600, 3800));
+        assert!(activity.observe(5, false, 600, 4400));
+    }
+}
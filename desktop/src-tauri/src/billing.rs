@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::settings;
+use crate::tags::{self, BILLABLE_TAG};
+
+// Hourly rate per project, in cents
+pub const RATES_SETTING: &str = "project_rates";
+// Entry durations are rounded up to a multiple of this; 0 disables rounding
+pub const ROUNDING_SETTING: &str = "rounding_minutes";
+
+pub fn rates(conn: &Connection) -> Result<BTreeMap<String, i64>, String> {
+    Ok(settings::get(conn, RATES_SETTING)?.unwrap_or_default())
+}
+
+// `None` removes the project's rate
+pub fn set_rate(conn: &Connection, project: &str, cents_per_hour: Option<i64>) -> Result<(), String> {
+    let mut rates = rates(conn)?;
+    match cents_per_hour {
+        Some(cents) if cents < 0 => return Err("rate cannot be negative".to_string()),
+        Some(cents) => rates.insert(project.to_string(), cents),
+        None => rates.remove(project),
+    };
+    settings::set(conn, RATES_SETTING, &rates)
+}
+
+pub fn rounding_minutes(conn: &Connection) -> Result<u32, String> {
+    Ok(settings::get(conn, ROUNDING_SETTING)?.unwrap_or(0))
+}
+
+pub fn set_rounding_minutes(conn: &Connection, minutes: u32) -> Result<(), String> {
+    settings::set(conn, ROUNDING_SETTING, &minutes)
+}
+
+pub fn round_up(seconds: i64, minutes: u32) -> i64 {
+    let step = minutes as i64 * 60;
+    if step == 0 {
+        return seconds;
+    }
+    (seconds + step - 1) / step * step
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InvoiceGroup {
+    Project,
+    Tag,
+}
+
+impl InvoiceGroup {
+    pub fn parse(group: &str) -> Result<Self, String> {
+        match group {
+            "project" => Ok(Self::Project),
+            "tag" => Ok(Self::Tag),
+            other => Err(format!("unknown invoice grouping '{}', expected project or tag", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InvoiceLine {
+    // Set when grouping by project
+    pub project: Option<String>,
+    // The entries' other tags, comma separated, when grouping by tag; None for untagged
+    pub tags: Option<String>,
+    pub seconds: i64,
+    pub hours: f64,
+    // None if the line has no rate or mixes projects with different rates
+    pub rate_cents: Option<i64>,
+    pub amount_cents: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InvoiceData {
+    pub lines: Vec<InvoiceLine>,
+    pub total_seconds: i64,
+    pub total_cents: i64,
+}
+
+type LineKey = (Option<String>, Option<String>);
+
+// Finished entries starting in [from_ts, to_ts), each rounded up and priced at
+// its project's rate, then summed into one line per group. An entry's tags
+// form a single group (minus the billable tag), so nothing is counted twice.
+// With `billable_only` entries without the billable tag are left out.
+pub fn invoice(
+    conn: &Connection,
+    from_ts: i64,
+    to_ts: i64,
+    group_by: &[InvoiceGroup],
+    billable_only: bool,
+) -> Result<InvoiceData, String> {
+    let rates = rates(conn)?;
+    let rounding = rounding_minutes(conn)?;
+    let entry_tags = tags::tags_in_range(conn, from_ts, to_ts)?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, project, end_ts - start_ts FROM entries
+             WHERE start_ts >= ?1 AND start_ts < ?2 AND end_ts IS NOT NULL
+             ORDER BY start_ts",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_ts, to_ts], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?))
+        })
+        .map_err(|e| e.to_string())?;
+
+    // Seconds per line, split by project so each is priced at its own rate
+    let mut grouped: BTreeMap<LineKey, BTreeMap<String, i64>> = BTreeMap::new();
+    for row in rows {
+        let (id, project, seconds) = row.map_err(|e| e.to_string())?;
+        let tags = entry_tags.get(&id).map(Vec::as_slice).unwrap_or_default();
+        if billable_only && !tags.iter().any(|tag| tag == BILLABLE_TAG) {
+            continue;
+        }
+        let other_tags: Vec<&str> = tags
+            .iter()
+            .map(String::as_str)
+            .filter(|tag| *tag != BILLABLE_TAG)
+            .collect();
+        let key = (
+            group_by.contains(&InvoiceGroup::Project).then(|| project.clone()),
+            (group_by.contains(&InvoiceGroup::Tag) && !other_tags.is_empty()).then(|| other_tags.join(", ")),
+        );
+        *grouped.entry(key).or_default().entry(project).or_default() += round_up(seconds.max(0), rounding);
+    }
+
+    let mut invoice = InvoiceData {
+        lines: Vec::new(),
+        total_seconds: 0,
+        total_cents: 0,
+    };
+    for ((project, tags), by_project) in grouped {
+        let seconds: i64 = by_project.values().sum();
+        let amount_cents: i64 = by_project
+            .iter()
+            .map(|(project, seconds)| {
+                let rate = rates.get(project).copied().unwrap_or(0);
+                (seconds * rate + 1800) / 3600
+            })
+            .sum();
+        let mut line_rates = by_project.keys().map(|project| rates.get(project).copied());
+        let first_rate = line_rates.next().flatten();
+        let rate_cents = if line_rates.all(|rate| rate == first_rate) {
+            first_rate
+        } else {
+            None
+        };
+        invoice.total_seconds += seconds;
+        invoice.total_cents += amount_cents;
+        invoice.lines.push(InvoiceLine {
+            project,
+            tags,
+            seconds,
+            hours: seconds as f64 / 3600.0,
+            rate_cents,
+            amount_cents,
+        });
+    }
+    Ok(invoice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db, entries};
+
+    #[test]
+    fn invoice_rounds_prices_and_filters_billable_entries() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        set_rate(&conn, "Acme", Some(10_000)).unwrap();
+        set_rate(&conn, "Beta", Some(6_000)).unwrap();
+        set_rounding_minutes(&conn, 15).unwrap();
+
+        // 50 minutes rounds to an hour, 20 to 30 minutes, 5 to 15
+        let a = entries::insert_entry(&conn, "Acme", "", 0, Some(50 * 60)).unwrap();
+        let b = entries::insert_entry(&conn, "Beta", "", 3_600, Some(3_600 + 20 * 60)).unwrap();
+        entries::insert_entry(&conn, "Acme", "", 7_200, Some(7_200 + 5 * 60)).unwrap();
+        entries::insert_entry(&conn, "Acme", "", 9_000, None).unwrap();
+        tags::set_entry_tags(&mut conn, a.id, &["Billable".to_string(), "design".to_string()]).unwrap();
+        tags::set_entry_tags(&mut conn, b.id, &["billable".to_string()]).unwrap();
+
+        let all = invoice(&conn, 0, 10_000, &[InvoiceGroup::Project], false).unwrap();
+        let lines: Vec<(Option<&str>, i64, Option<i64>, i64)> = all
+            .lines
+            .iter()
+            .map(|l| (l.project.as_deref(), l.seconds, l.rate_cents, l.amount_cents))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (Some("Acme"), 75 * 60, Some(10_000), 12_500),
+                (Some("Beta"), 30 * 60, Some(6_000), 3_000),
+            ]
+        );
+        assert_eq!(all.total_cents, 15_500);
+
+        // Only the billable entries, grouped by their other tags
+        let billable = invoice(&conn, 0, 10_000, &[InvoiceGroup::Tag], true).unwrap();
+        let lines: Vec<(Option<&str>, Option<i64>, i64)> = billable
+            .lines
+            .iter()
+            .map(|l| (l.tags.as_deref(), l.rate_cents, l.amount_cents))
+            .collect();
+        assert_eq!(lines, vec![(None, Some(6_000), 3_000), (Some("design"), Some(10_000), 10_000)]);
+        assert_eq!(billable.total_cents, 13_000);
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+
+use chrono_tz::Tz;
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::entries;
+use crate::progress;
+use crate::settings;
+
+// Weekly budget in seconds keyed by project
+pub const BUDGETS_SETTING: &str = "project_budgets";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BurndownDay {
+    pub date: String,
+    pub start_ts: i64,
+    // Tracked on the project from the start of the week through this day;
+    // None for days that haven't started yet
+    pub tracked_seconds: Option<i64>,
+    // Linear pace toward the budget by the end of this day
+    pub ideal_seconds: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Burndown {
+    pub project: String,
+    pub budget_seconds: i64,
+    pub days: Vec<BurndownDay>,
+}
+
+pub fn budgets(conn: &Connection) -> Result<BTreeMap<String, i64>, String> {
+    Ok(settings::get(conn, BUDGETS_SETTING)?.unwrap_or_default())
+}
+
+// `None` removes the project's budget
+pub fn set_budget(conn: &Connection, project: &str, weekly_seconds: Option<u64>) -> Result<(), String> {
+    let mut budgets = budgets(conn)?;
+    match weekly_seconds {
+        Some(0) => return Err("budget must be positive".to_string()),
+        Some(seconds) => budgets.insert(project.to_string(), seconds as i64),
+        None => budgets.remove(project),
+    };
+    settings::set(conn, BUDGETS_SETTING, &budgets)
+}
+
+// Cumulative tracked time per day of the week from the local day containing
+// `week_start_ts`, next to the ideal pace. Running entries count up to `now`.
+pub fn burndown(conn: &Connection, tz: &Tz, project: &str, week_start_ts: i64, now: i64) -> Result<Burndown, String> {
+    let budget_seconds = *budgets(conn)?
+        .get(project)
+        .ok_or_else(|| format!("project '{}' has no budget", project))?;
+    let days = progress::week_days(tz, week_start_ts);
+    let (from_ts, to_ts) = (days[0].1, days[days.len() - 1].2);
+    let entries: Vec<_> = entries::list_overlapping(conn, from_ts, to_ts, now)?
+        .into_iter()
+        .filter(|entry| entry.project == project)
+        .collect();
+
+    let mut cumulative = 0;
+    let days = days
+        .iter()
+        .enumerate()
+        .map(|(i, (date, day_start, day_end))| {
+            for entry in &entries {
+                let end_ts = entry.end_ts.unwrap_or(now).min(*day_end);
+                cumulative += (end_ts - entry.start_ts.max(*day_start)).max(0);
+            }
+            BurndownDay {
+                date: date.format("%Y-%m-%d").to_string(),
+                start_ts: *day_start,
+                tracked_seconds: (*day_start <= now).then_some(cumulative),
+                ideal_seconds: budget_seconds * (i as i64 + 1) / days.len() as i64,
+            }
+        })
+        .collect();
+    Ok(Burndown {
+        project: project.to_string(),
+        budget_seconds,
+        days,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn burndown_accumulates_and_leaves_the_future_empty() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        // Monday 2025-01-06 00:00 UTC
+        let monday = 1736121600;
+        assert!(burndown(&conn, &Tz::UTC, "Acme", monday, monday).is_err());
+        set_budget(&conn, "Acme", Some(7 * 3600)).unwrap();
+        entries::insert_entry(&conn, "Acme", "", monday + 9 * 3600, Some(monday + 11 * 3600)).unwrap();
+        entries::insert_entry(&conn, "Beta", "", monday + 12 * 3600, Some(monday + 13 * 3600)).unwrap();
+        // Running since Tuesday 09:00, and it is now Tuesday 10:00
+        let tuesday = monday + 86_400;
+        entries::insert_entry(&conn, "Acme", "", tuesday + 9 * 3600, None).unwrap();
+
+        let chart = burndown(&conn, &Tz::UTC, "Acme", monday + 3600, tuesday + 10 * 3600).unwrap();
+        let tracked: Vec<Option<i64>> = chart.days.iter().map(|day| day.tracked_seconds).collect();
+        assert_eq!(tracked, vec![Some(7_200), Some(10_800), None, None, None, None, None]);
+        assert_eq!(chart.days[0].ideal_seconds, 3600);
+        assert_eq!(chart.days[6].ideal_seconds, 7 * 3600);
+        assert_eq!(chart.days[0].date, "2025-01-06");
+    }
+}
@@ -0,0 +1,248 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::gaps;
+use crate::settings;
+
+// Opt-in: the active window's app name and title are only sampled while this is true
+pub const SAMPLING_SETTING: &str = "activity_sampling";
+
+// How often the active window is sampled; each sample stands for this long
+pub const SAMPLE_SECONDS: i64 = 30;
+// Samples older than this are pruned as new ones come in
+const RETENTION_SECONDS: i64 = 30 * 86_400;
+// Shorter matched stretches are noise rather than work worth an entry
+const MIN_SUGGESTION_SECONDS: i64 = 60;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rule {
+    pub id: i64,
+    pub pattern: String,
+    pub project: String,
+}
+
+impl Rule {
+    // Case-insensitive substring of the app name or the window title
+    fn matches(&self, app: &str, title: &str) -> bool {
+        let pattern = self.pattern.to_lowercase();
+        app.to_lowercase().contains(&pattern) || title.to_lowercase().contains(&pattern)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Suggestion {
+    pub project: String,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub duration_seconds: i64,
+    // Samples backing the suggestion, so the UI can show how sure it is
+    pub samples: u32,
+}
+
+pub fn sampling_enabled(conn: &Connection) -> Result<bool, String> {
+    Ok(settings::get(conn, SAMPLING_SETTING)?.unwrap_or(false))
+}
+
+// Turning sampling off also forgets what was captured so far
+pub fn set_sampling(conn: &Connection, enabled: bool) -> Result<(), String> {
+    settings::set(conn, SAMPLING_SETTING, &enabled)?;
+    if !enabled {
+        conn.execute("DELETE FROM activity_samples", [])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn record_sample(conn: &Connection, ts: i64, app: &str, title: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO activity_samples (ts, app, title) VALUES (?1, ?2, ?3)",
+        params![ts, app, title],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM activity_samples WHERE ts < ?1", params![ts - RETENTION_SECONDS])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn add_rule(conn: &Connection, pattern: &str, project: &str) -> Result<Rule, String> {
+    let (pattern, project) = (pattern.trim(), project.trim());
+    if pattern.is_empty() {
+        return Err("rule pattern cannot be empty".to_string());
+    }
+    if project.is_empty() {
+        return Err("project name cannot be empty".to_string());
+    }
+    conn.execute(
+        "INSERT INTO categorization_rules (pattern, project) VALUES (?1, ?2)",
+        params![pattern, project],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(Rule {
+        id: conn.last_insert_rowid(),
+        pattern: pattern.to_string(),
+        project: project.to_string(),
+    })
+}
+
+// Oldest first, which is also the order they are tried in
+pub fn list_rules(conn: &Connection) -> Result<Vec<Rule>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, pattern, project FROM categorization_rules ORDER BY id")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(Rule {
+                id: row.get(0)?,
+                pattern: row.get(1)?,
+                project: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+pub fn remove_rule(conn: &Connection, id: i64) -> Result<(), String> {
+    let removed = conn
+        .execute("DELETE FROM categorization_rules WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    if removed == 0 {
+        return Err(format!("rule {} not found", id));
+    }
+    Ok(())
+}
+
+fn samples_between(conn: &Connection, from_ts: i64, to_ts: i64) -> Result<Vec<(i64, String, String)>, String> {
+    let mut stmt = conn
+        .prepare("SELECT ts, app, title FROM activity_samples WHERE ts >= ?1 AND ts < ?2 ORDER BY ts")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_ts, to_ts], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+// Project assignments for untracked time in [from_ts, to_ts): consecutive
+// samples inside a gap whose app or title match the same rule become one
+// suggestion. The first matching rule wins; unmatched samples split stretches.
+pub fn suggest<Tz: chrono::TimeZone>(
+    conn: &Connection,
+    tz: &Tz,
+    from_ts: i64,
+    to_ts: i64,
+    now: i64,
+) -> Result<Vec<Suggestion>, String> {
+    let rules = list_rules(conn)?;
+    if rules.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut suggestions = Vec::new();
+    for gap in gaps::find_gaps(conn, tz, from_ts, to_ts, 0, None, now)? {
+        let mut current: Option<Suggestion> = None;
+        for (ts, app, title) in samples_between(conn, gap.start_ts, gap.end_ts)? {
+            let project = rules.iter().find(|rule| rule.matches(&app, &title)).map(|rule| &rule.project);
+            let end_ts = (ts + SAMPLE_SECONDS).min(gap.end_ts);
+            match (current.as_mut(), project) {
+                (Some(run), Some(project)) if run.project == *project && ts <= run.end_ts => {
+                    run.end_ts = end_ts;
+                    run.samples += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            suggestions.extend(current.take());
+            current = project.map(|project| Suggestion {
+                project: project.clone(),
+                start_ts: ts,
+                end_ts,
+                duration_seconds: 0,
+                samples: 1,
+            });
+        }
+        suggestions.extend(current);
+    }
+    Ok(suggestions
+        .into_iter()
+        .map(|run| Suggestion {
+            duration_seconds: run.end_ts - run.start_ts,
+            ..run
+        })
+        .filter(|run| run.duration_seconds >= MIN_SUGGESTION_SECONDS)
+        .collect())
+}
+
+// Sample the focused window every SAMPLE_SECONDS while sampling is on, the
+// user is at the keyboard and no timer runs, since only gaps get suggestions
+#[cfg(desktop)]
+pub fn spawn_sampler(app: tauri::AppHandle) {
+    use tauri::{async_runtime, Manager};
+    use user_idle::UserIdle;
+
+    use crate::db::{self, Database};
+    use crate::timer::Timer;
+
+    async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(SAMPLE_SECONDS as u64));
+        loop {
+            interval.tick().await;
+            let db = app.state::<Database>();
+            let enabled = db.lock().and_then(|conn| sampling_enabled(&conn)).unwrap_or(false);
+            let running = app
+                .state::<Timer>()
+                .lock()
+                .map(|timers| !timers.is_empty())
+                .unwrap_or(true);
+            let idle = UserIdle::get_time().map(|idle| idle.as_seconds()).unwrap_or(0);
+            if !enabled || running || idle >= SAMPLE_SECONDS as u64 * 2 {
+                continue;
+            }
+            let Ok(window) = active_win_pos_rs::get_active_window() else {
+                continue;
+            };
+            let result = db
+                .lock()
+                .and_then(|conn| record_sample(&conn, db::now_ts(), &window.app_name, &window.title));
+            match result {
+                Ok(()) => {}
+                Err(e) if db::is_unavailable(&e) => {}
+                Err(e) => log::warn!("failed to record activity sample: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    use crate::{db, entries};
+
+    #[test]
+    fn samples_in_gaps_become_suggestions() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        add_rule(&conn, "code", "Timegrid").unwrap();
+        add_rule(&conn, "Invoice", "Admin").unwrap();
+        assert!(add_rule(&conn, "  ", "Admin").is_err());
+
+        entries::insert_entry(&conn, "Acme", "", 1_000, Some(2_000)).unwrap();
+        for ts in (0..600).step_by(30) {
+            record_sample(&conn, ts, "Code", "main.rs").unwrap();
+        }
+        // Tracked time is never suggested
+        record_sample(&conn, 1_200, "Code", "lib.rs").unwrap();
+        for ts in (2_000..2_300).step_by(30) {
+            record_sample(&conn, ts, "Firefox", "invoice #12 - Mail").unwrap();
+        }
+        record_sample(&conn, 2_300, "Slack", "general").unwrap();
+        record_sample(&conn, 2_330, "Firefox", "invoice #12 - Mail").unwrap();
+
+        let suggestions = suggest(&conn, &Utc, 0, 3_000, 3_000).unwrap();
+        let runs: Vec<(&str, i64, i64, u32)> = suggestions
+            .iter()
+            .map(|s| (s.project.as_str(), s.start_ts, s.end_ts, s.samples))
+            .collect();
+        // The lone sample after the unmatched one is too short to suggest
+        assert_eq!(runs, vec![("Timegrid", 0, 600, 20), ("Admin", 2_000, 2_300, 10)]);
+    }
+}
@@ -0,0 +1,150 @@
+use std::io::Cursor;
+use std::time::Duration;
+
+use chrono::Timelike;
+use chrono_tz::Tz;
+use rodio::{Decoder, OutputStream, Sink};
+use tauri::{async_runtime, AppHandle, Manager};
+
+use crate::db::{self, Database};
+use crate::notify;
+use crate::settings;
+use crate::timefmt;
+use crate::timer::Timer;
+
+pub const HOURLY_CHIME_SETTING: &str = "hourly_chime";
+
+// A chime that fires more than this long after the hour (e.g. after the
+// machine slept through it) is skipped rather than played late
+const LATE_TOLERANCE_SECS: i64 = 60;
+
+static CHIME: &[u8] = include_bytes!("../sounds/chime.wav");
+
+// Play the bundled chime on the default output device, blocking until it ends
+pub fn play() -> Result<(), String> {
+    let (_stream, handle) =
+        OutputStream::try_default().map_err(|e| format!("no audio output device: {}", e))?;
+    let sink = Sink::try_new(&handle).map_err(|e| format!("failed to open audio output: {}", e))?;
+    let source = Decoder::new(Cursor::new(CHIME)).map_err(|e| format!("invalid chime sound: {}", e))?;
+    sink.append(source);
+    sink.sleep_until_end();
+    Ok(())
+}
+
+// Whether the OS is in Do Not Disturb / Focus, where that can be queried.
+// Unknown states count as not disturbed.
+#[cfg(target_os = "macos")]
+pub fn do_not_disturb() -> bool {
+    // macOS 12+ records active Focus assertions here
+    let Ok(home) = std::env::var("HOME") else {
+        return false;
+    };
+    let path = std::path::Path::new(&home).join("Library/DoNotDisturb/DB/Assertions.json");
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .and_then(|json| {
+            json["data"].as_array().map(|data| {
+                data.iter().any(|entry| {
+                    entry["storeAssertionRecords"]
+                        .as_array()
+                        .is_some_and(|records| !records.is_empty())
+                })
+            })
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "linux")]
+pub fn do_not_disturb() -> bool {
+    // GNOME turns banners off while Do Not Disturb is on
+    std::process::Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+        .output()
+        .map(|out| out.status.success() && String::from_utf8_lossy(&out.stdout).trim() == "false")
+        .unwrap_or(false)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub fn do_not_disturb() -> bool {
+    false
+}
+
+// Seconds from `now` until the next top of the hour in `tz`. Works for zones
+// with half- or quarter-hour offsets because it counts from the local clock.
+pub fn secs_until_next_hour(now: i64, tz: &Tz) -> i64 {
+    let local = timefmt::to_local(now, tz);
+    3600 - (local.minute() as i64 * 60 + local.second() as i64)
+}
+
+// Chime only when enabled, outside quiet hours and while some timer is
+// actively running (not paused)
+fn should_chime(app: &AppHandle) -> bool {
+    let enabled = app
+        .state::<Database>()
+        .lock()
+        .and_then(|conn| {
+            let enabled = settings::get(&conn, HOURLY_CHIME_SETTING)?.unwrap_or(false);
+            Ok(enabled && !notify::is_quiet(&conn, db::now_ts())?)
+        })
+        .unwrap_or(false);
+    let running = app
+        .state::<Timer>()
+        .lock()
+        .map(|timers| timers.any_active())
+        .unwrap_or(false);
+    enabled && running && !do_not_disturb()
+}
+
+// Sleep until each wall-clock hour and chime if a timer is running then
+pub fn spawn_hourly(app: AppHandle) {
+    async_runtime::spawn(async move {
+        loop {
+            let now = db::now_ts();
+            let tz = app
+                .state::<Database>()
+                .lock()
+                .and_then(|conn| timefmt::configured_zone(&conn))
+                .unwrap_or_else(|_| timefmt::system_zone());
+            let target = now + secs_until_next_hour(now, &tz);
+            tokio::time::sleep(Duration::from_secs((target - now) as u64)).await;
+
+            if db::now_ts() - target > LATE_TOLERANCE_SECS {
+                continue;
+            }
+            // Checking Do Not Disturb may run gsettings, so it happens off
+            // the async runtime along with the playback
+            let app = app.clone();
+            let chimed = async_runtime::spawn_blocking(move || {
+                if should_chime(&app) {
+                    play()
+                } else {
+                    Ok(())
+                }
+            });
+            match chimed.await {
+                Ok(Err(e)) => log::warn!("failed to play hourly chime: {}", e),
+                Err(e) => log::warn!("hourly chime task failed: {}", e),
+                Ok(Ok(())) => {}
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn next_hour_follows_local_offset() {
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 10, 20, 0).unwrap().timestamp();
+        assert_eq!(secs_until_next_hour(now, &Tz::UTC), 40 * 60);
+        // India is UTC+5:30, so 10:20 UTC is 15:50 local
+        let kolkata: Tz = "Asia/Kolkata".parse().unwrap();
+        assert_eq!(secs_until_next_hour(now, &kolkata), 10 * 60);
+        // Exactly on the hour waits a full hour rather than firing again
+        let on_hour = Utc.with_ymd_and_hms(2025, 6, 1, 11, 0, 0).unwrap().timestamp();
+        assert_eq!(secs_until_next_hour(on_hour, &Tz::UTC), 3600);
+    }
+}
@@ -0,0 +1,110 @@
+use rusqlite::{params, Connection, OptionalExtension};
+
+// Derived colors share saturation and lightness so they read alike on the timeline
+const SATURATION: f64 = 0.65;
+const LIGHTNESS: f64 = 0.5;
+
+// "#rrggbb", accepting the "#rgb" shorthand and either case
+pub fn normalize_hex(hex: &str) -> Result<String, String> {
+    let digits = hex.trim().strip_prefix('#').unwrap_or("");
+    let valid = matches!(digits.len(), 3 | 6) && digits.chars().all(|c| c.is_ascii_hexdigit());
+    if !valid {
+        return Err(format!("invalid color '{}', expected #rrggbb", hex));
+    }
+    let digits = digits.to_ascii_lowercase();
+    if digits.len() == 3 {
+        Ok(format!("#{}", digits.chars().flat_map(|c| [c, c]).collect::<String>()))
+    } else {
+        Ok(format!("#{}", digits))
+    }
+}
+
+// FNV-1a, so the color of a name never changes between builds or platforms
+fn name_hash(name: &str) -> u32 {
+    name.bytes()
+        .fold(0x811c_9dc5, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+}
+
+fn hsl_to_hex(hue: f64, saturation: f64, lightness: f64) -> String {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |value: f64| ((value + m) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+}
+
+// Color for a project without an explicit one, with the hue picked from its name
+pub fn derived_color(project: &str) -> String {
+    hsl_to_hex((name_hash(project) % 360) as f64, SATURATION, LIGHTNESS)
+}
+
+pub fn project_color(conn: &Connection, project: &str) -> Result<String, String> {
+    let explicit: Option<String> = conn
+        .query_row(
+            "SELECT color FROM project_colors WHERE project = ?1",
+            params![project],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    Ok(explicit.unwrap_or_else(|| derived_color(project)))
+}
+
+// Store an explicit color, or with `None` go back to the derived one.
+// Returns the color the project now has.
+pub fn set_project_color(conn: &Connection, project: &str, hex: Option<&str>) -> Result<String, String> {
+    match hex {
+        Some(hex) => {
+            let color = normalize_hex(hex)?;
+            conn.execute(
+                "INSERT OR REPLACE INTO project_colors (project, color) VALUES (?1, ?2)",
+                params![project, color],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(color)
+        }
+        None => {
+            conn.execute("DELETE FROM project_colors WHERE project = ?1", params![project])
+                .map_err(|e| e.to_string())?;
+            Ok(derived_color(project))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn explicit_colors_override_the_derived_one() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let derived = project_color(&conn, "Acme").unwrap();
+        assert_eq!(derived, derived_color("Acme"));
+        assert_ne!(derived, derived_color("Other"));
+        assert_eq!(normalize_hex(&derived).unwrap(), derived);
+
+        assert_eq!(set_project_color(&conn, "Acme", Some("#0AF")).unwrap(), "#00aaff");
+        assert_eq!(project_color(&conn, "Acme").unwrap(), "#00aaff");
+        assert!(set_project_color(&conn, "Acme", Some("blue")).is_err());
+        assert!(set_project_color(&conn, "Acme", Some("#12345g")).is_err());
+        assert_eq!(set_project_color(&conn, "Acme", None).unwrap(), derived);
+    }
+
+    #[test]
+    fn hsl_conversion_hits_the_primaries() {
+        assert_eq!(hsl_to_hex(0.0, 1.0, 0.5), "#ff0000");
+        assert_eq!(hsl_to_hex(120.0, 1.0, 0.5), "#00ff00");
+        assert_eq!(hsl_to_hex(240.0, 1.0, 0.5), "#0000ff");
+    }
+}
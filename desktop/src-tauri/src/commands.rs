@@ -0,0 +1,1827 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::archive;
+use crate::billing::{self, InvoiceData, InvoiceGroup};
+use crate::budget::{self, Burndown};
+use crate::categorize::{self, Rule, Suggestion};
+use crate::colors;
+use crate::daynotes;
+use crate::db::{self, CompactReport, Database, StorageInfo};
+use crate::entries::{self, Entry, EntryFilter, EntrySort, Page};
+use crate::events::{self, EntryChange};
+use crate::export::{self, card::CardProject};
+use crate::gaps::{self, Gap, OutsideHours, OutsideHoursAction, WorkingHours};
+use crate::i18n::{self, I18n};
+use crate::import::{self, mapped::ColumnMapping, ImportKind, ImportPreview, ImportSummary};
+use crate::logging;
+use crate::notify::{self, QuietHours};
+use crate::presets::{self, Preset};
+use crate::profiles::{self, Profile};
+use crate::progress;
+use crate::projects::{self, DuplicateGroup, ProjectInfo};
+use crate::recovery::{self, Orphan, RecoverAction, Recovery, TickWriter};
+use crate::recurrence::{self, Recurrence};
+use crate::repair::{self, RepairReport};
+use crate::reset::{self, ResetToken};
+use crate::session::{self, LockedSpan};
+use crate::settings;
+use crate::shortcuts;
+use crate::stats::{self, ActivityHeatmap, DayTimeline, RangeComparison, SessionInfo, Stats};
+use crate::tags;
+use crate::timefmt;
+use crate::transfer::{self, Reporter, Transfer};
+use crate::timer::{
+    self, DurationDisplay, DurationFormat, RunningTimerInfo, StopOutcome, Timer, TimerId, TimerState, TimerStatus,
+};
+use crate::tray;
+use crate::undo::{UndoOp, UndoStack, Undone};
+use crate::webhook::{self, WebhookResult};
+use crate::widget::{self, WidgetState};
+
+// Lock order for commands touching both: timer first, then database
+
+// Id of a timer's open entry, for change events
+fn open_entry(timers: &TimerState, id: TimerId) -> Vec<i64> {
+    timers.get(id).and_then(|timer| timer.entry_id).into_iter().collect()
+}
+
+#[tauri::command]
+pub fn split_entry(
+    app: AppHandle,
+    db: State<'_, Database>,
+    id: i64,
+    at_ts: i64,
+) -> Result<(Entry, Entry), String> {
+    let (first, second) = entries::split_entry(&mut db.lock()?, id, at_ts)?;
+    events::emit_entries_changed(&app, EntryChange::Split, vec![first.id, second.id]);
+    Ok((first, second))
+}
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+
+// Newest first unless `sort` is `start_asc`
+#[tauri::command]
+pub fn list_entries(
+    db: State<'_, Database>,
+    filter: Option<EntryFilter>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+    sort: Option<String>,
+) -> Result<Page<Entry>, String> {
+    let sort = match sort {
+        Some(sort) => EntrySort::parse(&sort)?,
+        None => EntrySort::StartDesc,
+    };
+    entries::list_entries(
+        &*db.lock()?,
+        &filter.unwrap_or_default(),
+        sort,
+        limit.unwrap_or(DEFAULT_PAGE_SIZE),
+        offset.unwrap_or(0),
+    )
+}
+
+// Distinct projects with usage counts, most recently used first, for autocomplete
+#[tauri::command]
+pub fn list_projects(db: State<'_, Database>) -> Result<Vec<ProjectInfo>, String> {
+    projects::list_projects(&*db.lock()?)
+}
+
+// Rename a project everywhere, returning the number of entries updated.
+// `merge` allows renaming onto an existing project to combine the two.
+#[tauri::command]
+pub fn rename_project(
+    app: AppHandle,
+    db: State<'_, Database>,
+    timer: State<'_, Timer>,
+    old: String,
+    new: String,
+    merge: Option<bool>,
+) -> Result<usize, String> {
+    let mut timers = timer.lock()?;
+    let ids = projects::rename_project(&mut db.lock()?, &old, &new, merge.unwrap_or(false))?;
+    timers.rename_project(&old, new.trim());
+    let count = ids.len();
+    events::emit_entries_changed(&app, EntryChange::Update, ids);
+    tray::refresh_menu(&app)?;
+    Ok(count)
+}
+
+#[tauri::command]
+pub fn find_duplicate_projects(db: State<'_, Database>) -> Result<Vec<DuplicateGroup>, String> {
+    projects::find_duplicates(&*db.lock()?)
+}
+
+// Merge `others` into `canonical`, entries and settings alike, returning the
+// number of entries moved
+#[tauri::command]
+pub fn merge_project_group(
+    app: AppHandle,
+    db: State<'_, Database>,
+    timer: State<'_, Timer>,
+    canonical: String,
+    others: Vec<String>,
+) -> Result<usize, String> {
+    let mut timers = timer.lock()?;
+    let ids = projects::merge_group(&mut db.lock()?, &canonical, &others)?;
+    for other in &others {
+        timers.rename_project(other, &canonical);
+    }
+    let count = ids.len();
+    events::emit_entries_changed(&app, EntryChange::Update, ids);
+    tray::refresh_menu(&app)?;
+    Ok(count)
+}
+
+// Remove a project's entries and their tags (`entries_only`), its presets,
+// recurrences, color, rate and budget (`settings_only`) or both (`all`),
+// returning how many rows went. Reversible with `undo`.
+#[tauri::command]
+pub fn delete_project(
+    app: AppHandle,
+    db: State<'_, Database>,
+    undo: State<'_, UndoStack>,
+    name: String,
+    mode: String,
+) -> Result<usize, String> {
+    let mode = projects::DeleteMode::parse(&mode)?;
+    let deleted = projects::delete_project(&mut db.lock()?, &name, mode)?;
+    let count = deleted.count();
+    if count == 0 {
+        return Ok(0);
+    }
+    let ids = deleted.entries.iter().map(|entry| entry.id).collect();
+    let had_presets = !deleted.presets.is_empty();
+    undo.push(
+        format!("delete project {}", name),
+        vec![
+            UndoOp::RestoreEntries(deleted.entries),
+            UndoOp::RestoreTags(deleted.tags),
+            UndoOp::RestorePresets(deleted.presets),
+            UndoOp::RestoreRecurrences(deleted.recurrences, deleted.runs),
+            UndoOp::RestoreProjectSettings(deleted.settings),
+        ],
+    )?;
+    events::emit_entries_changed(&app, EntryChange::Delete, ids);
+    if had_presets {
+        tray::refresh_menu(&app)?;
+    }
+    Ok(count)
+}
+
+// Delete all finished entries starting in [from_ts, to_ts), optionally for one
+// project. Reversible with `undo`.
+#[tauri::command]
+pub fn delete_entries(
+    app: AppHandle,
+    db: State<'_, Database>,
+    undo: State<'_, UndoStack>,
+    from_ts: i64,
+    to_ts: i64,
+    project: Option<String>,
+) -> Result<usize, String> {
+    let filter = EntryFilter {
+        project,
+        from_ts: Some(from_ts),
+        to_ts: Some(to_ts),
+    };
+    let deleted = entries::delete_entries(&mut db.lock()?, &filter)?;
+    let count = deleted.len();
+    if count > 0 {
+        let ids = deleted.iter().map(|entry| entry.id).collect();
+        undo.push(format!("delete {} entries", count), vec![UndoOp::RestoreEntries(deleted)])?;
+        events::emit_entries_changed(&app, EntryChange::Delete, ids);
+    }
+    Ok(count)
+}
+
+// Move finished entries starting in [from_ts, to_ts), optionally for one
+// project, by `delta_seconds`. Rejected if any would overlap another entry.
+// Reversible with `undo`.
+#[tauri::command]
+pub fn shift_entries(
+    app: AppHandle,
+    db: State<'_, Database>,
+    undo: State<'_, UndoStack>,
+    from_ts: i64,
+    to_ts: i64,
+    project: Option<String>,
+    delta_seconds: i64,
+) -> Result<usize, String> {
+    let before = entries::shift_entries(
+        &mut db.lock()?,
+        from_ts,
+        to_ts,
+        project.as_deref(),
+        delta_seconds,
+        db::now_ts(),
+    )?;
+    let count = before.len();
+    if count > 0 {
+        let ids = before.iter().map(|entry| entry.id).collect();
+        undo.push(format!("shift {} entries", count), vec![UndoOp::RestoreEntries(before)])?;
+        events::emit_entries_changed(&app, EntryChange::Update, ids);
+    }
+    Ok(count)
+}
+
+// Fix reversed and dangling entries; the report lists every change and the
+// whole repair can be reversed with `undo`
+#[tauri::command]
+pub fn repair_entries(
+    app: AppHandle,
+    db: State<'_, Database>,
+    timer: State<'_, Timer>,
+    undo: State<'_, UndoStack>,
+) -> Result<RepairReport, String> {
+    // Held for the whole repair so a timer can't start and be closed by it
+    let timers = timer.lock()?;
+    let report = repair::repair_entries(&mut db.lock()?, &timers.open_entries(), db::now_ts())?;
+    if !report.changes.is_empty() {
+        let before: Vec<Entry> = report.changes.iter().map(|c| c.before.clone()).collect();
+        let ids = before.iter().map(|entry| entry.id).collect();
+        undo.push(
+            format!("repair {} entries", before.len()),
+            vec![UndoOp::RestoreEntries(before)],
+        )?;
+        events::emit_entries_changed(&app, EntryChange::Update, ids);
+    }
+    Ok(report)
+}
+
+// Reverse the most recent bulk operation; None when there is nothing to undo
+#[tauri::command]
+pub fn undo(
+    app: AppHandle,
+    db: State<'_, Database>,
+    undo: State<'_, UndoStack>,
+) -> Result<Option<Undone>, String> {
+    let undone = undo.undo(&mut db.lock()?)?;
+    if let Some(undone) = &undone {
+        events::emit_entries_changed(&app, EntryChange::Update, undone.entry_ids.clone());
+        // Restored presets reappear in the tray submenu
+        tray::refresh_menu(&app)?;
+    }
+    Ok(undone)
+}
+
+// Label of the operation `undo` would reverse, for an "Undo …" button
+#[tauri::command]
+pub fn peek_undo(undo: State<'_, UndoStack>) -> Result<Option<String>, String> {
+    undo.peek()
+}
+
+// Start a timer and refresh the tray; shared by commands and tray/menu handlers.
+// Other running timers keep running.
+pub fn start_timer_with(
+    app: &AppHandle,
+    project: String,
+    note: String,
+    target_minutes: Option<u32>,
+) -> Result<TimerId, String> {
+    let timer_state = app.state::<Timer>();
+    let db = app.state::<Database>();
+    let mut timers = timer_state.lock()?;
+    let conn = db.lock()?;
+    let now = db::now_ts();
+    let tz = timefmt::configured_zone(&conn)?;
+    let outside = gaps::outside_hours(&conn, &tz, now)?;
+    if let Some(hours) = &outside {
+        if gaps::block_outside_hours(&conn)? {
+            return Err(format!(
+                "timers can only be started between {} and {}",
+                hours.start, hours.end
+            ));
+        }
+    }
+    let note = if note.trim().is_empty() {
+        let template = timer::note_template(&conn)?;
+        timer::render_note(&template, &project, &timefmt::to_local(now, &tz))
+    } else {
+        note
+    };
+    let id = timer::start(&conn, &mut timers, project.clone(), note, target_minutes, now)?;
+    timer_state.set_last_discarded(false);
+    events::emit_entries_changed(app, EntryChange::Insert, open_entry(&timers, id));
+    tray::show_status(app, &timer::status(&timers, None, now))?;
+    if let Some(hours) = outside {
+        let _ = app.emit(
+            gaps::OUTSIDE_HOURS_EVENT,
+            OutsideHours {
+                project,
+                hours,
+                action: OutsideHoursAction::Warned,
+            },
+        );
+    }
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn start_timer(
+    app: AppHandle,
+    project: String,
+    note: Option<String>,
+) -> Result<TimerId, String> {
+    start_timer_with(&app, project, note.unwrap_or_default(), None)
+}
+
+// Projects for the widget's up/down cycling, most recently started first
+#[tauri::command]
+pub fn get_recent_projects(db: State<'_, Database>, limit: usize) -> Result<Vec<String>, String> {
+    timer::recent_projects(&*db.lock()?, limit)
+}
+
+// "Resume" shortcut action: start a timer on the most recently started project
+#[tauri::command]
+pub fn start_last_project(app: AppHandle) -> Result<TimerId, String> {
+    let project = timer::recent_projects(&*app.state::<Database>().lock()?, 1)?
+        .pop()
+        .ok_or_else(|| "no project has been tracked yet".to_string())?;
+    start_timer_with(&app, project, String::new(), None)
+}
+
+// Timer commands act on the primary timer unless given an id
+#[tauri::command]
+pub fn pause_timer(
+    app: AppHandle,
+    db: State<'_, Database>,
+    timer: State<'_, Timer>,
+    id: Option<TimerId>,
+) -> Result<TimerStatus, String> {
+    let mut timers = timer.lock()?;
+    let conn = db.lock()?;
+    let now = db::now_ts();
+    let id = timers.resolve(id)?;
+    let closed = open_entry(&timers, id);
+    timer::pause(&conn, &mut timers, id, now)?;
+    app.state::<TickWriter>().persist_now(now);
+    events::emit_entries_changed(&app, EntryChange::Update, closed);
+    tray::show_status(&app, &timer::status(&timers, None, now))?;
+    Ok(timer::status(&timers, Some(id), now))
+}
+
+#[tauri::command]
+pub fn resume_timer(
+    app: AppHandle,
+    db: State<'_, Database>,
+    timer: State<'_, Timer>,
+    id: Option<TimerId>,
+) -> Result<TimerStatus, String> {
+    let mut timers = timer.lock()?;
+    let conn = db.lock()?;
+    let now = db::now_ts();
+    let id = timers.resolve(id)?;
+    timer::resume(&conn, &mut timers, id, now)?;
+    events::emit_entries_changed(&app, EntryChange::Insert, open_entry(&timers, id));
+    tray::show_status(&app, &timer::status(&timers, None, now))?;
+    Ok(timer::status(&timers, Some(id), now))
+}
+
+#[tauri::command]
+pub fn stop_timer(
+    app: AppHandle,
+    db: State<'_, Database>,
+    timer_state: State<'_, Timer>,
+    id: Option<TimerId>,
+) -> Result<Option<Entry>, String> {
+    let mut timers = timer_state.lock()?;
+    let conn = db.lock()?;
+    let min_seconds = timer::min_entry_seconds(&conn)?;
+    let id = timers.resolve(id)?;
+    let open = open_entry(&timers, id);
+    let now = db::now_ts();
+    let outcome = timer::stop(&conn, &mut timers, id, min_seconds, now)?;
+    timer_state.set_last_discarded(outcome == StopOutcome::Discarded);
+    app.state::<TickWriter>().persist_now(now);
+    if let Err(e) = progress::report(&app, &conn, now) {
+        log::warn!("failed to report daily progress: {}", e);
+    }
+    let remaining = timer::status(&timers, None, now);
+    match outcome {
+        StopOutcome::Saved(entry) => {
+            events::emit_entries_changed(&app, EntryChange::Update, vec![entry.id]);
+            tray::show_status(&app, &remaining)?;
+            Ok(Some(entry))
+        }
+        StopOutcome::Discarded => {
+            events::emit_entries_changed(&app, EntryChange::Delete, open);
+            if remaining.running {
+                tray::show_status(&app, &remaining)?;
+            } else {
+                tray::show_discarded(&app);
+            }
+            Ok(None)
+        }
+        StopOutcome::Paused => {
+            tray::show_status(&app, &remaining)?;
+            Ok(None)
+        }
+    }
+}
+
+// Status of the primary timer, with a count and total of all running timers
+#[tauri::command]
+pub fn get_timer_status(timer_state: State<'_, Timer>) -> Result<TimerStatus, String> {
+    let timers = timer_state.lock()?;
+    let mut status = timer::status(&timers, None, db::now_ts());
+    status.last_entry_discarded = timer_state.last_discarded();
+    Ok(status)
+}
+
+#[tauri::command]
+pub fn list_running_timers(timer: State<'_, Timer>) -> Result<Vec<RunningTimerInfo>, String> {
+    Ok(timer::list_running(&*timer.lock()?, db::now_ts()))
+}
+
+// 0 turns daily progress reporting off
+#[tauri::command]
+pub fn set_daily_target(db: State<'_, Database>, seconds: u64) -> Result<(), String> {
+    settings::set(&*db.lock()?, progress::DAILY_TARGET_SETTING, &seconds)
+}
+
+// Supports {date}, {time}, {project} and {weekday}; empty disables it
+#[tauri::command]
+pub fn set_note_template(db: State<'_, Database>, template: String) -> Result<(), String> {
+    settings::set(&*db.lock()?, timer::NOTE_TEMPLATE_SETTING, &template)
+}
+
+#[tauri::command]
+pub fn set_min_entry_seconds(db: State<'_, Database>, seconds: u32) -> Result<(), String> {
+    settings::set(&*db.lock()?, timer::MIN_ENTRY_SETTING, &seconds)
+}
+
+// Timers running longer than this are stopped automatically; `None` disables it
+#[tauri::command]
+pub fn set_max_entry_seconds(db: State<'_, Database>, seconds: Option<u64>) -> Result<(), String> {
+    timer::set_max_entry_seconds(&*db.lock()?, seconds)
+}
+
+#[tauri::command]
+pub fn switch_project(
+    app: AppHandle,
+    db: State<'_, Database>,
+    timer: State<'_, Timer>,
+    new_project: String,
+    id: Option<TimerId>,
+) -> Result<(), String> {
+    let mut timers = timer.lock()?;
+    let mut conn = db.lock()?;
+    let now = db::now_ts();
+    let id = timers.resolve(id)?;
+    let closed = open_entry(&timers, id);
+    timer::switch_project(&mut conn, &mut timers, id, new_project, now)?;
+    events::emit_entries_changed(&app, EntryChange::Update, closed);
+    events::emit_entries_changed(&app, EntryChange::Insert, open_entry(&timers, id));
+    tray::show_status(&app, &timer::status(&timers, None, now))
+}
+
+#[tauri::command]
+pub fn set_widget_autohide_ms(
+    db: State<'_, Database>,
+    widget: State<'_, WidgetState>,
+    ms: u64,
+) -> Result<(), String> {
+    settings::set(&*db.lock()?, widget::AUTOHIDE_SETTING, &ms)?;
+    widget.set_autohide_ms(ms);
+    Ok(())
+}
+
+// Resize the widget within fixed bounds; the size is kept across restarts
+#[tauri::command]
+pub fn set_widget_size(app: AppHandle, width: f64, height: f64) -> Result<(), String> {
+    widget::set_size(&app, widget::WidgetSize::new(width, height)?)
+}
+
+// Open the widget without stealing focus (e.g. from a fullscreen app)
+#[cfg(desktop)]
+#[tauri::command]
+pub fn set_widget_nonactivating(app: AppHandle, db: State<'_, Database>, enabled: bool) -> Result<(), String> {
+    settings::set(&*db.lock()?, widget::NONACTIVATING_SETTING, &enabled)?;
+    widget::set_nonactivating(&app, enabled)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn set_widget_nonactivating(_enabled: bool) -> Result<(), String> {
+    Ok(())
+}
+
+// Place the widget under the tray icon again, e.g. after a layout change
+#[cfg(desktop)]
+#[tauri::command]
+pub fn reposition_widget(app: AppHandle) -> Result<(), String> {
+    widget::position_widget_window(&app).map_err(|e| e.to_string())
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn reposition_widget() -> Result<(), String> {
+    Ok(())
+}
+
+// Visibility, geometry and tray state of the widget for diagnosing why it
+// didn't show or update
+#[cfg(desktop)]
+#[tauri::command]
+pub fn get_widget_diagnostics(app: AppHandle) -> Result<widget::WidgetDiag, String> {
+    Ok(widget::diagnostics(&app))
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn get_widget_diagnostics() -> Result<widget::WidgetDiag, String> {
+    Err("the widget is not supported on this platform".to_string())
+}
+
+// Drop the dragged offset so the widget sits under the tray icon again
+#[cfg(desktop)]
+#[tauri::command]
+pub fn reset_widget_position(app: AppHandle) -> Result<(), String> {
+    widget::reset_position(&app)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn reset_widget_position() -> Result<(), String> {
+    Ok(())
+}
+
+#[tauri::command]
+pub fn save_preset(
+    app: AppHandle,
+    db: State<'_, Database>,
+    name: String,
+    project: String,
+    default_note: Option<String>,
+    target_minutes: Option<u32>,
+) -> Result<(), String> {
+    let preset = Preset {
+        name,
+        project,
+        default_note: default_note.unwrap_or_default(),
+        target_minutes,
+    };
+    presets::save_preset(&*db.lock()?, &preset)?;
+    tray::refresh_menu(&app)
+}
+
+#[tauri::command]
+pub fn list_presets(db: State<'_, Database>) -> Result<Vec<Preset>, String> {
+    presets::list_presets(&*db.lock()?)
+}
+
+#[tauri::command]
+pub fn delete_preset(app: AppHandle, db: State<'_, Database>, name: String) -> Result<(), String> {
+    presets::delete_preset(&*db.lock()?, &name)?;
+    tray::refresh_menu(&app)
+}
+
+// Start a timer pre-filled from a preset; also used by the tray submenu
+pub fn start_preset_by_name(app: &AppHandle, name: &str) -> Result<TimerId, String> {
+    let preset = presets::get_preset(&*app.state::<Database>().lock()?, name)?;
+    start_timer_with(app, preset.project, preset.default_note, preset.target_minutes)
+}
+
+#[tauri::command]
+pub fn start_preset(app: AppHandle, name: String) -> Result<TimerId, String> {
+    start_preset_by_name(&app, &name)
+}
+
+#[tauri::command]
+pub fn add_recurring(
+    app: AppHandle,
+    db: State<'_, Database>,
+    project: String,
+    note: Option<String>,
+    weekday: String,
+    start_hhmm: String,
+    duration_min: u32,
+) -> Result<Recurrence, String> {
+    let mut conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    let recurrence = recurrence::add_recurring(
+        &conn,
+        &project,
+        note.as_deref().unwrap_or(""),
+        &weekday,
+        &start_hhmm,
+        duration_min,
+        db::now_ts(),
+    )?;
+    let created = recurrence::materialize(&mut conn, &tz, db::now_ts())?;
+    events::emit_entries_changed(&app, EntryChange::Insert, created);
+    Ok(recurrence)
+}
+
+#[tauri::command]
+pub fn list_recurrences(db: State<'_, Database>) -> Result<Vec<Recurrence>, String> {
+    recurrence::list_recurrences(&*db.lock()?)
+}
+
+#[tauri::command]
+pub fn delete_recurrence(db: State<'_, Database>, id: i64) -> Result<(), String> {
+    recurrence::delete_recurrence(&mut db.lock()?, id)
+}
+
+#[tauri::command]
+pub fn unlock(app: AppHandle, db: State<'_, Database>, passphrase: String) -> Result<(), String> {
+    db.unlock(&passphrase)?;
+    app.state::<DurationDisplay>().reload(&*db.lock()?);
+    #[cfg(desktop)]
+    if crate::menubar::is_enabled(&app) {
+        crate::menubar::apply(&app, true)?;
+    }
+    recovery::check(&app)?;
+    tray::refresh_menu(&app)
+}
+
+// An empty passphrase removes encryption
+#[tauri::command]
+pub fn set_encryption_passphrase(db: State<'_, Database>, passphrase: String) -> Result<(), String> {
+    db.set_passphrase(&passphrase)
+}
+
+#[tauri::command]
+pub fn is_database_locked(db: State<'_, Database>) -> Result<bool, String> {
+    db.is_locked()
+}
+
+#[tauri::command]
+pub fn compact_database(db: State<'_, Database>) -> Result<CompactReport, String> {
+    db.compact()
+}
+
+// Emit `database-changed` when another program (e.g. a sync service)
+// changes the database file
+#[cfg(desktop)]
+#[tauri::command]
+pub fn set_watch_database(app: AppHandle, enabled: bool) -> Result<(), String> {
+    crate::dbwatch::set_watching(&app, enabled)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn set_watch_database(_enabled: bool) -> Result<(), String> {
+    Err("watching the database is not supported on this platform".to_string())
+}
+
+// Pause running timers while the screen is locked and resume them on unlock
+#[tauri::command]
+pub fn set_pause_on_lock(db: State<'_, Database>, enabled: bool) -> Result<(), String> {
+    session::set_pause_on_lock(&*db.lock()?, enabled)
+}
+
+// Spans the screen was locked, recorded while pausing on lock is on
+#[tauri::command]
+pub fn get_locked_spans(db: State<'_, Database>, from_ts: i64, to_ts: i64) -> Result<Vec<LockedSpan>, String> {
+    session::locked_spans(&*db.lock()?, from_ts, to_ts)
+}
+
+// First step of `reset_all_data`; the token is valid for one attempt within a minute
+#[tauri::command]
+pub fn request_reset_token(tokens: State<'_, ResetToken>) -> Result<String, String> {
+    tokens.issue()
+}
+
+// Delete all entries, settings and in-memory state, leaving a freshly
+// migrated database. Requires a token from `request_reset_token`.
+#[tauri::command]
+pub fn reset_all_data(
+    app: AppHandle,
+    db: State<'_, Database>,
+    timer_state: State<'_, Timer>,
+    undo: State<'_, UndoStack>,
+    tokens: State<'_, ResetToken>,
+    confirm_token: String,
+) -> Result<(), String> {
+    tokens.redeem(&confirm_token)?;
+    {
+        let mut timers = timer_state.lock()?;
+        reset::wipe(&mut db.lock()?)?;
+        timers.clear();
+        undo.clear()?;
+    }
+    log::warn!("all data was reset");
+    app.state::<Recovery>().clear();
+    // Every preference is back to its default: locale, widget, tick
+    // interval, tray emoji and the rest
+    profiles::apply_preferences(&app)?;
+    #[cfg(desktop)]
+    if let Err(e) = widget::reset_position(&app) {
+        log::warn!("failed to reset the widget position: {}", e);
+    }
+    tray::set_title(&app, "", "")?;
+    let _ = app.emit(reset::DATA_RESET_EVENT, ());
+    Ok(())
+}
+
+// Longest single entry, or run of same-project entries less than
+// `merge_gaps_under` seconds apart, starting in the range
+#[tauri::command]
+pub fn get_longest_session(
+    db: State<'_, Database>,
+    from_ts: i64,
+    to_ts: i64,
+    merge_gaps_under: Option<u64>,
+) -> Result<Option<SessionInfo>, String> {
+    stats::longest_session(&*db.lock()?, from_ts, to_ts, merge_gaps_under, db::now_ts())
+}
+
+// Per-project totals of range a against range b, with deltas and percent change
+#[tauri::command]
+pub fn compare_ranges(
+    db: State<'_, Database>,
+    a_from: i64,
+    a_to: i64,
+    b_from: i64,
+    b_to: i64,
+) -> Result<RangeComparison, String> {
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    stats::compare_ranges(&conn, &tz, (a_from, a_to), (b_from, b_to), db::now_ts())
+}
+
+#[tauri::command]
+pub fn get_stats(
+    db: State<'_, Database>,
+    from_ts: i64,
+    to_ts: i64,
+    group_by: String,
+) -> Result<Stats, String> {
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    stats::get_stats(&conn, &tz, from_ts, to_ts, &group_by, db::now_ts())
+}
+
+// Per-day totals for a contributions-style grid, with the current streak
+#[tauri::command]
+pub fn get_activity_heatmap(
+    db: State<'_, Database>,
+    from_ts: i64,
+    to_ts: i64,
+) -> Result<ActivityHeatmap, String> {
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    stats::activity_heatmap(&conn, &tz, from_ts, to_ts, db::now_ts())
+}
+
+// Tracked seconds per local hour of day, 0 to 23
+#[tauri::command]
+pub fn get_hourly_distribution(db: State<'_, Database>, from_ts: i64, to_ts: i64) -> Result<[u64; 24], String> {
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    stats::hourly_distribution(&conn, &tz, from_ts, to_ts, db::now_ts())
+}
+
+// Entries of the local day containing `date_ts`, clipped to that day
+#[tauri::command]
+pub fn get_timeline(db: State<'_, Database>, date_ts: i64) -> Result<DayTimeline, String> {
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    stats::day_timeline(&conn, &tz, date_ts, db::now_ts())
+}
+
+// Untracked spans longer than `min_gap_seconds`, limited to the working
+// hours when they are set
+#[tauri::command]
+pub fn find_gaps(db: State<'_, Database>, from_ts: i64, to_ts: i64, min_gap_seconds: u64) -> Result<Vec<Gap>, String> {
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    let hours = gaps::working_hours(&conn)?;
+    gaps::find_gaps(&conn, &tz, from_ts, to_ts, min_gap_seconds, hours.as_ref(), db::now_ts())
+}
+
+#[tauri::command]
+pub fn set_working_hours(db: State<'_, Database>, start_hhmm: String, end_hhmm: String) -> Result<WorkingHours, String> {
+    let hours = WorkingHours::new(&start_hhmm, &end_hhmm)?;
+    gaps::set_working_hours(&*db.lock()?, Some(&hours))?;
+    Ok(hours)
+}
+
+#[tauri::command]
+pub fn clear_working_hours(db: State<'_, Database>) -> Result<(), String> {
+    gaps::set_working_hours(&*db.lock()?, None)
+}
+
+// Suppress TimeGrid's OS notifications and chime between the two local
+// times, wrapping past midnight when end is before start. Events still reach the UI.
+#[tauri::command]
+pub fn set_quiet_hours(
+    db: State<'_, Database>,
+    start_hhmm: String,
+    end_hhmm: String,
+    enabled: bool,
+) -> Result<QuietHours, String> {
+    let hours = QuietHours::new(&start_hhmm, &end_hhmm, enabled)?;
+    notify::set_quiet_hours(&*db.lock()?, &hours)?;
+    Ok(hours)
+}
+
+// Strict mode: refuse to start timers outside the working hours and stop
+// running ones when the hours end. Off by default, which only warns.
+#[tauri::command]
+pub fn set_block_outside_hours(db: State<'_, Database>, block: bool) -> Result<(), String> {
+    gaps::set_block_outside_hours(&*db.lock()?, block)
+}
+
+// Opt in to sampling the active window for project suggestions; opting out
+// deletes the samples
+#[tauri::command]
+pub fn set_activity_sampling(db: State<'_, Database>, enabled: bool) -> Result<(), String> {
+    categorize::set_sampling(&*db.lock()?, enabled)
+}
+
+#[tauri::command]
+pub fn add_categorization_rule(db: State<'_, Database>, pattern: String, project: String) -> Result<Rule, String> {
+    categorize::add_rule(&*db.lock()?, &pattern, &project)
+}
+
+#[tauri::command]
+pub fn list_categorization_rules(db: State<'_, Database>) -> Result<Vec<Rule>, String> {
+    categorize::list_rules(&*db.lock()?)
+}
+
+#[tauri::command]
+pub fn remove_categorization_rule(db: State<'_, Database>, id: i64) -> Result<(), String> {
+    categorize::remove_rule(&*db.lock()?, id)
+}
+
+// Suggestions only; nothing is tracked until one is passed to `confirm_suggestion`
+#[tauri::command]
+pub fn suggest_project(db: State<'_, Database>, from_ts: i64, to_ts: i64) -> Result<Vec<Suggestion>, String> {
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    categorize::suggest(&conn, &tz, from_ts, to_ts, db::now_ts())
+}
+
+#[tauri::command]
+pub fn confirm_suggestion(
+    app: AppHandle,
+    db: State<'_, Database>,
+    project: String,
+    start_ts: i64,
+    end_ts: i64,
+) -> Result<Entry, String> {
+    let project = project.trim();
+    if project.is_empty() {
+        return Err("project name cannot be empty".to_string());
+    }
+    if start_ts >= end_ts {
+        return Err(format!("empty time range {}..{}", start_ts, end_ts));
+    }
+    let entry = entries::insert_entry(&*db.lock()?, project, "", start_ts, Some(end_ts))?;
+    events::emit_entries_changed(&app, EntryChange::Insert, vec![entry.id]);
+    Ok(entry)
+}
+
+// Replace an entry's tags; returns them normalized
+#[tauri::command]
+pub fn set_entry_tags(
+    app: AppHandle,
+    db: State<'_, Database>,
+    id: i64,
+    tags: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let tags = tags::set_entry_tags(&mut db.lock()?, id, &tags)?;
+    events::emit_entries_changed(&app, EntryChange::Update, vec![id]);
+    Ok(tags)
+}
+
+// Add and remove tags on all entries starting in [from_ts, to_ts), optionally
+// for one project. Returns how many entries changed; reversible with `undo`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn tag_entries(
+    app: AppHandle,
+    db: State<'_, Database>,
+    undo: State<'_, UndoStack>,
+    from_ts: i64,
+    to_ts: i64,
+    project: Option<String>,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<usize, String> {
+    let changed = tags::tag_entries(&mut db.lock()?, from_ts, to_ts, project, &add, &remove)?;
+    let count = changed.len();
+    if count > 0 {
+        let ids = changed.iter().map(|(id, _)| *id).collect();
+        undo.push(format!("tag {} entries", count), vec![UndoOp::RestoreTags(changed)])?;
+        events::emit_entries_changed(&app, EntryChange::Update, ids);
+    }
+    Ok(count)
+}
+
+// Explicit color if one was set, else one derived from the name
+#[tauri::command]
+pub fn get_project_color(db: State<'_, Database>, project: String) -> Result<String, String> {
+    colors::project_color(&*db.lock()?, &project)
+}
+
+// `None` goes back to the derived color; returns the color now in effect
+#[tauri::command]
+pub fn set_project_color(db: State<'_, Database>, project: String, hex: Option<String>) -> Result<String, String> {
+    colors::set_project_color(&*db.lock()?, &project, hex.as_deref())
+}
+
+// Hourly rate in cents; `None` removes it
+#[tauri::command]
+pub fn set_project_rate(db: State<'_, Database>, project: String, cents_per_hour: Option<i64>) -> Result<(), String> {
+    billing::set_rate(&*db.lock()?, &project, cents_per_hour)
+}
+
+// Weekly time budget in seconds; `None` removes it
+#[tauri::command]
+pub fn set_project_budget(db: State<'_, Database>, project: String, weekly_seconds: Option<u64>) -> Result<(), String> {
+    budget::set_budget(&*db.lock()?, &project, weekly_seconds)
+}
+
+// Cumulative tracked time against the ideal pace for each day of the week
+// from the local day containing `week_start_ts`
+#[tauri::command]
+pub fn get_budget_burndown(db: State<'_, Database>, project: String, week_start_ts: i64) -> Result<Burndown, String> {
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    budget::burndown(&conn, &tz, &project, week_start_ts, db::now_ts())
+}
+
+#[tauri::command]
+pub fn set_rounding_minutes(
+    db: State<'_, Database>,
+    display: State<'_, DurationDisplay>,
+    minutes: u32,
+) -> Result<(), String> {
+    billing::set_rounding_minutes(&*db.lock()?, minutes)?;
+    display.set_rounding(minutes);
+    Ok(())
+}
+
+// "clock" (HH:MM:SS) or "decimal" hours for the tray and widget
+#[tauri::command]
+pub fn set_duration_format(
+    app: AppHandle,
+    db: State<'_, Database>,
+    display: State<'_, DurationDisplay>,
+    timer_state: State<'_, Timer>,
+    format: String,
+) -> Result<(), String> {
+    let format = DurationFormat::parse(&format)?;
+    timer::set_duration_format(&*db.lock()?, format)?;
+    display.set_format(format);
+    tray::show_status(&app, &timer::status(&*timer_state.lock()?, None, db::now_ts()))
+}
+
+// Elapsed time rounded and formatted exactly like the tray title
+#[tauri::command]
+pub fn get_display_elapsed(display: State<'_, DurationDisplay>, seconds: u64) -> String {
+    display.format(seconds as i64)
+}
+
+// Billable amounts grouped by any of "project" and "tag"
+#[tauri::command]
+pub fn get_invoice(
+    db: State<'_, Database>,
+    from_ts: i64,
+    to_ts: i64,
+    group_by: Vec<String>,
+    billable_only: Option<bool>,
+) -> Result<InvoiceData, String> {
+    let group_by = group_by
+        .iter()
+        .map(|group| InvoiceGroup::parse(group))
+        .collect::<Result<Vec<_>, _>>()?;
+    billing::invoice(&*db.lock()?, from_ts, to_ts, &group_by, billable_only.unwrap_or(false))
+}
+
+// Accepts Monday..Sunday (or three-letter abbreviations)
+#[tauri::command]
+pub fn set_week_start(db: State<'_, Database>, day: String) -> Result<(), String> {
+    stats::set_week_start(&*db.lock()?, &day).map(|_| ())
+}
+
+// Override the OS locale and rebuild the menus in the new language
+#[tauri::command]
+pub fn set_locale(
+    app: AppHandle,
+    db: State<'_, Database>,
+    i18n: State<'_, I18n>,
+    code: String,
+) -> Result<(), String> {
+    i18n.set_locale(&code)?;
+    settings::set(&*db.lock()?, i18n::LOCALE_SETTING, &code)?;
+    #[cfg(desktop)]
+    crate::app_menu::rebuild(&app)?;
+    Ok(())
+}
+
+// Version, build and platform details for the About panel and bug reports
+#[tauri::command]
+pub fn get_app_info(app: AppHandle) -> Result<crate::about::AppInfo, String> {
+    Ok(crate::about::info(&app))
+}
+
+#[cfg(desktop)]
+#[tauri::command]
+pub async fn check_for_updates(app: AppHandle) -> Result<crate::updater::UpdateInfo, String> {
+    crate::updater::check(&app).await
+}
+
+#[cfg(desktop)]
+#[tauri::command]
+pub async fn install_update(app: AppHandle) -> Result<(), String> {
+    crate::updater::install(&app).await
+}
+
+// Mobile: updates are delivered through the app stores
+#[cfg(mobile)]
+#[tauri::command]
+pub async fn check_for_updates(_app: AppHandle) -> Result<(), String> {
+    Err("updates are not supported on this platform".to_string())
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub async fn install_update(_app: AppHandle) -> Result<(), String> {
+    Err("updates are not supported on this platform".to_string())
+}
+
+#[cfg(desktop)]
+#[tauri::command]
+pub fn set_check_updates_on_launch(db: State<'_, Database>, enabled: bool) -> Result<(), String> {
+    settings::set(&*db.lock()?, crate::updater::CHECK_ON_LAUNCH_SETTING, &enabled)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn set_check_updates_on_launch(_enabled: bool) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(desktop)]
+#[tauri::command]
+pub fn set_hourly_chime(db: State<'_, Database>, enabled: bool) -> Result<(), String> {
+    settings::set(&*db.lock()?, crate::chime::HOURLY_CHIME_SETTING, &enabled)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn set_hourly_chime(_enabled: bool) -> Result<(), String> {
+    Ok(())
+}
+
+// Nudge to start a timer after `minutes` of input activity with none running
+#[cfg(desktop)]
+#[tauri::command]
+pub fn set_autostart_prompt(db: State<'_, Database>, minutes: u32, enabled: bool) -> Result<(), String> {
+    if enabled && minutes == 0 {
+        return Err("minutes must be at least 1".to_string());
+    }
+    let conn = db.lock()?;
+    settings::set(&conn, crate::autostart::MINUTES_SETTING, &minutes)?;
+    settings::set(&conn, crate::autostart::ENABLED_SETTING, &enabled)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn set_autostart_prompt(_minutes: u32, _enabled: bool) -> Result<(), String> {
+    Ok(())
+}
+
+// Preview the chime from settings; plays regardless of timer or Do Not Disturb
+#[cfg(desktop)]
+#[tauri::command]
+pub async fn play_test_chime() -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(crate::chime::play)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub async fn play_test_chime() -> Result<(), String> {
+    Err("the hourly chime is not supported on this platform".to_string())
+}
+
+// `light` or `dark`, for the frontend to match on startup
+#[cfg(desktop)]
+#[tauri::command]
+pub fn get_system_theme(app: AppHandle) -> Result<String, String> {
+    let theme = crate::appearance::system_theme(&app)?;
+    Ok(crate::appearance::theme_name(theme).to_string())
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn get_system_theme(_app: AppHandle) -> Result<String, String> {
+    Err("system theme detection is not supported on this platform".to_string())
+}
+
+// Returns whether focus mode is now on
+#[cfg(desktop)]
+#[tauri::command]
+pub fn toggle_focus_mode(app: AppHandle) -> Result<bool, String> {
+    crate::focus::toggle(&app)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn toggle_focus_mode(_app: AppHandle) -> Result<bool, String> {
+    Err("focus mode is not supported on this platform".to_string())
+}
+
+// Also turn on macOS Do Not Disturb (through the user's Shortcuts) in focus mode
+#[cfg(desktop)]
+#[tauri::command]
+pub fn set_focus_mode_dnd(db: State<'_, Database>, enabled: bool) -> Result<(), String> {
+    settings::set(&*db.lock()?, crate::focus::DND_SETTING, &enabled)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn set_focus_mode_dnd(_enabled: bool) -> Result<(), String> {
+    Ok(())
+}
+
+// Check an accelerator before saving it: errors name invalid parts or the
+// feature already bound to it. `action` is the binding being edited, which
+// may keep its own accelerator.
+#[tauri::command]
+pub fn validate_accelerator(
+    db: State<'_, Database>,
+    accelerator: String,
+    action: Option<String>,
+) -> Result<(), String> {
+    shortcuts::validate(&*db.lock()?, &accelerator, action.as_deref()).map(|_| ())
+}
+
+// Remap a menu accelerator (settings, new_entry or toggle_timer) and rebuild the menu
+#[tauri::command]
+pub fn set_menu_shortcut(
+    app: AppHandle,
+    db: State<'_, Database>,
+    action: String,
+    accelerator: String,
+) -> Result<String, String> {
+    let canonical = shortcuts::set_menu_shortcut(&*db.lock()?, &action, &accelerator)?;
+    #[cfg(desktop)]
+    crate::app_menu::rebuild(&app)?;
+    Ok(canonical)
+}
+
+// One of widget, main_window, menu or toggle_timer
+#[tauri::command]
+pub fn set_tray_click_action(app: AppHandle, action: String) -> Result<(), String> {
+    tray::set_click_action(&app, tray::ClickAction::parse(&action)?)
+}
+
+// Same actions as a single click except menu, or "none" to turn double-clicks off
+#[tauri::command]
+pub fn set_tray_double_click_action(app: AppHandle, action: String) -> Result<(), String> {
+    let action = match action.as_str() {
+        "none" => None,
+        action => Some(tray::ClickAction::parse(action)?),
+    };
+    tray::set_double_click_action(&app, action)
+}
+
+// Idle tray title: "app_name", "today_total" or "last_project"
+#[tauri::command]
+pub fn set_idle_tray_mode(app: AppHandle, mode: String) -> Result<(), String> {
+    tray::set_idle_mode(&app, tray::IdleMode::parse(&mode)?)
+}
+
+// Ask before quitting while a timer runs instead of exiting right away
+#[cfg(desktop)]
+#[tauri::command]
+pub fn set_confirm_quit_while_running(db: State<'_, Database>, enabled: bool) -> Result<(), String> {
+    settings::set(&*db.lock()?, crate::quit::CONFIRM_QUIT_SETTING, &enabled)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn set_confirm_quit_while_running(_enabled: bool) -> Result<(), String> {
+    Ok(())
+}
+
+// Off: closing the main window quits the app instead of hiding it
+#[cfg(desktop)]
+#[tauri::command]
+pub fn set_close_to_tray(db: State<'_, Database>, enabled: bool) -> Result<(), String> {
+    settings::set(&*db.lock()?, crate::quit::CLOSE_TO_TRAY_SETTING, &enabled)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn set_close_to_tray(_enabled: bool) -> Result<(), String> {
+    Ok(())
+}
+
+// Make the tray and widget the whole UI: the main window stays hidden, its
+// menu actions go to the widget and macOS hides the Dock icon
+#[cfg(desktop)]
+#[tauri::command]
+pub fn set_menubar_only(app: AppHandle, enabled: bool) -> Result<(), String> {
+    crate::menubar::set_enabled(&app, enabled)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn set_menubar_only(_enabled: bool) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(desktop)]
+#[tauri::command]
+pub fn get_menubar_only(app: AppHandle) -> bool {
+    crate::menubar::is_enabled(&app)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn get_menubar_only() -> bool {
+    false
+}
+
+// Answer to a `confirm-quit` event; `save` stops and saves running timers first
+#[cfg(desktop)]
+#[tauri::command]
+pub fn confirm_quit(app: AppHandle, save: bool) -> Result<(), String> {
+    crate::quit::confirm(&app, save)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn confirm_quit(_save: bool) -> Result<(), String> {
+    Ok(())
+}
+
+// Cycle the tray title through custom summaries while no single timer
+// dominates; an empty list goes back to the standard title
+#[cfg(desktop)]
+#[tauri::command]
+pub fn set_tray_rotation(app: AppHandle, items: Vec<String>, interval_ms: u64) -> Result<(), String> {
+    crate::rotation::set(&app, items, interval_ms)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn set_tray_rotation(_items: Vec<String>, _interval_ms: u64) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(desktop)]
+#[tauri::command]
+pub fn clear_tray_rotation(app: AppHandle) -> Result<(), String> {
+    crate::rotation::clear(&app)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn clear_tray_rotation() -> Result<(), String> {
+    Ok(())
+}
+
+// False where the tray can't show text, so the widget should show elapsed time
+#[tauri::command]
+pub fn tray_supports_title(app: AppHandle) -> bool {
+    tray::supports_title(&app)
+}
+
+// Prefix of the running tray title; an empty string shows no emoji
+#[cfg(desktop)]
+#[tauri::command]
+pub fn set_tray_emoji(app: AppHandle, emoji: String) -> Result<(), String> {
+    tray::set_emoji(&app, &emoji)
+}
+
+#[cfg(mobile)]
+#[tauri::command]
+pub fn set_tray_emoji(emoji: String) -> Result<(), String> {
+    tray::validate_emoji(&emoji).map(|_| ())
+}
+
+// Folder holding the database and settings
+fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("could not resolve the data directory: {}", e))?;
+    if !dir.is_dir() {
+        return Err(format!("data directory {} does not exist", dir.display()));
+    }
+    Ok(dir)
+}
+
+#[tauri::command]
+pub fn get_data_dir(app: AppHandle) -> Result<String, String> {
+    Ok(data_dir(&app)?.to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+pub fn get_storage_info(app: AppHandle, db: State<'_, Database>) -> Result<StorageInfo, String> {
+    db.storage_info(&data_dir(&app)?)
+}
+
+// Reveal the data directory in Finder, Explorer or the xdg file manager
+#[tauri::command]
+pub fn open_data_dir(app: AppHandle) -> Result<(), String> {
+    let dir = data_dir(&app)?;
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| format!("could not open {}: {}", dir.display(), e))
+}
+
+// One of off, error, warn, info, debug or trace; applies immediately
+#[tauri::command]
+pub fn set_log_level(db: State<'_, Database>, level: String) -> Result<(), String> {
+    logging::set_level(&*db.lock()?, &level)
+}
+
+#[tauri::command]
+pub fn set_file_logging(db: State<'_, Database>, enabled: bool) -> Result<(), String> {
+    logging::set_file_logging(&*db.lock()?, enabled)
+}
+
+// Reveal the log file, for attaching to bug reports
+#[tauri::command]
+pub fn open_log_file(app: AppHandle) -> Result<(), String> {
+    let file = logging::log_file(&data_dir(&app)?);
+    if !file.is_file() {
+        return Err("no log file yet; turn on file logging first".to_string());
+    }
+    app.opener()
+        .reveal_item_in_dir(&file)
+        .map_err(|e| format!("could not reveal {}: {}", file.display(), e))
+}
+
+// `None` (or an empty URL) removes the webhook
+#[tauri::command]
+pub fn set_webhook(db: State<'_, Database>, url: Option<String>, secret: Option<String>) -> Result<(), String> {
+    webhook::set_config(&*db.lock()?, url.as_deref(), secret.as_deref())
+}
+
+// Send a signed `test` event to the configured webhook and report the status
+// and round-trip time; times out after a few seconds
+#[tauri::command]
+pub async fn test_webhook(db: State<'_, Database>) -> Result<WebhookResult, String> {
+    let config = webhook::config(&*db.lock()?)?.ok_or("no webhook URL is configured")?;
+    webhook::send_test(&config, db::now_ts()).await
+}
+
+// `None` follows the system timezone again
+#[tauri::command]
+pub fn set_timezone(db: State<'_, Database>, tz: Option<String>) -> Result<(), String> {
+    timefmt::set_timezone(&*db.lock()?, tz.as_deref())
+}
+
+#[tauri::command]
+pub fn get_timezone(db: State<'_, Database>) -> Result<String, String> {
+    Ok(timefmt::configured_zone(&*db.lock()?)?.name().to_string())
+}
+
+// Dry run of an import: counts, detected date format and a sample of parsed
+// records, without writing anything. `kind` is toggl_csv or settings.
+#[tauri::command]
+pub fn preview_import(db: State<'_, Database>, kind: String, path: String) -> Result<ImportPreview, String> {
+    let kind = ImportKind::parse(&kind)?;
+    let text = import::read_file(&path)?;
+    let tz = timefmt::configured_zone(&*db.lock()?)?;
+    import::preview(kind, &text, &tz)
+}
+
+// Times in the file are read in the configured timezone
+// Runs in the background, sending `import-progress` events; `cancel_import`
+// stops it and rolls back everything imported so far
+#[tauri::command]
+pub async fn import_toggl_csv(app: AppHandle, path: String) -> Result<ImportSummary, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let text = import::read_file(&path)?;
+        let db = app.state::<Database>();
+        // Other commands get BUSY_ERROR until the import is done
+        let mut conn = db.lock_for_transfer()?;
+        let tz = timefmt::configured_zone(&conn)?;
+        let mut reporter = Reporter::new(&app, transfer::IMPORT_PROGRESS_EVENT);
+        let (summary, ids) = import::toggl::import(&mut conn, &text, &tz, &mut |processed, total| {
+            reporter.report(processed, total)
+        })?;
+        drop(conn);
+        events::emit_entries_changed(&app, EntryChange::Insert, ids);
+        Ok(summary)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Import a CSV from any tracker, with `mapping` saying which columns hold
+// what. Times are read in the configured timezone; runs in the background
+// like `import_toggl_csv`.
+#[tauri::command]
+pub async fn import_csv_mapped(app: AppHandle, path: String, mapping: ColumnMapping) -> Result<ImportSummary, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let text = import::read_file(&path)?;
+        let db = app.state::<Database>();
+        // Other commands get BUSY_ERROR until the import is done
+        let mut conn = db.lock_for_transfer()?;
+        let tz = timefmt::configured_zone(&conn)?;
+        let mut reporter = Reporter::new(&app, transfer::IMPORT_PROGRESS_EVENT);
+        let (summary, ids) = import::mapped::import(&mut conn, &text, &mapping, &tz, &mut |processed, total| {
+            reporter.report(processed, total)
+        })?;
+        drop(conn);
+        events::emit_entries_changed(&app, EntryChange::Insert, ids);
+        Ok(summary)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Stop the running import, backup or restore; it fails with "cancelled"
+#[tauri::command]
+pub fn cancel_import(transfer: State<'_, Transfer>) {
+    transfer.cancel();
+}
+
+// Copy the database file to `path`, sending `export-progress` events
+#[tauri::command]
+pub async fn backup_data(app: AppHandle, path: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut reporter = Reporter::new(&app, transfer::EXPORT_PROGRESS_EVENT);
+        app.state::<Database>().backup_to(Path::new(&path), &mut |processed, total| {
+            reporter.report(processed, total)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Replace all data with a backup made by `backup_data`, sending
+// `import-progress` events. An encrypted backup needs its `passphrase` so it
+// can be checked before it replaces anything. Returns whether the restored
+// database is encrypted; running timers and undo history belong to the old
+// data and are dropped.
+#[tauri::command]
+pub async fn restore_data(app: AppHandle, path: String, passphrase: Option<String>) -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let passphrase = passphrase.unwrap_or_default();
+        let db = app.state::<Database>();
+        // Copied and checked without holding anything, so timers keep ticking
+        let mut reporter = Reporter::new(&app, transfer::IMPORT_PROGRESS_EVENT);
+        let staged = db.stage_restore(Path::new(&path), &passphrase, &mut |processed, total| {
+            reporter.report(processed, total)
+        })?;
+        let encrypted = {
+            let mut timers = app.state::<Timer>().lock()?;
+            let encrypted = db.restore_staged(&staged, &passphrase)?;
+            timers.clear();
+            app.state::<UndoStack>().clear()?;
+            encrypted
+        };
+        log::warn!("data was restored from a backup");
+        app.state::<Recovery>().clear();
+        if let Ok(conn) = app.state::<Database>().lock() {
+            app.state::<DurationDisplay>().reload(&conn);
+        }
+        tray::set_title(&app, "", "")?;
+        tray::refresh_menu(&app)?;
+        let _ = app.emit(reset::DATA_RESET_EVENT, ());
+        Ok(encrypted)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn import_settings(app: AppHandle, db: State<'_, Database>, path: String) -> Result<ImportSummary, String> {
+    let text = import::read_file(&path)?;
+    let summary = import::import_settings(&mut db.lock()?, &text)?;
+    profiles::apply_preferences(&app)?;
+    app.emit(profiles::SETTINGS_CHANGED_EVENT, ())
+        .map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+// Snapshot the current preferences (rates, budgets, rounding, shortcuts…)
+// under `name`, replacing a profile of that name
+#[tauri::command]
+pub fn save_profile(db: State<'_, Database>, name: String) -> Result<Profile, String> {
+    profiles::save_profile(&db.lock()?, &name, db::now_ts())
+}
+
+// Switch every preference to the profile's, apply them to the running app
+// and emit `settings-changed` so windows pick up the new values
+#[tauri::command]
+pub fn load_profile(app: AppHandle, db: State<'_, Database>, name: String) -> Result<Profile, String> {
+    let profile = profiles::load_profile(&mut db.lock()?, &name)?;
+    profiles::apply_preferences(&app)?;
+    app.emit(profiles::SETTINGS_CHANGED_EVENT, &profile.name)
+        .map_err(|e| e.to_string())?;
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn list_profiles(db: State<'_, Database>) -> Result<Vec<Profile>, String> {
+    profiles::list_profiles(&db.lock()?)
+}
+
+#[tauri::command]
+pub fn delete_profile(db: State<'_, Database>, name: String) -> Result<(), String> {
+    profiles::delete_profile(&db.lock()?, &name)
+}
+
+#[tauri::command]
+pub fn export_csv(db: State<'_, Database>, from_ts: i64, to_ts: i64, path: String) -> Result<(), String> {
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    let entries = entries::list_range(&conn, from_ts, to_ts)?;
+    export::write_file(&path, &export::csv::render(&entries, &tz))
+}
+
+// Write finished entries starting in [from_ts, to_ts) that carry every one
+// of `tags` (all entries when empty) as a JSON array. Returns how many were written.
+#[tauri::command]
+pub fn export_json(
+    db: State<'_, Database>,
+    from_ts: i64,
+    to_ts: i64,
+    tags: Vec<String>,
+    path: String,
+) -> Result<usize, String> {
+    if from_ts >= to_ts {
+        return Err(format!("empty time range {}..{}", from_ts, to_ts));
+    }
+    let wanted = tags::normalize(&tags);
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    let entry_tags = tags::tags_in_range(&conn, from_ts, to_ts)?;
+    let entries: Vec<Entry> = entries::list_range(&conn, from_ts, to_ts)?
+        .into_iter()
+        .filter(|entry| entry.end_ts.is_some())
+        .filter(|entry| {
+            let have = entry_tags.get(&entry.id).map(Vec::as_slice).unwrap_or_default();
+            wanted.iter().all(|tag| have.contains(tag))
+        })
+        .collect();
+    export::write_file(&path, export::json::render(&entries, &entry_tags, &tz)?)?;
+    Ok(entries.len())
+}
+
+// Timesheet grid for the week from the local day containing `week_start_ts`:
+// projects × days in hours, rounded per the rounding setting, with totals
+#[tauri::command]
+pub fn export_week_matrix(db: State<'_, Database>, week_start_ts: i64, path: String) -> Result<(), String> {
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    let days = progress::week_days(&tz, week_start_ts);
+    let (from_ts, to_ts) = (days[0].1, days[days.len() - 1].2);
+    let entries = entries::list_overlapping(&conn, from_ts, to_ts, db::now_ts())?;
+    let rounding = billing::rounding_minutes(&conn)?;
+    export::write_file(&path, export::matrix::render(&entries, &days, rounding))
+}
+
+// Move finished entries starting before `before_ts` into a new JSON archive
+// at `path`, returning how many were archived. Stats and exports only cover
+// the active database; stats and the PDF timesheet name the archive cutoff.
+#[tauri::command]
+pub fn archive_entries(
+    app: AppHandle,
+    db: State<'_, Database>,
+    before_ts: i64,
+    path: String,
+) -> Result<usize, String> {
+    let archived = archive::archive_entries(&mut db.lock()?, before_ts, Path::new(&path), db::now_ts())?;
+    let ids: Vec<i64> = archived.iter().map(|entry| entry.id).collect();
+    let count = ids.len();
+    events::emit_entries_changed(&app, EntryChange::Delete, ids);
+    Ok(count)
+}
+
+// Re-import an archive; entries already present are skipped
+#[tauri::command]
+pub fn restore_archive(app: AppHandle, db: State<'_, Database>, path: String) -> Result<usize, String> {
+    let restored = archive::restore_archive(&mut db.lock()?, Path::new(&path))?;
+    let ids: Vec<i64> = restored.iter().map(|entry| entry.id).collect();
+    let count = ids.len();
+    events::emit_entries_changed(&app, EntryChange::Insert, ids);
+    Ok(count)
+}
+
+#[tauri::command]
+pub fn list_archives(db: State<'_, Database>) -> Result<Vec<archive::ArchiveRecord>, String> {
+    archive::archives(&*db.lock()?)
+}
+
+// Printable timesheet grouped by day with daily and weekly totals
+#[tauri::command]
+pub fn export_pdf(
+    app: AppHandle,
+    db: State<'_, Database>,
+    from_ts: i64,
+    to_ts: i64,
+    path: String,
+) -> Result<(), String> {
+    if from_ts >= to_ts {
+        return Err(format!("empty time range {}..{}", from_ts, to_ts));
+    }
+    let fonts = export::pdf::load_fonts(&app)?;
+    let (entries, tz, week_start, archived_before) = {
+        let conn = db.lock()?;
+        (
+            entries::list_range(&conn, from_ts, to_ts)?,
+            timefmt::configured_zone(&conn)?,
+            stats::week_start(&conn)?,
+            archive::archived_before(&conn)?.filter(|before| from_ts < *before),
+        )
+    };
+    let pdf = export::pdf::render(
+        &entries,
+        &tz,
+        week_start,
+        (from_ts, to_ts),
+        archived_before,
+        &fonts,
+    )?;
+    export::write_file(&path, pdf)
+}
+
+// Shareable PNG card with the total time and top projects for a range
+#[tauri::command]
+pub fn export_summary_image(
+    app: AppHandle,
+    db: State<'_, Database>,
+    from_ts: i64,
+    to_ts: i64,
+    path: String,
+) -> Result<(), String> {
+    if from_ts >= to_ts {
+        return Err(format!("empty time range {}..{}", from_ts, to_ts));
+    }
+    let fonts = export::pdf::load_fonts(&app)?;
+    let (stats, projects, tz) = {
+        let conn = db.lock()?;
+        let tz = timefmt::configured_zone(&conn)?;
+        let mut stats = stats::get_stats(&conn, &tz, from_ts, to_ts, "project", db::now_ts())?;
+        stats.buckets.sort_by(|a, b| b.seconds.cmp(&a.seconds).then_with(|| a.key.cmp(&b.key)));
+        let projects = stats
+            .buckets
+            .iter()
+            .take(3)
+            .map(|bucket| {
+                Ok(CardProject {
+                    name: bucket.key.clone(),
+                    seconds: bucket.seconds,
+                    color: colors::project_color(&conn, &bucket.key)?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        (stats, projects, tz)
+    };
+    let png = export::card::render(&projects, stats.total_seconds, &tz, (from_ts, to_ts), &fonts)?;
+    export::write_file(&path, png)
+}
+
+// Per-project totals and a per-day breakdown, for pasting into tickets and emails
+#[tauri::command]
+pub fn export_markdown(db: State<'_, Database>, from_ts: i64, to_ts: i64, path: String) -> Result<(), String> {
+    if from_ts >= to_ts {
+        return Err(format!("empty time range {}..{}", from_ts, to_ts));
+    }
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    let entries = entries::list_range(&conn, from_ts, to_ts)?;
+    let notes = daynotes::notes_in_range(&conn, &tz, from_ts, to_ts)?;
+    let archived_before = archive::archived_before(&conn)?.filter(|before| from_ts < *before);
+    export::write_file(
+        &path,
+        export::markdown::render(&entries, &notes, &tz, (from_ts, to_ts), archived_before),
+    )
+}
+
+// Free-form journal note for the local day containing `day_ts`; an empty
+// note removes it
+#[tauri::command]
+pub fn set_day_note(db: State<'_, Database>, day_ts: i64, note: String) -> Result<(), String> {
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    daynotes::set_day_note(&conn, &tz, day_ts, &note)
+}
+
+#[tauri::command]
+pub fn get_day_note(db: State<'_, Database>, day_ts: i64) -> Result<Option<String>, String> {
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    daynotes::get_day_note(&conn, &tz, day_ts)
+}
+
+// Export one project's entries as csv, json or markdown; returns how many
+// entries were written (running ones are left out, as in every export)
+#[tauri::command]
+pub fn export_project(
+    db: State<'_, Database>,
+    project: String,
+    from_ts: i64,
+    to_ts: i64,
+    format: String,
+    path: String,
+) -> Result<usize, String> {
+    let format = export::Format::parse(&format)?;
+    if from_ts >= to_ts {
+        return Err(format!("empty time range {}..{}", from_ts, to_ts));
+    }
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    let entries: Vec<Entry> = entries::list_range(&conn, from_ts, to_ts)?
+        .into_iter()
+        .filter(|entry| entry.project == project)
+        .collect();
+    let contents = match format {
+        export::Format::Csv => export::csv::render(&entries, &tz),
+        export::Format::Json => {
+            export::json::render(&entries, &tags::tags_in_range(&conn, from_ts, to_ts)?, &tz)?
+        }
+        export::Format::Markdown => {
+            let archived_before = archive::archived_before(&conn)?.filter(|before| from_ts < *before);
+            // Only notes for days this project was worked on; the rest may
+            // be about other clients
+            let mut notes = daynotes::notes_in_range(&conn, &tz, from_ts, to_ts)?;
+            notes.retain(|day, _| {
+                entries
+                    .iter()
+                    .any(|entry| timefmt::to_local(entry.start_ts, &tz).date_naive() == *day)
+            });
+            export::markdown::render(&entries, &notes, &tz, (from_ts, to_ts), archived_before)
+        }
+    };
+    export::write_file(&path, contents)?;
+    Ok(export::finished(&entries).count())
+}
+
+#[tauri::command]
+pub fn export_ics(db: State<'_, Database>, from_ts: i64, to_ts: i64, path: String) -> Result<(), String> {
+    let conn = db.lock()?;
+    let tz = timefmt::configured_zone(&conn)?;
+    let entries = entries::list_range(&conn, from_ts, to_ts)?;
+    export::write_file(&path, &export::ics::render(&entries, &tz, db::now_ts()))
+}
+
+// The orphaned entries found at launch, for a frontend that mounted after the
+// `crash-recovery` event
+#[tauri::command]
+pub fn get_crash_recovery(recovery: State<'_, Recovery>) -> Option<Orphan> {
+    recovery.pending()
+}
+
+// How often, in seconds, a running timer's last tick is saved for crash recovery
+#[tauri::command]
+pub fn set_tick_persist_interval(app: AppHandle, seconds: u64) -> Result<(), String> {
+    recovery::set_tick_persist_seconds(&app, seconds)
+}
+
+// Resolve a crash-orphaned timer: `resume`, `close_at_last_tick` or `discard`
+#[tauri::command]
+pub fn recover_timer(app: AppHandle, action: String) -> Result<Vec<i64>, String> {
+    let action = RecoverAction::parse(&action)?;
+    let ids = recovery::resolve(&app, action)?;
+    let change = match action {
+        RecoverAction::Resume => None,
+        RecoverAction::CloseAtLastTick => Some(EntryChange::Update),
+        RecoverAction::Discard => Some(EntryChange::Delete),
+    };
+    if let Some(change) = change {
+        events::emit_entries_changed(&app, change, ids.clone());
+    }
+    Ok(ids)
+}
+
+// Run a tray/menu action without a real OS event, for end-to-end tests.
+// Debug desktop builds only.
+#[cfg(all(desktop, debug_assertions))]
+#[tauri::command]
+pub fn trigger_action(app: AppHandle, action: String) -> Result<(), String> {
+    match action.as_str() {
+        "show_widget" => widget::show(&app),
+        "hide_widget" => {
+            if let Some(window) = app.get_webview_window(widget::WIDGET_LABEL) {
+                widget::hide(&window);
+            }
+        }
+        "show_main" => crate::menubar::show_main(&app),
+        // Same as the tray's toggle-timer click action
+        "toggle_timer" => app.emit("toggle-timer", ()).map_err(|e| e.to_string())?,
+        other => {
+            return Err(format!(
+                "unknown action '{}', expected show_widget, hide_widget, show_main or toggle_timer",
+                other
+            ))
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(all(desktop, debug_assertions)))]
+#[tauri::command]
+pub fn trigger_action(_action: String) -> Result<(), String> {
+    Err("trigger_action is only available in debug desktop builds".to_string())
+}
@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::progress;
+use crate::timefmt;
+
+// Notes are keyed by the start of their local day in the configured zone
+fn day_start(tz: &Tz, ts: i64) -> i64 {
+    progress::day_bounds(tz, ts).1
+}
+
+// Set the note of the local day containing `ts`; a blank note removes it
+pub fn set_day_note(conn: &Connection, tz: &Tz, ts: i64, note: &str) -> Result<(), String> {
+    let day_ts = day_start(tz, ts);
+    if note.trim().is_empty() {
+        conn.execute("DELETE FROM day_notes WHERE day_ts = ?1", params![day_ts])
+    } else {
+        conn.execute(
+            "INSERT INTO day_notes (day_ts, note) VALUES (?1, ?2)
+             ON CONFLICT(day_ts) DO UPDATE SET note = excluded.note",
+            params![day_ts, note],
+        )
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn get_day_note(conn: &Connection, tz: &Tz, ts: i64) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT note FROM day_notes WHERE day_ts = ?1",
+        params![day_start(tz, ts)],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+// Notes of the days starting in [from_ts, to_ts), by local date
+pub fn notes_in_range(conn: &Connection, tz: &Tz, from_ts: i64, to_ts: i64) -> Result<BTreeMap<NaiveDate, String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT day_ts, note FROM day_notes WHERE day_ts >= ?1 AND day_ts < ?2")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![day_start(tz, from_ts), to_ts], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    let mut notes = BTreeMap::new();
+    for row in rows {
+        let (day_ts, note) = row.map_err(|e| e.to_string())?;
+        notes.insert(timefmt::to_local(day_ts, tz).date_naive(), note);
+    }
+    Ok(notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::db;
+
+    #[test]
+    fn notes_are_bucketed_by_local_day() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let tz: Tz = "America/New_York".parse().unwrap();
+        // 2025-06-03 03:30 UTC is still June 2 in New York, the same day as 08:00 local
+        let late = 1748921400;
+        set_day_note(&conn, &tz, late, "shipped the release").unwrap();
+        assert_eq!(
+            get_day_note(&conn, &tz, 1748865600).unwrap().as_deref(),
+            Some("shipped the release")
+        );
+        assert_eq!(get_day_note(&conn, &Tz::UTC, late).unwrap(), None);
+
+        let notes = notes_in_range(&conn, &tz, 1748865600, 1748952000).unwrap();
+        assert_eq!(
+            notes.into_iter().collect::<Vec<_>>(),
+            vec![(NaiveDate::from_ymd_opt(2025, 6, 2).unwrap(), "shipped the release".to_string())]
+        );
+
+        set_day_note(&conn, &tz, late, "  ").unwrap();
+        assert_eq!(get_day_note(&conn, &tz, late).unwrap(), None);
+    }
+}
@@ -0,0 +1,730 @@
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Mutex, MutexGuard, TryLockError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+// Returned by data commands while an encrypted database hasn't been unlocked
+pub const LOCKED_ERROR: &str = "LockedError: the database is encrypted; call unlock first";
+
+// Returned by maintenance while another operation (e.g. an import or restore) holds the database
+pub const BUSY_ERROR: &str =
+    "BusyError: another operation such as an import or restore is writing to the database; try again when it finishes";
+
+// Backups and restores are copied in chunks of this size, reporting progress after each
+const COPY_CHUNK_BYTES: usize = 1 << 20;
+
+// First bytes of every plaintext SQLite file; SQLCipher files start with a random salt
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactReport {
+    pub integrity_ok: bool,
+    // `ok`, or the problems reported by `PRAGMA integrity_check`
+    pub integrity: String,
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageInfo {
+    pub database_bytes: u64,
+    pub data_dir_bytes: u64,
+    pub entry_count: i64,
+}
+
+struct Store {
+    conn: Option<Connection>,
+    encrypted: bool,
+}
+
+// Local SQLite store shared by all commands through Tauri managed state.
+// An encrypted (SQLCipher) database starts locked until `unlock` is called.
+pub struct Database {
+    path: PathBuf,
+    store: Mutex<Store>,
+    // When we last wrote to the file, in epoch milliseconds, so the file
+    // watcher can tell our own writes from external ones
+    last_write_ms: AtomicI64,
+    // Set while an import, backup or restore holds the database, so other
+    // callers fail fast with BUSY_ERROR instead of waiting for it
+    transferring: AtomicBool,
+}
+
+// Clears the transfer flag when the import, backup or restore ends
+pub struct TransferFlag<'a>(&'a AtomicBool);
+
+impl Drop for TransferFlag<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+// Access to an unlocked connection; only handed out while one is open
+pub struct DbGuard<'a> {
+    store: MutexGuard<'a, Store>,
+    last_write_ms: &'a AtomicI64,
+    // SQLite's change counter when the guard was handed out
+    changes_before: i64,
+    // Held by transfers so the flag is cleared once the guard is dropped
+    _transfer: Option<TransferFlag<'a>>,
+}
+
+// Note the time if anything was written while the guard was held
+impl Drop for DbGuard<'_> {
+    fn drop(&mut self) {
+        if total_changes(self) != self.changes_before {
+            self.last_write_ms.store(now_ms(), Ordering::Relaxed);
+        }
+    }
+}
+
+impl Deref for DbGuard<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.store.conn.as_ref().expect("DbGuard requires an open connection")
+    }
+}
+
+impl DerefMut for DbGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.store.conn.as_mut().expect("DbGuard requires an open connection")
+    }
+}
+
+impl Database {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+        let store = if is_readable(&conn) {
+            migrate(&conn)?;
+            Store {
+                conn: Some(conn),
+                encrypted: false,
+            }
+        } else {
+            Store {
+                conn: None,
+                encrypted: true,
+            }
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            store: Mutex::new(store),
+            last_write_ms: AtomicI64::new(0),
+            transferring: AtomicBool::new(false),
+        })
+    }
+
+    fn store(&self) -> Result<MutexGuard<'_, Store>, String> {
+        self.store
+            .lock()
+            .map_err(|_| "database lock poisoned".to_string())
+    }
+
+    pub fn lock(&self) -> Result<DbGuard<'_>, String> {
+        if self.transferring.load(Ordering::SeqCst) {
+            return Err(BUSY_ERROR.to_string());
+        }
+        self.guard(None)
+    }
+
+    // Hold the database for a long import. Until the guard is dropped every
+    // other `lock` fails with BUSY_ERROR rather than stalling the UI and the
+    // tick loop behind it.
+    pub fn lock_for_transfer(&self) -> Result<DbGuard<'_>, String> {
+        let transfer = self.begin_transfer()?;
+        self.guard(Some(transfer))
+    }
+
+    fn begin_transfer(&self) -> Result<TransferFlag<'_>, String> {
+        if self.transferring.swap(true, Ordering::SeqCst) {
+            return Err(BUSY_ERROR.to_string());
+        }
+        Ok(TransferFlag(&self.transferring))
+    }
+
+    fn guard<'a>(&'a self, transfer: Option<TransferFlag<'a>>) -> Result<DbGuard<'a>, String> {
+        let store = self.store()?;
+        if store.conn.is_none() {
+            return Err(LOCKED_ERROR.to_string());
+        }
+        let changes_before = total_changes(store.conn.as_ref().expect("checked above"));
+        Ok(DbGuard {
+            store,
+            last_write_ms: &self.last_write_ms,
+            changes_before,
+            _transfer: transfer,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn last_write_ms(&self) -> i64 {
+        self.last_write_ms.load(Ordering::Relaxed)
+    }
+
+    // Whether a command holds the database right now, i.e. may be writing
+    pub fn is_busy(&self) -> bool {
+        matches!(self.store.try_lock(), Err(TryLockError::WouldBlock))
+    }
+
+    fn touch(&self) {
+        self.last_write_ms.store(now_ms(), Ordering::Relaxed);
+    }
+
+    pub fn is_locked(&self) -> Result<bool, String> {
+        Ok(self.store()?.conn.is_none())
+    }
+
+    pub fn unlock(&self, passphrase: &str) -> Result<(), String> {
+        let mut store = self.store()?;
+        if store.conn.is_some() {
+            return Ok(());
+        }
+        let conn = open_with_key(&self.path, passphrase)?;
+        if !is_readable(&conn) {
+            return Err("incorrect passphrase".to_string());
+        }
+        migrate(&conn)?;
+        self.touch();
+        store.conn = Some(conn);
+        Ok(())
+    }
+
+    // Check integrity, then VACUUM to reclaim space left by deletes. Refuses
+    // instead of waiting when another operation has the database, and skips
+    // the VACUUM if the integrity check finds problems.
+    pub fn compact(&self) -> Result<CompactReport, String> {
+        let store = match self.store.try_lock() {
+            Ok(store) => store,
+            Err(TryLockError::WouldBlock) => return Err(BUSY_ERROR.to_string()),
+            Err(TryLockError::Poisoned(_)) => return Err("database lock poisoned".to_string()),
+        };
+        let conn = store.conn.as_ref().ok_or(LOCKED_ERROR)?;
+        if !conn.is_autocommit() {
+            return Err(BUSY_ERROR.to_string());
+        }
+
+        let size_before = file_size(&self.path);
+        let integrity = integrity_check(conn)?;
+        let integrity_ok = integrity == "ok";
+
+        if integrity_ok {
+            conn.execute_batch("VACUUM").map_err(|e| e.to_string())?;
+            self.touch();
+        }
+        Ok(CompactReport {
+            integrity_ok,
+            integrity,
+            size_before,
+            size_after: file_size(&self.path),
+        })
+    }
+
+    // Disk usage of the database and everything else in `data_dir`
+    pub fn storage_info(&self, data_dir: &Path) -> Result<StorageInfo, String> {
+        let entry_count = self
+            .lock()?
+            .query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        Ok(StorageInfo {
+            database_bytes: file_size(&self.path),
+            data_dir_bytes: dir_size(data_dir),
+            entry_count,
+        })
+    }
+
+    // Copy the database file to `dest` as is, encrypted or not. Holds the
+    // database for the whole copy so no write lands halfway through.
+    pub fn backup_to(
+        &self,
+        dest: &Path,
+        progress: &mut dyn FnMut(u64, u64) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let _transfer = self.begin_transfer()?;
+        let store = self.store()?;
+        if store.conn.as_ref().is_some_and(|conn| !conn.is_autocommit()) {
+            return Err(BUSY_ERROR.to_string());
+        }
+        copy_file(&self.path, dest, progress)
+    }
+
+    // Copy a backup from `source` next to the database and check that it is
+    // an intact TimeGrid database, without touching the live data. Encrypted
+    // backups can only be checked with their `passphrase`.
+    pub fn stage_restore(
+        &self,
+        source: &Path,
+        passphrase: &str,
+        progress: &mut dyn FnMut(u64, u64) -> Result<(), String>,
+    ) -> Result<PathBuf, String> {
+        let staged = self.path.with_extension("db.restore");
+        copy_file(source, &staged, progress)?;
+        let checked = if has_sqlite_header(&staged) {
+            verify_database(&staged, "")
+        } else if passphrase.is_empty() {
+            Err("it is encrypted or not a database; enter its passphrase to restore it".to_string())
+        } else {
+            verify_database(&staged, passphrase)
+        };
+        if let Err(e) = checked {
+            let _ = fs::remove_file(&staged);
+            return Err(format!("{} can't be restored: {}", source.display(), e));
+        }
+        Ok(staged)
+    }
+
+    // Swap a staged backup in for the database. The current file is kept as
+    // `.db.bak`, and put back and reopened if anything fails. Returns whether
+    // the restored database is encrypted; it is opened with `passphrase`.
+    pub fn restore_staged(&self, staged: &Path, passphrase: &str) -> Result<bool, String> {
+        let mut store = self.store()?;
+        if store.conn.as_ref().is_some_and(|conn| !conn.is_autocommit()) {
+            let _ = fs::remove_file(staged);
+            return Err(BUSY_ERROR.to_string());
+        }
+        self.swap_in(&mut store, staged, passphrase)?;
+        Ok(store.encrypted)
+    }
+
+    // Replace the database file with `staged` and open it with `passphrase`.
+    // The current file is moved to `.db.bak` first and put back and reopened
+    // if the swap or the open fails; an encrypted original comes back locked.
+    fn swap_in(&self, store: &mut Store, staged: &Path, passphrase: &str) -> Result<(), String> {
+        let previous = self.path.with_extension("db.bak");
+
+        // Close the old connection before swapping the files
+        store.conn = None;
+        self.touch();
+        if let Err(e) = fs::rename(&self.path, &previous) {
+            let _ = fs::remove_file(staged);
+            self.open_store(store, "")?;
+            return Err(e.to_string());
+        }
+        let swapped = fs::rename(staged, &self.path)
+            .map_err(|e| e.to_string())
+            .and_then(|()| self.open_store(store, passphrase));
+        let error = match swapped {
+            Ok(()) if store.conn.is_some() => return Ok(()),
+            Ok(()) => "the new database could not be opened".to_string(),
+            Err(e) => e,
+        };
+        let _ = fs::remove_file(staged);
+        store.conn = None;
+        fs::rename(&previous, &self.path).map_err(|e| e.to_string())?;
+        self.open_store(store, "")?;
+        Err(error)
+    }
+
+    // Open the file at `path` into `store`. One that doesn't read with
+    // `passphrase` (e.g. an encrypted original reopened after a failed swap)
+    // is left locked until `unlock`.
+    fn open_store(&self, store: &mut Store, passphrase: &str) -> Result<(), String> {
+        let conn = open_with_key(&self.path, passphrase)?;
+        self.touch();
+        if is_readable(&conn) {
+            migrate(&conn)?;
+            store.encrypted = !passphrase.is_empty();
+            store.conn = Some(conn);
+        } else {
+            store.encrypted = true;
+            store.conn = None;
+        }
+        Ok(())
+    }
+
+    // Reopen the file after another program replaced it (e.g. a sync service
+    // renaming a downloaded copy over it), since the open connection still
+    // reads the old one. A plaintext replacement must have the SQLite header
+    // and read cleanly, or the open connection is kept. An encrypted one
+    // can't be checked without the passphrase, so it is locked until `unlock`.
+    pub fn reopen(&self) -> Result<(), String> {
+        let mut store = self.store()?;
+        let Some(open) = store.conn.as_ref() else {
+            // `unlock` opens whatever file is there
+            return Ok(());
+        };
+        if !open.is_autocommit() {
+            return Err(BUSY_ERROR.to_string());
+        }
+        if store.encrypted {
+            store.conn = None;
+            return Ok(());
+        }
+        if !has_sqlite_header(&self.path) {
+            return Err("the replaced file is not a SQLite database".to_string());
+        }
+        let conn = Connection::open(&self.path).map_err(|e| e.to_string())?;
+        if !is_readable(&conn) {
+            return Err("the replaced database can't be read".to_string());
+        }
+        migrate(&conn)?;
+        self.touch();
+        store.conn = Some(conn);
+        Ok(())
+    }
+
+    // Encrypt, rekey, or (with an empty passphrase) decrypt the database.
+    // A plaintext database is migrated by exporting it into a new encrypted
+    // file that then replaces the original.
+    pub fn set_passphrase(&self, passphrase: &str) -> Result<(), String> {
+        let mut store = self.store()?;
+        let conn = store.conn.as_ref().ok_or(LOCKED_ERROR)?;
+
+        if store.encrypted && !passphrase.is_empty() {
+            return conn
+                .pragma_update(None, "rekey", passphrase)
+                .map_err(|e| e.to_string());
+        }
+        if !store.encrypted && passphrase.is_empty() {
+            return Ok(());
+        }
+
+        let export_path = self.path.with_extension("db.export");
+        let _ = fs::remove_file(&export_path);
+        let export = export_path.to_string_lossy().to_string();
+        conn.execute("ATTACH DATABASE ?1 AS export KEY ?2", params![export, passphrase])
+            .map_err(|e| e.to_string())?;
+        let exported = conn
+            .query_row("SELECT sqlcipher_export('export')", [], |_| Ok(()))
+            .map_err(|e| e.to_string());
+        conn.execute_batch("DETACH DATABASE export")
+            .map_err(|e| e.to_string())?;
+        if let Err(e) = exported {
+            let _ = fs::remove_file(&export_path);
+            return Err(e);
+        }
+
+        self.swap_in(&mut store, &export_path, passphrase)?;
+        // Never leave the old, possibly unencrypted copy behind
+        let _ = fs::remove_file(self.path.with_extension("db.bak"));
+        Ok(())
+    }
+}
+
+// Copy `from` to `to` through a temporary file, so a failed or cancelled
+// copy never leaves a partial file at `to`
+fn copy_file(
+    from: &Path,
+    to: &Path,
+    progress: &mut dyn FnMut(u64, u64) -> Result<(), String>,
+) -> Result<(), String> {
+    let partial = to.with_extension("partial");
+    let result = copy_chunks(from, &partial, progress)
+        .and_then(|()| fs::rename(&partial, to).map_err(|e| e.to_string()));
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    result
+}
+
+fn copy_chunks(
+    from: &Path,
+    to: &Path,
+    progress: &mut dyn FnMut(u64, u64) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut source = File::open(from).map_err(|e| format!("failed to read {}: {}", from.display(), e))?;
+    let total = source.metadata().map_err(|e| e.to_string())?.len();
+    let mut dest = File::create(to).map_err(|e| format!("failed to write {}: {}", to.display(), e))?;
+    let mut buffer = vec![0; COPY_CHUNK_BYTES];
+    let mut processed = 0;
+    loop {
+        let read = source.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        dest.write_all(&buffer[..read]).map_err(|e| e.to_string())?;
+        processed += read as u64;
+        progress(processed, total)?;
+    }
+    dest.sync_all().map_err(|e| e.to_string())
+}
+
+// Whether the file starts with the plaintext SQLite header
+pub fn has_sqlite_header(path: &Path) -> bool {
+    let mut header = [0; 16];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok()
+        && &header == SQLITE_HEADER
+}
+
+// Open `path` with `passphrase` (empty for plaintext) and check that it is an
+// intact database with TimeGrid's tables
+fn verify_database(path: &Path, passphrase: &str) -> Result<(), String> {
+    let conn = open_with_key(path, passphrase)?;
+    let tables: i64 = conn
+        .query_row(
+            "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name IN ('entries', 'settings')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|_| "it can't be read; check the passphrase".to_string())?;
+    if tables != 2 {
+        return Err("it is not a TimeGrid database".to_string());
+    }
+    match integrity_check(&conn)?.as_str() {
+        "ok" => Ok(()),
+        problems => Err(format!("the integrity check failed: {}", problems)),
+    }
+}
+
+// `ok`, or the problems reported by `PRAGMA integrity_check`
+fn integrity_check(conn: &Connection) -> Result<String, String> {
+    let mut stmt = conn
+        .prepare("PRAGMA integrity_check")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows.join("\n"))
+}
+
+// Errors that only mean the database can't be used right now, which
+// background jobs skip quietly and retry on their next run
+pub fn is_unavailable(error: &str) -> bool {
+    error == LOCKED_ERROR || error == BUSY_ERROR
+}
+
+fn open_with_key(path: &Path, passphrase: &str) -> Result<Connection, String> {
+    let conn = Connection::open(path).map_err(|e| e.to_string())?;
+    if !passphrase.is_empty() {
+        conn.pragma_update(None, "key", passphrase)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(conn)
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|meta| meta.len()).unwrap_or(0)
+}
+
+// Total length of the regular files under `dir`. Symlinks are not followed,
+// so links out of the directory (or back into it) are neither counted nor
+// walked, and unreadable entries are skipped.
+pub fn dir_size(dir: &Path) -> u64 {
+    let mut total = 0;
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(meta) = fs::symlink_metadata(entry.path()) else {
+                continue;
+            };
+            if meta.is_dir() {
+                pending.push(entry.path());
+            } else if meta.is_file() {
+                total += meta.len();
+            }
+        }
+    }
+    total
+}
+
+// An encrypted database opened without (or with the wrong) key fails on first read
+fn is_readable(conn: &Connection) -> bool {
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))
+        .is_ok()
+}
+
+// Create tables on first launch; every statement must be safe to re-run
+pub fn migrate(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project TEXT NOT NULL,
+            note TEXT NOT NULL DEFAULT '',
+            start_ts INTEGER NOT NULL,
+            end_ts INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_entries_start ON entries(start_ts);
+        CREATE INDEX IF NOT EXISTS idx_entries_project ON entries(project);
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS presets (
+            name TEXT PRIMARY KEY,
+            project TEXT NOT NULL,
+            default_note TEXT NOT NULL DEFAULT '',
+            target_minutes INTEGER
+        );
+        CREATE TABLE IF NOT EXISTS recurrences (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project TEXT NOT NULL,
+            note TEXT NOT NULL DEFAULT '',
+            weekday TEXT NOT NULL,
+            start_hhmm TEXT NOT NULL,
+            duration_min INTEGER NOT NULL,
+            created_ts INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS recurrence_runs (
+            recurrence_id INTEGER NOT NULL,
+            occurrence_ts INTEGER NOT NULL,
+            PRIMARY KEY (recurrence_id, occurrence_ts)
+        );
+        CREATE TABLE IF NOT EXISTS entry_tags (
+            entry_id INTEGER NOT NULL,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (entry_id, tag)
+        );
+        CREATE INDEX IF NOT EXISTS idx_entry_tags_tag ON entry_tags(tag);
+        CREATE TABLE IF NOT EXISTS project_colors (
+            project TEXT PRIMARY KEY,
+            color TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS activity_samples (
+            ts INTEGER NOT NULL,
+            app TEXT NOT NULL,
+            title TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_activity_samples_ts ON activity_samples(ts);
+        CREATE TABLE IF NOT EXISTS categorization_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            pattern TEXT NOT NULL,
+            project TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS locked_spans (
+            start_ts INTEGER NOT NULL,
+            end_ts INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_locked_spans_start ON locked_spans(start_ts);
+        CREATE TABLE IF NOT EXISTS day_notes (
+            day_ts INTEGER PRIMARY KEY,
+            note TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS settings_profiles (
+            name TEXT PRIMARY KEY,
+            settings TEXT NOT NULL,
+            saved_ts INTEGER NOT NULL
+        );",
+    )
+    .map_err(|e| e.to_string())
+}
+
+// Rows changed through this connection since it was opened
+fn total_changes(conn: &Connection) -> i64 {
+    conn.query_row("SELECT total_changes()", [], |row| row.get(0))
+        .unwrap_or(0)
+}
+
+// Current wall-clock time as UTC epoch milliseconds
+pub fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+// Current wall-clock time as UTC epoch seconds
+pub fn now_ts() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dir_size_sums_files_without_following_symlinks() {
+        let dir = std::env::temp_dir().join(format!("timegrid-dir-size-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("a.bin"), [0u8; 100]).unwrap();
+        fs::write(dir.join("nested/b.bin"), [0u8; 23]).unwrap();
+        #[cfg(unix)]
+        {
+            // A loop back to the root would never finish if it were followed
+            std::os::unix::fs::symlink(&dir, dir.join("nested/loop")).unwrap();
+            std::os::unix::fs::symlink(dir.join("a.bin"), dir.join("link.bin")).unwrap();
+        }
+
+        assert_eq!(dir_size(&dir), 123);
+        assert_eq!(dir_size(&dir.join("missing")), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn restore_rejects_files_that_are_not_timegrid_databases() {
+        let dir = std::env::temp_dir().join(format!("timegrid-restore-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let db = Database::open(&dir.join("timegrid.db")).unwrap();
+        db.lock().unwrap().execute("INSERT INTO settings (key, value) VALUES ('kept', '1')", []).unwrap();
+
+        // Whole pages of junk used to pass for an encrypted backup
+        fs::write(dir.join("junk.db"), [7u8; 4096]).unwrap();
+        let no_progress = &mut |_: u64, _: u64| -> Result<(), String> { Ok(()) };
+        assert!(db.stage_restore(&dir.join("junk.db"), "", no_progress).is_err());
+        assert!(db.stage_restore(&dir.join("junk.db"), "secret", no_progress).is_err());
+        let other = Connection::open(dir.join("other.db")).unwrap();
+        other.execute_batch("CREATE TABLE notes (body TEXT)").unwrap();
+        drop(other);
+        assert!(db.stage_restore(&dir.join("other.db"), "", no_progress).is_err());
+
+        let backup = Database::open(&dir.join("backup.db")).unwrap();
+        backup.lock().unwrap().execute("INSERT INTO settings (key, value) VALUES ('restored', '1')", []).unwrap();
+        drop(backup);
+        let staged = db.stage_restore(&dir.join("backup.db"), "", no_progress).unwrap();
+        assert!(!db.restore_staged(&staged, "").unwrap());
+        let keys: i64 = db
+            .lock()
+            .unwrap()
+            .query_row("SELECT count(*) FROM settings WHERE key = 'restored'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(keys, 1);
+        assert!(dir.join("timegrid.db.bak").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn encrypting_keeps_the_data_and_no_plaintext_copy() {
+        let dir = std::env::temp_dir().join(format!("timegrid-passphrase-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("timegrid.db");
+        let db = Database::open(&path).unwrap();
+        db.lock().unwrap().execute("INSERT INTO settings (key, value) VALUES ('kept', '1')", []).unwrap();
+
+        db.set_passphrase("secret").unwrap();
+        assert!(!has_sqlite_header(&path));
+        assert!(!dir.join("timegrid.db.bak").exists());
+        let kept = |db: &Database| -> i64 {
+            db.lock()
+                .unwrap()
+                .query_row("SELECT count(*) FROM settings WHERE key = 'kept'", [], |row| row.get(0))
+                .unwrap()
+        };
+        assert_eq!(kept(&db), 1);
+
+        db.set_passphrase("").unwrap();
+        assert!(has_sqlite_header(&path));
+        assert_eq!(kept(&db), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn transfers_make_other_callers_fail_fast() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let transfer = db.lock_for_transfer().unwrap();
+        assert_eq!(db.lock().err().as_deref(), Some(BUSY_ERROR));
+        assert!(db.lock_for_transfer().is_err());
+        drop(transfer);
+        assert!(db.lock().is_ok());
+    }
+}
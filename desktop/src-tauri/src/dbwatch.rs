@@ -0,0 +1,133 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Mutex;
+
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db::{self, Database};
+use crate::settings;
+
+pub const WATCH_SETTING: &str = "watch_database";
+pub const DATABASE_CHANGED_EVENT: &str = "database-changed";
+
+// File events this soon after one of our own writes are taken to be ours
+const OWN_WRITE_GRACE_MS: i64 = 2_000;
+// A sync service touches the file several times per change; report it once
+const NOTIFY_INTERVAL_MS: i64 = 1_000;
+
+// The running watcher, if watching is turned on
+#[derive(Default)]
+pub struct DbWatcher {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    last_notified_ms: AtomicI64,
+}
+
+// Whether an event path is the database or one of its SQLite side files
+fn is_database_file(path: &Path, database: &Path) -> bool {
+    let (Some(name), Some(database)) = (path.file_name(), database.file_name()) else {
+        return false;
+    };
+    let (name, database) = (name.to_string_lossy(), database.to_string_lossy());
+    name == database
+        || ["-journal", "-wal"]
+            .iter()
+            .any(|suffix| name.strip_suffix(suffix) == Some(&*database))
+}
+
+// Whether the event is a new file moved or created at the database path,
+// rather than a write to the open one
+fn is_replaced(event: &notify::Event, database: &Path) -> bool {
+    matches!(event.kind, EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_)))
+        && event.paths.iter().any(|path| path.file_name() == database.file_name())
+}
+
+fn on_event(app: &AppHandle, event: notify::Event) {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+    let db = app.state::<Database>();
+    if !event.paths.iter().any(|path| is_database_file(path, db.path())) {
+        return;
+    }
+    let now = db::now_ms();
+    if db.is_busy() || now - db.last_write_ms() < OWN_WRITE_GRACE_MS {
+        return;
+    }
+    if is_replaced(&event, db.path()) {
+        match db.reopen() {
+            Ok(()) => log::info!("reopened the database after it was replaced"),
+            Err(e) => log::warn!("kept the open database after it was replaced: {}", e),
+        }
+    }
+    let state = app.state::<DbWatcher>();
+    if now - state.last_notified_ms.load(Ordering::Relaxed) < NOTIFY_INTERVAL_MS {
+        return;
+    }
+    state.last_notified_ms.store(now, Ordering::Relaxed);
+    log::info!("the database file was changed by another program");
+    let _ = app.emit(DATABASE_CHANGED_EVENT, ());
+}
+
+// Start or stop watching the database file. The directory is watched rather
+// than the file, since sync services often replace the file instead of
+// writing to it.
+pub fn set_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    let state = app.state::<DbWatcher>();
+    let mut watcher = state.watcher.lock().map_err(|_| "watcher lock poisoned")?;
+    if !enabled {
+        *watcher = None;
+        return Ok(());
+    }
+    if watcher.is_some() {
+        return Ok(());
+    }
+    let db = app.state::<Database>();
+    let dir = db.path().parent().ok_or("the database has no parent directory")?;
+    let handle = app.clone();
+    let mut created = notify::recommended_watcher(move |result: notify::Result<notify::Event>| match result {
+        Ok(event) => on_event(&handle, event),
+        Err(e) => log::warn!("database watcher error: {}", e),
+    })
+    .map_err(|e| format!("failed to watch the database: {}", e))?;
+    created
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("failed to watch the database: {}", e))?;
+    *watcher = Some(created);
+    Ok(())
+}
+
+// Persist the choice and apply it
+pub fn set_watching(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set(&*app.state::<Database>().lock()?, WATCH_SETTING, &enabled)?;
+    set_enabled(app, enabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_the_database_and_its_side_files() {
+        let database = Path::new("/data/timegrid.db");
+        assert!(is_database_file(Path::new("/data/timegrid.db"), database));
+        assert!(is_database_file(Path::new("/data/timegrid.db-journal"), database));
+        assert!(is_database_file(Path::new("/data/timegrid.db-wal"), database));
+        assert!(!is_database_file(Path::new("/data/timegrid.db.restore"), database));
+        assert!(!is_database_file(Path::new("/data/logs/timegrid.log"), database));
+    }
+
+    #[test]
+    fn a_rename_onto_the_database_is_a_replace() {
+        use notify::event::{CreateKind, DataChange, RenameMode};
+        let database = Path::new("/data/timegrid.db");
+        let event = |kind, path: &str| notify::Event::new(kind).add_path(path.into());
+        let renamed = EventKind::Modify(ModifyKind::Name(RenameMode::To));
+        assert!(is_replaced(&event(renamed, "/data/timegrid.db"), database));
+        assert!(is_replaced(&event(EventKind::Create(CreateKind::File), "/data/timegrid.db"), database));
+        assert!(!is_replaced(&event(renamed, "/data/timegrid.db-wal"), database));
+        let written = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+        assert!(!is_replaced(&event(written, "/data/timegrid.db"), database));
+    }
+}
@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{async_runtime, AppHandle, Emitter, Manager, Monitor, PhysicalPosition, WebviewWindow};
+
+use crate::widget;
+
+pub const DISPLAYS_CHANGED_EVENT: &str = "displays-changed";
+
+// Tauri has no monitor change event, so the monitor list is polled
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+// A window showing less than this much of itself on any display is pulled back
+const MIN_VISIBLE: (i32, i32) = (100, 50);
+
+// A screen rectangle in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Area {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Area {
+    fn overlap(&self, other: &Area) -> (i32, i32) {
+        let width = (self.x + self.width).min(other.x + other.width) - self.x.max(other.x);
+        let height = (self.y + self.height).min(other.y + other.height) - self.y.max(other.y);
+        (width.max(0), height.max(0))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Display {
+    pub name: Option<String>,
+    pub area: Area,
+    pub scale_factor: f64,
+}
+
+impl Display {
+    fn from_monitor(monitor: &Monitor) -> Self {
+        let (position, size) = (monitor.position(), monitor.size());
+        Self {
+            name: monitor.name().cloned(),
+            area: Area {
+                x: position.x,
+                y: position.y,
+                width: size.width as i32,
+                height: size.height as i32,
+            },
+            scale_factor: monitor.scale_factor(),
+        }
+    }
+}
+
+// Where to move a window so it is usable again, or None if enough of it is
+// already on one of `displays`. An off-screen window is centered on `fallback`
+// (the primary display), shrunk to its top-left corner if it doesn't fit.
+pub fn into_view(window: Area, displays: &[Area], fallback: Area) -> Option<(i32, i32)> {
+    let min_width = MIN_VISIBLE.0.min(window.width);
+    let min_height = MIN_VISIBLE.1.min(window.height);
+    let visible = displays.iter().any(|display| {
+        let (width, height) = window.overlap(display);
+        width >= min_width && height >= min_height
+    });
+    if visible {
+        return None;
+    }
+    Some((
+        fallback.x + (fallback.width - window.width).max(0) / 2,
+        fallback.y + (fallback.height - window.height).max(0) / 2,
+    ))
+}
+
+fn displays(app: &AppHandle) -> Vec<Display> {
+    app.available_monitors()
+        .map(|monitors| monitors.iter().map(Display::from_monitor).collect())
+        .unwrap_or_default()
+}
+
+fn window_area(window: &WebviewWindow) -> Option<Area> {
+    let position = window.outer_position().ok()?;
+    let size = window.outer_size().ok()?;
+    Some(Area {
+        x: position.x,
+        y: position.y,
+        width: size.width as i32,
+        height: size.height as i32,
+    })
+}
+
+// Bring a window back into view if the display it was on went away
+fn rescue(window: &WebviewWindow, displays: &[Display], fallback: Area) {
+    let Some(area) = window_area(window) else {
+        return;
+    };
+    let areas: Vec<Area> = displays.iter().map(|display| display.area).collect();
+    if let Some((x, y)) = into_view(area, &areas, fallback) {
+        log::info!("moving window '{}' back onto a display", window.label());
+        let _ = window.set_position(PhysicalPosition::new(x, y));
+    }
+}
+
+fn on_change(app: &AppHandle, displays: &[Display]) {
+    let fallback = app
+        .primary_monitor()
+        .ok()
+        .flatten()
+        .map(|monitor| Display::from_monitor(&monitor).area)
+        .or_else(|| displays.first().map(|display| display.area));
+    if let Some(fallback) = fallback {
+        for window in app.webview_windows().values() {
+            if window.label() != widget::WIDGET_LABEL {
+                rescue(window, displays, fallback);
+            }
+        }
+    }
+    // The widget follows the tray icon, wherever it ended up
+    if let Some(widget) = app.get_webview_window(widget::WIDGET_LABEL) {
+        if widget.is_visible().unwrap_or(false) {
+            if let Err(e) = widget::position_widget_window(app) {
+                log::warn!("failed to reposition widget: {}", e);
+            }
+        }
+    }
+    let _ = app.emit(DISPLAYS_CHANGED_EVENT, displays);
+}
+
+// Watch for displays being connected, disconnected, moved or rescaled
+pub fn spawn_watcher(app: AppHandle) {
+    async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        let mut last = displays(&app);
+        loop {
+            interval.tick().await;
+            let current = displays(&app);
+            // An empty list is usually a transient failure mid-reconfiguration
+            if current.is_empty() || current == last {
+                continue;
+            }
+            on_change(&app, &current);
+            last = current;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(x: i32, y: i32, width: i32, height: i32) -> Area {
+        Area { x, y, width, height }
+    }
+
+    #[test]
+    fn off_screen_windows_move_to_the_fallback_display() {
+        let laptop = area(0, 0, 2880, 1800);
+        let window = area(3200, 200, 1000, 700);
+        // Still visible while the external display on the right is connected
+        assert_eq!(into_view(window, &[laptop, area(2880, 0, 3840, 2160)], laptop), None);
+        // Once it's gone the window is centered on the laptop screen
+        assert_eq!(into_view(window, &[laptop], laptop), Some((940, 550)));
+        // A sliver along the edge doesn't count as visible
+        assert_eq!(into_view(area(2850, 100, 1000, 700), &[laptop], laptop), Some((940, 550)));
+    }
+}
@@ -0,0 +1,462 @@
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+// A tracked block of time. `end_ts` is None while the timer is still running.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: i64,
+    pub project: String,
+    pub note: String,
+    pub start_ts: i64,
+    pub end_ts: Option<i64>,
+}
+
+impl Entry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            project: row.get(1)?,
+            note: row.get(2)?,
+            start_ts: row.get(3)?,
+            end_ts: row.get(4)?,
+        })
+    }
+}
+
+const ENTRY_COLUMNS: &str = "id, project, note, start_ts, end_ts";
+
+pub fn get_entry(conn: &Connection, id: i64) -> Result<Entry, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM entries WHERE id = ?1", ENTRY_COLUMNS),
+        params![id],
+        Entry::from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("entry {} not found", id))
+}
+
+pub fn insert_entry(
+    conn: &Connection,
+    project: &str,
+    note: &str,
+    start_ts: i64,
+    end_ts: Option<i64>,
+) -> Result<Entry, String> {
+    conn.execute(
+        "INSERT INTO entries (project, note, start_ts, end_ts) VALUES (?1, ?2, ?3, ?4)",
+        params![project, note, start_ts, end_ts],
+    )
+    .map_err(|e| e.to_string())?;
+    get_entry(conn, conn.last_insert_rowid())
+}
+
+// Divide an entry at `at_ts`: the original keeps [start, at) and a new entry
+// with the same project, note and tags covers [at, end)
+pub fn split_entry(conn: &mut Connection, id: i64, at_ts: i64) -> Result<(Entry, Entry), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let entry = get_entry(&tx, id)?;
+    let end_ts = entry
+        .end_ts
+        .ok_or_else(|| format!("entry {} is still running and cannot be split", id))?;
+    if at_ts <= entry.start_ts || at_ts >= end_ts {
+        return Err(format!(
+            "split point {} must lie strictly between {} and {}",
+            at_ts, entry.start_ts, end_ts
+        ));
+    }
+
+    tx.execute(
+        "UPDATE entries SET end_ts = ?1 WHERE id = ?2",
+        params![at_ts, id],
+    )
+    .map_err(|e| e.to_string())?;
+    let second = insert_entry(&tx, &entry.project, &entry.note, at_ts, Some(end_ts))?;
+    tx.execute(
+        "INSERT INTO entry_tags (entry_id, tag) SELECT ?1, tag FROM entry_tags WHERE entry_id = ?2",
+        params![second.id, id],
+    )
+    .map_err(|e| e.to_string())?;
+    let first = get_entry(&tx, id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok((first, second))
+}
+
+// Entries that have not ended yet, oldest first
+pub fn open_entries(conn: &Connection) -> Result<Vec<Entry>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM entries WHERE end_ts IS NULL ORDER BY start_ts, id",
+            ENTRY_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], Entry::from_row).map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())
+}
+
+// Entries starting in [from_ts, to_ts), oldest first
+pub fn list_range(conn: &Connection, from_ts: i64, to_ts: i64) -> Result<Vec<Entry>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM entries WHERE start_ts >= ?1 AND start_ts < ?2 ORDER BY start_ts, id",
+            ENTRY_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_ts, to_ts], Entry::from_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())
+}
+
+// Entries overlapping [from_ts, to_ts), oldest first; running ones count as
+// lasting until `now`
+pub fn list_overlapping(conn: &Connection, from_ts: i64, to_ts: i64, now: i64) -> Result<Vec<Entry>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM entries WHERE start_ts < ?2 AND COALESCE(end_ts, ?3) > ?1 ORDER BY start_ts, id",
+            ENTRY_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_ts, to_ts, now], Entry::from_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())
+}
+
+// Finished entries starting before `before_ts`, oldest first
+pub fn list_finished_before(conn: &Connection, before_ts: i64) -> Result<Vec<Entry>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM entries WHERE start_ts < ?1 AND end_ts IS NOT NULL ORDER BY start_ts, id",
+            ENTRY_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![before_ts], Entry::from_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())
+}
+
+// Project of the most recently started entry, if any
+pub fn last_project(conn: &Connection) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT project FROM entries ORDER BY start_ts DESC, id DESC LIMIT 1",
+        [],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+// Optional filters shared by the page query and its count
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EntryFilter {
+    pub project: Option<String>,
+    pub from_ts: Option<i64>,
+    pub to_ts: Option<i64>,
+}
+
+impl EntryFilter {
+    // WHERE clause with positional placeholders; values are always bound,
+    // never interpolated
+    fn where_clause(&self) -> (String, Vec<Value>) {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        if let Some(project) = &self.project {
+            values.push(Value::Text(project.clone()));
+            conditions.push(format!("project = ?{}", values.len()));
+        }
+        if let Some(from_ts) = self.from_ts {
+            values.push(Value::Integer(from_ts));
+            conditions.push(format!("start_ts >= ?{}", values.len()));
+        }
+        if let Some(to_ts) = self.to_ts {
+            values.push(Value::Integer(to_ts));
+            conditions.push(format!("start_ts < ?{}", values.len()));
+        }
+        if conditions.is_empty() {
+            (String::new(), values)
+        } else {
+            (format!(" WHERE {}", conditions.join(" AND ")), values)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntrySort {
+    StartAsc,
+    StartDesc,
+}
+
+impl EntrySort {
+    pub fn parse(sort: &str) -> Result<Self, String> {
+        match sort {
+            "start_asc" => Ok(Self::StartAsc),
+            "start_desc" => Ok(Self::StartDesc),
+            other => Err(format!("unknown sort '{}', expected start_asc or start_desc", other)),
+        }
+    }
+
+    fn order_by(self) -> &'static str {
+        match self {
+            Self::StartAsc => "start_ts ASC, id ASC",
+            Self::StartDesc => "start_ts DESC, id DESC",
+        }
+    }
+}
+
+// One page of results plus the number of rows matching the filters
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total_count: i64,
+}
+
+pub fn list_entries(
+    conn: &Connection,
+    filter: &EntryFilter,
+    sort: EntrySort,
+    limit: u32,
+    offset: u32,
+) -> Result<Page<Entry>, String> {
+    let (where_clause, mut values) = filter.where_clause();
+
+    let total_count = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM entries{}", where_clause),
+            params_from_iter(values.iter()),
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    values.push(Value::Integer(limit as i64));
+    values.push(Value::Integer(offset as i64));
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM entries{} ORDER BY {} LIMIT ?{} OFFSET ?{}",
+            ENTRY_COLUMNS,
+            where_clause,
+            sort.order_by(),
+            values.len() - 1,
+            values.len()
+        ))
+        .map_err(|e| e.to_string())?;
+    let items = stmt
+        .query_map(params_from_iter(values.iter()), Entry::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(Page { items, total_count })
+}
+
+// Delete finished entries matching `filter` in one transaction and return
+// them so the deletion can be undone. Running entries are never touched.
+// A filter with neither a project nor a time bound is rejected so nothing
+// is wiped by accident.
+pub fn delete_entries(conn: &mut Connection, filter: &EntryFilter) -> Result<Vec<Entry>, String> {
+    if filter.project.as_deref().is_some_and(|p| p.trim().is_empty()) {
+        return Err("project filter cannot be empty".to_string());
+    }
+    if filter.project.is_none() && filter.from_ts.is_none() && filter.to_ts.is_none() {
+        return Err("refusing to delete without a project or time range".to_string());
+    }
+    if let (Some(from_ts), Some(to_ts)) = (filter.from_ts, filter.to_ts) {
+        if from_ts >= to_ts {
+            return Err(format!("empty time range {}..{}", from_ts, to_ts));
+        }
+    }
+
+    let (where_clause, values) = filter.where_clause();
+    let where_clause = if where_clause.is_empty() {
+        " WHERE end_ts IS NOT NULL".to_string()
+    } else {
+        format!("{} AND end_ts IS NOT NULL", where_clause)
+    };
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let deleted = {
+        let mut stmt = tx
+            .prepare(&format!(
+                "SELECT {} FROM entries{} ORDER BY start_ts, id",
+                ENTRY_COLUMNS, where_clause
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params_from_iter(values.iter()), Entry::from_row)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| e.to_string())?
+    };
+    tx.execute(
+        &format!("DELETE FROM entries{}", where_clause),
+        params_from_iter(values.iter()),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(deleted)
+}
+
+// Move finished entries starting in [from_ts, to_ts), optionally for one
+// project, by `delta_seconds` in one transaction and return their previous
+// state so the shift can be undone. Running entries are never touched. The
+// shift is rejected if it would leave an entry ending before it starts or
+// overlapping an entry that wasn't moved; running ones count as lasting until
+// `now`.
+pub fn shift_entries(
+    conn: &mut Connection,
+    from_ts: i64,
+    to_ts: i64,
+    project: Option<&str>,
+    delta_seconds: i64,
+    now: i64,
+) -> Result<Vec<Entry>, String> {
+    if from_ts >= to_ts {
+        return Err(format!("empty time range {}..{}", from_ts, to_ts));
+    }
+    let filter = EntryFilter {
+        project: project.map(str::to_string),
+        from_ts: Some(from_ts),
+        to_ts: Some(to_ts),
+    };
+    let (where_clause, values) = filter.where_clause();
+    let where_clause = format!("{} AND end_ts IS NOT NULL", where_clause);
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let before = {
+        let mut stmt = tx
+            .prepare(&format!(
+                "SELECT {} FROM entries{} ORDER BY start_ts, id",
+                ENTRY_COLUMNS, where_clause
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params_from_iter(values.iter()), Entry::from_row)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| e.to_string())?
+    };
+    if before.is_empty() || delta_seconds == 0 {
+        return Ok(Vec::new());
+    }
+
+    for entry in &before {
+        let start_ts = entry.start_ts.checked_add(delta_seconds);
+        let end_ts = entry.end_ts.and_then(|end_ts| end_ts.checked_add(delta_seconds));
+        let (start_ts, end_ts) = start_ts
+            .zip(end_ts)
+            .ok_or_else(|| format!("shifting entry {} by {}s overflows", entry.id, delta_seconds))?;
+        if end_ts < start_ts {
+            return Err(format!("entry {} would end before it starts", entry.id));
+        }
+        tx.execute(
+            "UPDATE entries SET start_ts = ?1, end_ts = ?2 WHERE id = ?3",
+            params![start_ts, end_ts, entry.id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    let moved: Vec<i64> = before.iter().map(|entry| entry.id).collect();
+    for id in &moved {
+        let entry = get_entry(&tx, *id)?;
+        let end_ts = entry.end_ts.unwrap_or(now);
+        let clash = list_overlapping(&tx, entry.start_ts, end_ts, now)?
+            .into_iter()
+            .find(|other| !moved.contains(&other.id));
+        if let Some(other) = clash {
+            return Err(format!(
+                "entry {} would overlap entry {} ({})",
+                entry.id, other.id, other.project
+            ));
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(before)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db, tags};
+
+    #[test]
+    fn split_keeps_project_note_and_tags_on_both_halves() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let entry = insert_entry(&conn, "Acme", "Design", 1_000, Some(4_600)).unwrap();
+        tags::set_entry_tags(&mut conn, entry.id, &["billable".to_string(), "design".to_string()]).unwrap();
+
+        for at_ts in [1_000, 4_600, 5_000] {
+            assert!(split_entry(&mut conn, entry.id, at_ts).is_err());
+        }
+        let (first, second) = split_entry(&mut conn, entry.id, 2_800).unwrap();
+        assert_eq!((first.id, first.start_ts, first.end_ts), (entry.id, 1_000, Some(2_800)));
+        assert_eq!((second.start_ts, second.end_ts), (2_800, Some(4_600)));
+        assert_eq!((second.project.as_str(), second.note.as_str()), ("Acme", "Design"));
+        assert_eq!(tags::entry_tags(&conn, second.id).unwrap(), vec!["billable", "design"]);
+        assert_eq!(tags::entry_tags(&conn, first.id).unwrap(), vec!["billable", "design"]);
+
+        let running = insert_entry(&conn, "Acme", "", 5_000, None).unwrap();
+        assert!(split_entry(&mut conn, running.id, 5_100).is_err());
+    }
+
+    #[test]
+    fn list_entries_pages_with_filtered_count() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        for start in 0..5 {
+            insert_entry(&conn, "A", "", start * 100, Some(start * 100 + 50)).unwrap();
+        }
+        insert_entry(&conn, "B'; DROP TABLE entries; --", "", 250, None).unwrap();
+
+        let filter = EntryFilter {
+            project: Some("A".to_string()),
+            ..Default::default()
+        };
+        let page = list_entries(&conn, &filter, EntrySort::StartDesc, 2, 1).unwrap();
+        assert_eq!(page.total_count, 5);
+        let starts: Vec<i64> = page.items.iter().map(|e| e.start_ts).collect();
+        assert_eq!(starts, vec![300, 200]);
+
+        let filter = EntryFilter {
+            project: Some("B'; DROP TABLE entries; --".to_string()),
+            from_ts: Some(200),
+            to_ts: Some(300),
+        };
+        let page = list_entries(&conn, &filter, EntrySort::StartAsc, 10, 0).unwrap();
+        assert_eq!(page.total_count, 1);
+        assert_eq!(page.items[0].start_ts, 250);
+    }
+
+    #[test]
+    fn shift_moves_matching_entries_and_rejects_overlaps() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let a = insert_entry(&conn, "A", "", 100, Some(200)).unwrap();
+        let b = insert_entry(&conn, "B", "", 250, Some(300)).unwrap();
+        let running = insert_entry(&conn, "A", "", 400, None).unwrap();
+
+        // A would run into B
+        assert!(shift_entries(&mut conn, 0, 1_000, Some("A"), 100, 500).is_err());
+        assert_eq!(get_entry(&conn, a.id).unwrap(), a);
+
+        let before = shift_entries(&mut conn, 0, 1_000, Some("A"), -50, 500).unwrap();
+        assert_eq!(before, vec![a.clone()]);
+        let shifted = get_entry(&conn, a.id).unwrap();
+        assert_eq!((shifted.start_ts, shifted.end_ts), (50, Some(150)));
+        assert_eq!(get_entry(&conn, running.id).unwrap(), running);
+
+        // Moving both together keeps them apart
+        assert_eq!(shift_entries(&mut conn, 0, 300, None, 60, 500).unwrap().len(), 2);
+        assert_eq!(get_entry(&conn, b.id).unwrap().start_ts, 310);
+        assert!(shift_entries(&mut conn, 0, 1_000, None, i64::MAX, 500).is_err());
+    }
+}
@@ -0,0 +1,30 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+pub const ENTRIES_CHANGED_EVENT: &str = "entries-changed";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryChange {
+    Insert,
+    Update,
+    Delete,
+    Split,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntriesChanged {
+    pub change: EntryChange,
+    pub ids: Vec<i64>,
+}
+
+// Tell every window which entries a command touched so open lists refresh.
+// Every command that writes to `entries` reports through here.
+pub fn emit_entries_changed(app: &AppHandle, change: EntryChange, ids: Vec<i64>) {
+    if ids.is_empty() {
+        return;
+    }
+    if let Err(e) = app.emit(ENTRIES_CHANGED_EVENT, EntriesChanged { change, ids }) {
+        log::warn!("failed to emit {}: {}", ENTRIES_CHANGED_EVENT, e);
+    }
+}
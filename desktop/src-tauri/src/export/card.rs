@@ -0,0 +1,207 @@
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use chrono_tz::Tz;
+use tiny_skia::{Color, Paint, Pixmap, PremultipliedColorU8, Rect, Transform};
+
+use crate::export::markdown::format_duration;
+use crate::export::pdf::Fonts;
+use crate::timefmt;
+
+// Open Graph size, so the card previews well when shared
+const WIDTH: u32 = 1200;
+const HEIGHT: u32 = 630;
+const PADDING: f32 = 72.0;
+const TOP_PROJECTS: usize = 3;
+
+const BACKGROUND: [u8; 3] = [0x1f, 0x24, 0x30];
+const TEXT: [u8; 3] = [0xf5, 0xf6, 0xf8];
+const MUTED: [u8; 3] = [0x9a, 0xa3, 0xb5];
+const TRACK: [u8; 3] = [0x2c, 0x33, 0x42];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardProject {
+    pub name: String,
+    pub seconds: i64,
+    // "#rrggbb", as from `colors::project_color`
+    pub color: String,
+}
+
+fn rgb(hex: &str) -> [u8; 3] {
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+            .unwrap_or(0)
+    };
+    [channel(1), channel(3), channel(5)]
+}
+
+fn fill_rect(pixmap: &mut Pixmap, x: f32, y: f32, width: f32, height: f32, color: [u8; 3]) {
+    if let Some(rect) = Rect::from_xywh(x, y, width, height) {
+        let mut paint = Paint::default();
+        paint.set_color(Color::from_rgba8(color[0], color[1], color[2], 255));
+        pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+    }
+}
+
+// Mix `color` into one pixel by glyph coverage. The card is opaque, so
+// premultiplied and straight colors are the same.
+fn blend(pixmap: &mut Pixmap, x: i32, y: i32, color: [u8; 3], coverage: f32) {
+    let (width, height) = (pixmap.width() as i32, pixmap.height() as i32);
+    if x < 0 || y < 0 || x >= width || y >= height {
+        return;
+    }
+    let index = (y * width + x) as usize;
+    let pixel = &mut pixmap.pixels_mut()[index];
+    let coverage = coverage.clamp(0.0, 1.0);
+    let mix = |src: u8, dst: u8| (src as f32 * coverage + dst as f32 * (1.0 - coverage)).round() as u8;
+    if let Some(mixed) = PremultipliedColorU8::from_rgba(
+        mix(color[0], pixel.red()),
+        mix(color[1], pixel.green()),
+        mix(color[2], pixel.blue()),
+        255,
+    ) {
+        *pixel = mixed;
+    }
+}
+
+fn text_width(font: &FontRef, size: f32, text: &str) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            width += scaled.kern(previous, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+// Shorten `text` with an ellipsis until it fits in `max_width`
+fn fit(font: &FontRef, size: f32, text: &str, max_width: f32) -> String {
+    if text_width(font, size, text) <= max_width {
+        return text.to_string();
+    }
+    let mut chars: Vec<char> = text.chars().collect();
+    while !chars.is_empty() {
+        chars.pop();
+        let candidate = format!("{}…", chars.iter().collect::<String>().trim_end());
+        if text_width(font, size, &candidate) <= max_width {
+            return candidate;
+        }
+    }
+    "…".to_string()
+}
+
+// Draw `text` with its baseline at `y`
+fn draw_text(pixmap: &mut Pixmap, font: &FontRef, size: f32, x: f32, y: f32, text: &str, color: [u8; 3]) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut caret = x;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        let glyph = id.with_scale_and_position(size, point(caret, y));
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+        if let Some(outline) = font.outline_glyph(glyph) {
+            let bounds = outline.px_bounds();
+            outline.draw(|gx, gy, coverage| {
+                blend(
+                    pixmap,
+                    bounds.min.x as i32 + gx as i32,
+                    bounds.min.y as i32 + gy as i32,
+                    color,
+                    coverage,
+                );
+            });
+        }
+    }
+}
+
+// A PNG card with the date range, total time and the top projects as bars.
+// `projects` must be sorted by time, most first.
+pub fn render(
+    projects: &[CardProject],
+    total_seconds: i64,
+    tz: &Tz,
+    (from_ts, to_ts): (i64, i64),
+    fonts: &Fonts,
+) -> Result<Vec<u8>, String> {
+    let regular = FontRef::try_from_slice(&fonts.regular).map_err(|e| format!("invalid font: {}", e))?;
+    let bold = FontRef::try_from_slice(&fonts.bold).map_err(|e| format!("invalid font: {}", e))?;
+    let mut pixmap = Pixmap::new(WIDTH, HEIGHT).ok_or("failed to allocate the image")?;
+    pixmap.fill(Color::from_rgba8(BACKGROUND[0], BACKGROUND[1], BACKGROUND[2], 255));
+
+    // `to_ts` is exclusive, so label the range with the last included day
+    let range = format!(
+        "{} – {}",
+        timefmt::format_local(from_ts, tz, "%b %-d, %Y"),
+        timefmt::format_local(to_ts - 1, tz, "%b %-d, %Y")
+    );
+    draw_text(&mut pixmap, &bold, 28.0, PADDING, PADDING + 24.0, "TimeGrid", MUTED);
+    draw_text(&mut pixmap, &regular, 28.0, PADDING, PADDING + 68.0, &range, MUTED);
+    draw_text(&mut pixmap, &bold, 96.0, PADDING, PADDING + 180.0, &format_duration(total_seconds), TEXT);
+
+    let content_width = WIDTH as f32 - 2.0 * PADDING;
+    let label_width = 360.0;
+    let bar_x = PADDING + label_width + 24.0;
+    let bar_width = content_width - label_width - 24.0 - 160.0;
+    let longest = projects.first().map_or(0, |project| project.seconds).max(1);
+    for (i, project) in projects.iter().take(TOP_PROJECTS).enumerate() {
+        let y = PADDING + 270.0 + i as f32 * 72.0;
+        let name = fit(&regular, 32.0, &project.name, label_width);
+        draw_text(&mut pixmap, &regular, 32.0, PADDING, y + 28.0, &name, TEXT);
+        fill_rect(&mut pixmap, bar_x, y + 4.0, bar_width, 28.0, TRACK);
+        let filled = bar_width * project.seconds.max(0) as f32 / longest as f32;
+        fill_rect(&mut pixmap, bar_x, y + 4.0, filled.max(4.0), 28.0, rgb(&project.color));
+        let duration = format_duration(project.seconds);
+        let duration_x = WIDTH as f32 - PADDING - text_width(&regular, 28.0, &duration);
+        draw_text(&mut pixmap, &regular, 28.0, duration_x, y + 28.0, &duration, MUTED);
+    }
+
+    pixmap.encode_png().map_err(|e| format!("failed to encode the image: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fonts() -> Fonts {
+        Fonts {
+            regular: include_bytes!("../../fonts/DejaVuSans.ttf").to_vec(),
+            bold: include_bytes!("../../fonts/DejaVuSans-Bold.ttf").to_vec(),
+        }
+    }
+
+    #[test]
+    fn renders_a_png_card() {
+        let projects = vec![
+            CardProject {
+                name: "Client work with a name far too long to fit next to its bar".to_string(),
+                seconds: 36_000,
+                color: "#3b82f6".to_string(),
+            },
+            CardProject {
+                name: "Admin".to_string(),
+                seconds: 5_400,
+                color: "#ef4444".to_string(),
+            },
+        ];
+        let png = render(&projects, 41_400, &Tz::UTC, (1743465600, 1744070400), &fonts()).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn long_names_are_ellipsized() {
+        let fonts = fonts();
+        let font = FontRef::try_from_slice(&fonts.regular).unwrap();
+        assert_eq!(fit(&font, 32.0, "Admin", 360.0), "Admin");
+        let fitted = fit(&font, 32.0, &"Project ".repeat(20), 360.0);
+        assert!(fitted.ends_with('…'));
+        assert!(text_width(&font, 32.0, &fitted) <= 360.0);
+    }
+}
@@ -0,0 +1,59 @@
+use chrono_tz::Tz;
+
+use crate::entries::Entry;
+use crate::export::finished;
+use crate::timefmt;
+
+const HEADER: &str = "id,project,note,start,end,duration_seconds";
+
+// Quote a field when it contains a delimiter, quote or line break
+pub fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Start/end are local ISO 8601 times with offsets in `tz`; durations come
+// from the UTC timestamps so DST changes never distort them
+pub fn render(entries: &[Entry], tz: &Tz) -> String {
+    let mut out = String::from(HEADER);
+    out.push('\n');
+    for (entry, end_ts) in finished(entries) {
+        let row = [
+            entry.id.to_string(),
+            escape(&entry.project),
+            escape(&entry.note),
+            timefmt::format_rfc3339(entry.start_ts, tz),
+            timefmt::format_rfc3339(end_ts, tz),
+            (end_ts - entry.start_ts).to_string(),
+        ];
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_spanning_spring_forward() {
+        let tz: Tz = "Europe/Berlin".parse().unwrap();
+        // 00:30–01:30 UTC on 2025-03-30, across the 02:00 → 03:00 local jump
+        let entry = Entry {
+            id: 1,
+            project: "Acme, Inc".to_string(),
+            note: "said \"hi\"".to_string(),
+            start_ts: 1743294600,
+            end_ts: Some(1743298200),
+        };
+        let csv = render(&[entry], &tz);
+        assert_eq!(
+            csv.lines().nth(1).unwrap(),
+            "1,\"Acme, Inc\",\"said \"\"hi\"\"\",2025-03-30T01:30:00+01:00,2025-03-30T03:30:00+02:00,3600"
+        );
+    }
+}
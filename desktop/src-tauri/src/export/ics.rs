@@ -0,0 +1,42 @@
+use chrono_tz::Tz;
+
+use crate::entries::Entry;
+use crate::export::finished;
+use crate::timefmt;
+
+// Escape TEXT values per RFC 5545
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+
+// Events use UTC times so they land correctly in any calendar; the configured
+// zone is advertised for clients that display the calendar's own zone
+pub fn render(entries: &[Entry], tz: &Tz, now: i64) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//TimeGrid//TimeGrid//EN".to_string(),
+        format!("X-WR-TIMEZONE:{}", tz.name()),
+    ];
+    let stamp = timefmt::format_ics_utc(now);
+    for (entry, end_ts) in finished(entries) {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:entry-{}@timegrid", entry.id));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("DTSTART:{}", timefmt::format_ics_utc(entry.start_ts)));
+        lines.push(format!("DTEND:{}", timefmt::format_ics_utc(end_ts)));
+        lines.push(format!("SUMMARY:{}", escape(&entry.project)));
+        if !entry.note.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape(&entry.note)));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+    let mut out = lines.join("\r\n");
+    out.push_str("\r\n");
+    out
+}
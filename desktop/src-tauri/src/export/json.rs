@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use chrono_tz::Tz;
+use serde::Serialize;
+
+use crate::entries::Entry;
+use crate::export::finished;
+use crate::timefmt;
+
+// One exported entry: raw timestamps plus local ISO 8601 times for readability
+#[derive(Debug, Serialize)]
+struct JsonEntry<'a> {
+    id: i64,
+    project: &'a str,
+    note: &'a str,
+    tags: &'a [String],
+    start: String,
+    end: String,
+    start_ts: i64,
+    end_ts: i64,
+    duration_seconds: i64,
+}
+
+// `tags` maps entry ids to their tags; entries missing from it have none
+pub fn render(entries: &[Entry], tags: &HashMap<i64, Vec<String>>, tz: &Tz) -> Result<String, String> {
+    let rows: Vec<JsonEntry> = finished(entries)
+        .map(|(entry, end_ts)| JsonEntry {
+            id: entry.id,
+            project: &entry.project,
+            note: &entry.note,
+            tags: tags.get(&entry.id).map(Vec::as_slice).unwrap_or_default(),
+            start: timefmt::format_rfc3339(entry.start_ts, tz),
+            end: timefmt::format_rfc3339(end_ts, tz),
+            start_ts: entry.start_ts,
+            end_ts,
+            duration_seconds: end_ts - entry.start_ts,
+        })
+        .collect();
+    serde_json::to_string_pretty(&rows).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_running_entries() {
+        let tz: Tz = "Europe/Berlin".parse().unwrap();
+        let entry = |id, end_ts| Entry {
+            id,
+            project: "Acme".to_string(),
+            note: String::new(),
+            start_ts: 1743294600,
+            end_ts,
+        };
+        let tags = HashMap::from([(1, vec!["billable".to_string()])]);
+        let json = render(&[entry(1, Some(1743298200)), entry(2, None)], &tags, &tz).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.as_array().unwrap().len(), 1);
+        assert_eq!(parsed[0]["tags"], serde_json::json!(["billable"]));
+        assert_eq!(parsed[0]["end"], "2025-03-30T03:30:00+02:00");
+        assert_eq!(parsed[0]["duration_seconds"], 3600);
+    }
+}
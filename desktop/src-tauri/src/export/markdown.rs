@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+
+use crate::entries::Entry;
+use crate::export::finished;
+use crate::timefmt;
+
+const SPECIAL: &str = "\\`*_{}[]<>()#+-!|~";
+
+// Backslash-escape Markdown syntax so names and notes render literally, and
+// fold line breaks so a note can't break out of its table row
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\r' => {}
+            '\n' => out.push(' '),
+            c if SPECIAL.contains(c) => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// `2h 5m`; seconds are dropped
+pub fn format_duration(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    format!("{}h {}m", seconds / 3600, (seconds % 3600) / 60)
+}
+
+// A report for pasting into tickets and emails: a per-project summary, then
+// one table per local day under that day's note. Entries must be sorted by
+// start time; days with a note but no entries get a section too.
+pub fn render(
+    entries: &[Entry],
+    notes: &BTreeMap<NaiveDate, String>,
+    tz: &Tz,
+    (from_ts, to_ts): (i64, i64),
+    archived_before: Option<i64>,
+) -> String {
+    let mut projects: BTreeMap<&str, i64> = BTreeMap::new();
+    let mut days: Vec<(NaiveDate, Vec<(&Entry, i64)>)> = Vec::new();
+    let mut total = 0;
+    for (entry, end_ts) in finished(entries) {
+        let seconds = (end_ts - entry.start_ts).max(0);
+        *projects.entry(&entry.project).or_default() += seconds;
+        total += seconds;
+        let day = timefmt::to_local(entry.start_ts, tz).date_naive();
+        match days.last_mut() {
+            Some((last, rows)) if *last == day => rows.push((entry, end_ts)),
+            _ => days.push((day, vec![(entry, end_ts)])),
+        }
+    }
+    for day in notes.keys() {
+        if !days.iter().any(|(date, _)| date == day) {
+            days.push((*day, Vec::new()));
+        }
+    }
+    days.sort_by_key(|(day, _)| *day);
+
+    // `to_ts` is exclusive, so label the range with the last included day
+    let mut out = format!(
+        "# Time report: {} – {}\n\n",
+        timefmt::format_local(from_ts, tz, "%Y-%m-%d"),
+        timefmt::format_local(to_ts - 1, tz, "%Y-%m-%d")
+    );
+    if let Some(before) = archived_before {
+        out.push_str(&format!(
+            "_Entries before {} are archived and not included._\n\n",
+            timefmt::format_local(before, tz, "%Y-%m-%d")
+        ));
+    }
+    out.push_str(&format!("**Total:** {}\n\n", format_duration(total)));
+
+    out.push_str("## By project\n\n| Project | Time |\n| --- | ---: |\n");
+    let mut by_time: Vec<(&str, i64)> = projects.into_iter().collect();
+    by_time.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    for (project, seconds) in by_time {
+        out.push_str(&format!("| {} | {} |\n", escape(project), format_duration(seconds)));
+    }
+
+    out.push_str("\n## By day\n");
+    for (day, rows) in days {
+        out.push_str(&format!("\n### {}\n\n", day.format("%A, %Y-%m-%d")));
+        if let Some(note) = notes.get(&day) {
+            for line in note.lines() {
+                out.push_str(&format!("> {}\n", escape(line)));
+            }
+            out.push('\n');
+        }
+        if rows.is_empty() {
+            continue;
+        }
+        out.push_str("| Time | Project | Note | Duration |\n| --- | --- | --- | ---: |\n");
+        let mut day_total = 0;
+        for (entry, end_ts) in rows {
+            let seconds = (end_ts - entry.start_ts).max(0);
+            day_total += seconds;
+            out.push_str(&format!(
+                "| {}–{} | {} | {} | {} |\n",
+                timefmt::format_local(entry.start_ts, tz, "%H:%M"),
+                timefmt::format_local(end_ts, tz, "%H:%M"),
+                escape(&entry.project),
+                escape(&entry.note),
+                format_duration(seconds)
+            ));
+        }
+        out.push_str(&format!("| | | **Day total** | **{}** |\n", format_duration(day_total)));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_syntax_and_groups_by_day() {
+        assert_eq!(escape("a|b *c*\nd"), "a\\|b \\*c\\* d");
+        assert_eq!(format_duration(3600 * 2 + 5 * 60 + 59), "2h 5m");
+
+        let entry = |id, project: &str, note: &str, start_ts: i64, end_ts: i64| Entry {
+            id,
+            project: project.to_string(),
+            note: note.to_string(),
+            start_ts,
+            end_ts: Some(end_ts),
+        };
+        // 2025-06-02 09:00 UTC onwards
+        let entries = [
+            entry(1, "Acme_Co", "fix [bug]", 1748854800, 1748862000),
+            entry(2, "Beta", "", 1748866500, 1748868300),
+            entry(3, "Acme_Co", "", 1748941200, 1748944800),
+        ];
+        let notes = BTreeMap::from([
+            (NaiveDate::from_ymd_opt(2025, 6, 2).unwrap(), "kickoff\n*retro*".to_string()),
+            (NaiveDate::from_ymd_opt(2025, 6, 4).unwrap(), "off sick".to_string()),
+        ]);
+        let md = render(&entries, &notes, &Tz::UTC, (1748822400, 1749081600), None);
+        assert!(md.starts_with("# Time report: 2025-06-02 – 2025-06-04\n"));
+        assert!(md.contains("**Total:** 3h 30m"));
+        assert!(md.contains("| Acme\\_Co | 3h 0m |\n| Beta | 0h 30m |"));
+        assert!(md.contains("| 09:00–11:00 | Acme\\_Co | fix \\[bug\\] | 2h 0m |"));
+        assert!(md.contains("### Monday, 2025-06-02"));
+        assert!(md.contains("| | | **Day total** | **2h 30m** |"));
+        assert!(md.contains("### Tuesday, 2025-06-03"));
+        assert!(md.contains("### Monday, 2025-06-02\n\n> kickoff\n> \\*retro\\*\n\n| Time |"));
+        assert!(md.ends_with("### Wednesday, 2025-06-04\n\n> off sick\n\n"));
+    }
+}
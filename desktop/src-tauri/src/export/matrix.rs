@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use crate::billing;
+use crate::entries::Entry;
+use crate::export::{csv::escape, finished};
+
+fn hours(seconds: i64) -> String {
+    if seconds == 0 {
+        "0".to_string()
+    } else {
+        format!("{:.2}", seconds as f64 / 3600.0)
+    }
+}
+
+// Projects × days grid of hours. Entries crossing midnight are split between
+// days and each cell is rounded up on its own, so the totals add up to the
+// cells shown. Empty cells are written as 0.
+pub fn render(entries: &[Entry], days: &[(NaiveDate, i64, i64)], rounding_minutes: u32) -> String {
+    let mut seconds: BTreeMap<&str, Vec<i64>> = BTreeMap::new();
+    for (entry, end_ts) in finished(entries) {
+        for (i, (_, day_start, day_end)) in days.iter().enumerate() {
+            let overlap = end_ts.min(*day_end) - entry.start_ts.max(*day_start);
+            if overlap > 0 {
+                seconds.entry(&entry.project).or_insert_with(|| vec![0; days.len()])[i] += overlap;
+            }
+        }
+    }
+
+    let mut out = String::from("project");
+    for (date, _, _) in days {
+        out.push_str(&format!(",{}", date.format("%Y-%m-%d")));
+    }
+    out.push_str(",total\n");
+    let mut day_totals = vec![0; days.len()];
+    for (project, cells) in &seconds {
+        let cells: Vec<i64> = cells.iter().map(|cell| billing::round_up(*cell, rounding_minutes)).collect();
+        let mut row = vec![escape(project)];
+        for (i, cell) in cells.iter().enumerate() {
+            day_totals[i] += cell;
+            row.push(hours(*cell));
+        }
+        row.push(hours(cells.iter().sum()));
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    let mut row = vec!["total".to_string()];
+    row.extend(day_totals.iter().map(|total| hours(*total)));
+    row.push(hours(day_totals.iter().sum()));
+    out.push_str(&row.join(","));
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Tz;
+
+    use crate::progress;
+
+    #[test]
+    fn grid_splits_entries_at_midnight_and_rounds_cells() {
+        let tz: Tz = "Europe/Berlin".parse().unwrap();
+        // Monday 2025-03-24 00:00 in Berlin
+        let monday = 1742770800;
+        let days = progress::week_days(&tz, monday + 3600);
+        assert_eq!(days[0].1, monday);
+        let entry = |id, project: &str, start_ts: i64, end_ts: i64| Entry {
+            id,
+            project: project.to_string(),
+            note: String::new(),
+            start_ts,
+            end_ts: Some(end_ts),
+        };
+        let entries = [
+            entry(1, "Acme, Inc", monday + 9 * 3600, monday + 10 * 3600 + 60),
+            // 23:00 Monday to 01:00 Tuesday
+            entry(2, "Beta", monday + 23 * 3600, monday + 25 * 3600),
+        ];
+        let csv = render(&entries, &days, 15);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "project,2025-03-24,2025-03-25,2025-03-26,2025-03-27,2025-03-28,2025-03-29,2025-03-30,total"
+        );
+        assert_eq!(lines[1], "\"Acme, Inc\",1.25,0,0,0,0,0,0,1.25");
+        assert_eq!(lines[2], "Beta,1.00,1.00,0,0,0,0,0,2.00");
+        assert_eq!(lines[3], "total,2.25,1.00,0,0,0,0,0,3.25");
+    }
+}
@@ -0,0 +1,48 @@
+use std::fs;
+use std::path::Path;
+
+use crate::entries::Entry;
+
+pub mod card;
+pub mod csv;
+pub mod ics;
+pub mod json;
+pub mod markdown;
+pub mod matrix;
+pub mod pdf;
+
+// Formats `export_project` can write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl Format {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "markdown" | "md" => Ok(Self::Markdown),
+            _ => Err(format!("unknown export format '{}', expected csv, json or markdown", format)),
+        }
+    }
+}
+
+// Only finished entries are exported; a running timer appears once stopped
+pub fn finished(entries: &[Entry]) -> impl Iterator<Item = (&Entry, i64)> {
+    entries
+        .iter()
+        .filter_map(|entry| entry.end_ts.map(|end_ts| (entry, end_ts)))
+}
+
+pub fn write_file(path: &str, contents: impl AsRef<[u8]>) -> Result<(), String> {
+    let path = Path::new(path);
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            return Err(format!("directory {} does not exist", parent.display()));
+        }
+    }
+    fs::write(path, contents).map_err(|e| format!("failed to write {}: {}", path.display(), e))
+}
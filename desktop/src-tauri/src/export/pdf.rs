@@ -0,0 +1,332 @@
+use std::fs;
+use std::io::Cursor;
+
+use chrono::{NaiveDate, Weekday};
+use chrono_tz::Tz;
+use printpdf::{
+    IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Point,
+};
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+
+use crate::entries::Entry;
+use crate::export::finished;
+use crate::stats::start_of_week;
+use crate::timefmt;
+use crate::timer::format_elapsed;
+
+const REGULAR_FONT: &str = "fonts/DejaVuSans.ttf";
+const BOLD_FONT: &str = "fonts/DejaVuSans-Bold.ttf";
+
+// A4 portrait
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 15.0;
+
+const TEXT_SIZE: f32 = 9.0;
+const LINE_HEIGHT: f32 = 4.5;
+const ROW_GAP: f32 = 1.5;
+
+// Column x positions and widths in mm
+const COL_TIME: f32 = MARGIN;
+const COL_PROJECT: f32 = MARGIN + 28.0;
+const PROJECT_WIDTH: f32 = 42.0;
+const COL_NOTE: f32 = COL_PROJECT + PROJECT_WIDTH + 3.0;
+const NOTE_WIDTH: f32 = 78.0;
+const COL_DURATION: f32 = PAGE_WIDTH - MARGIN - 20.0;
+
+// Rough average glyph width of DejaVu Sans as a fraction of the font size;
+// enough to wrap columns without measuring every glyph
+const AVG_GLYPH_EM: f32 = 0.55;
+const PT_TO_MM: f32 = 0.3528;
+
+pub struct Fonts {
+    pub regular: Vec<u8>,
+    pub bold: Vec<u8>,
+}
+
+// Read the bundled fonts from the app's resource directory
+pub fn load_fonts(app: &AppHandle) -> Result<Fonts, String> {
+    let load = |name: &str| {
+        let path = app
+            .path()
+            .resolve(name, BaseDirectory::Resource)
+            .map_err(|e| format!("could not locate font asset {}: {}", name, e))?;
+        fs::read(&path).map_err(|e| format!("could not load font asset {}: {}", path.display(), e))
+    };
+    Ok(Fonts {
+        regular: load(REGULAR_FONT)?,
+        bold: load(BOLD_FONT)?,
+    })
+}
+
+// Split `text` into lines of at most `max_chars`, breaking at spaces where
+// possible and inside words that are longer than a whole line
+pub fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let max_chars = max_chars.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            let line_len = line.chars().count();
+            if line_len > 0 && line_len + 1 + word.len() <= max_chars {
+                line.push(' ');
+                line.extend(&word);
+                continue;
+            }
+            if line_len > 0 {
+                lines.push(std::mem::take(&mut line));
+            }
+            while word.len() > max_chars {
+                lines.push(word.drain(..max_chars).collect());
+            }
+            line.extend(word);
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+fn chars_for_width(width_mm: f32) -> usize {
+    (width_mm / (TEXT_SIZE * AVG_GLYPH_EM * PT_TO_MM)) as usize
+}
+
+// Writes rows top to bottom and starts a new page whenever the next block
+// would run into the bottom margin
+struct Writer {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    y: f32,
+    page: usize,
+    range_label: String,
+    // Shown under the range when part of it has been archived
+    archive_note: Option<String>,
+}
+
+impl Writer {
+    fn text(&self, text: &str, size: f32, x: f32, bold: bool) {
+        let font = if bold { &self.bold } else { &self.regular };
+        self.layer.use_text(text, size, Mm(x), Mm(self.y), font);
+    }
+
+    fn rule(&self) {
+        let y = self.y + LINE_HEIGHT - 1.0;
+        self.layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(MARGIN), Mm(y)), false),
+                (Point::new(Mm(PAGE_WIDTH - MARGIN), Mm(y)), false),
+            ],
+            is_closed: false,
+        });
+    }
+
+    fn header(&mut self) {
+        self.y = PAGE_HEIGHT - MARGIN;
+        let title = if self.page == 1 { "Timesheet" } else { "Timesheet (continued)" };
+        self.text(title, 16.0, MARGIN, true);
+        self.y -= 7.0;
+        self.text(&self.range_label, 10.0, MARGIN, false);
+        if let Some(note) = &self.archive_note {
+            self.y -= 5.0;
+            self.text(note, TEXT_SIZE, MARGIN, false);
+        }
+        self.y -= 9.0;
+        self.text("Time", TEXT_SIZE, COL_TIME, true);
+        self.text("Project", TEXT_SIZE, COL_PROJECT, true);
+        self.text("Note", TEXT_SIZE, COL_NOTE, true);
+        self.text("Duration", TEXT_SIZE, COL_DURATION, true);
+        self.y -= LINE_HEIGHT;
+        self.rule();
+        self.y -= ROW_GAP;
+    }
+
+    // Make room for a block of `lines` text lines, breaking the page if needed
+    fn reserve(&mut self, lines: usize) {
+        let needed = lines as f32 * LINE_HEIGHT + ROW_GAP;
+        if self.y - needed < MARGIN {
+            let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+            self.layer = self.doc.get_page(page).get_layer(layer);
+            self.page += 1;
+            self.header();
+        }
+    }
+
+    fn total_row(&mut self, label: &str, seconds: i64) {
+        self.reserve(1);
+        self.text(label, TEXT_SIZE, COL_NOTE, true);
+        self.text(&format_elapsed(seconds), TEXT_SIZE, COL_DURATION, true);
+        self.y -= LINE_HEIGHT + ROW_GAP;
+    }
+}
+
+// Render finished entries as a timesheet grouped by local day, with daily and
+// weekly totals. Entries must be sorted by start time. `archived_before` is
+// the archive cutoff when the range reaches into archived data.
+pub fn render(
+    entries: &[Entry],
+    tz: &Tz,
+    week_start: Weekday,
+    (from_ts, to_ts): (i64, i64),
+    archived_before: Option<i64>,
+    fonts: &Fonts,
+) -> Result<Vec<u8>, String> {
+    let (doc, _pages) = layout(entries, tz, week_start, (from_ts, to_ts), archived_before, fonts)?;
+    doc.save_to_bytes()
+        .map_err(|e| format!("failed to render PDF: {}", e))
+}
+
+// Lay out the document, returning it with its page count
+fn layout(
+    entries: &[Entry],
+    tz: &Tz,
+    week_start: Weekday,
+    (from_ts, to_ts): (i64, i64),
+    archived_before: Option<i64>,
+    fonts: &Fonts,
+) -> Result<(PdfDocumentReference, usize), String> {
+    let (doc, page, layer) =
+        PdfDocument::new("Timesheet", Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+    let regular = doc
+        .add_external_font(Cursor::new(&fonts.regular))
+        .map_err(|e| format!("could not load font asset {}: {}", REGULAR_FONT, e))?;
+    let bold = doc
+        .add_external_font(Cursor::new(&fonts.bold))
+        .map_err(|e| format!("could not load font asset {}: {}", BOLD_FONT, e))?;
+    let layer = doc.get_page(page).get_layer(layer);
+
+    // `to_ts` is exclusive, so label the range with the last included day
+    let range_label = format!(
+        "{} – {}",
+        timefmt::format_local(from_ts, tz, "%Y-%m-%d"),
+        timefmt::format_local(to_ts - 1, tz, "%Y-%m-%d")
+    );
+    let mut out = Writer {
+        doc,
+        layer,
+        regular,
+        bold,
+        y: 0.0,
+        page: 1,
+        range_label,
+        archive_note: archived_before.map(|before| {
+            format!(
+                "Entries before {} are archived and not included",
+                timefmt::format_local(before, tz, "%Y-%m-%d")
+            )
+        }),
+    };
+    out.header();
+
+    let project_chars = chars_for_width(PROJECT_WIDTH);
+    let note_chars = chars_for_width(NOTE_WIDTH);
+    let mut day: Option<NaiveDate> = None;
+    let mut week: Option<NaiveDate> = None;
+    let (mut day_total, mut week_total, mut grand_total) = (0, 0, 0);
+
+    for (entry, end_ts) in finished(entries) {
+        let start = timefmt::to_local(entry.start_ts, tz);
+        let entry_day = start.date_naive();
+        let entry_week = start_of_week(entry_day, week_start);
+
+        if day.is_some() && day != Some(entry_day) {
+            out.total_row("Day total", day_total);
+            day_total = 0;
+        }
+        if week.is_some() && week != Some(entry_week) {
+            out.total_row("Week total", week_total);
+            week_total = 0;
+        }
+        if day != Some(entry_day) {
+            out.reserve(2);
+            out.y -= ROW_GAP;
+            out.text(&start.format("%A, %Y-%m-%d").to_string(), TEXT_SIZE, COL_TIME, true);
+            out.y -= LINE_HEIGHT;
+            day = Some(entry_day);
+            week = Some(entry_week);
+        }
+
+        let seconds = (end_ts - entry.start_ts).max(0);
+        let project = wrap(&entry.project, project_chars);
+        let note = wrap(&entry.note, note_chars);
+        let rows = project.len().max(note.len());
+        out.reserve(rows);
+        let time = format!(
+            "{}–{}",
+            start.format("%H:%M"),
+            timefmt::format_local(end_ts, tz, "%H:%M")
+        );
+        out.text(&time, TEXT_SIZE, COL_TIME, false);
+        out.text(&format_elapsed(seconds), TEXT_SIZE, COL_DURATION, false);
+        for i in 0..rows {
+            // A note longer than a page continues on the next one
+            if i > 0 {
+                out.reserve(1);
+            }
+            if let Some(line) = project.get(i) {
+                out.text(line, TEXT_SIZE, COL_PROJECT, false);
+            }
+            if let Some(line) = note.get(i) {
+                out.text(line, TEXT_SIZE, COL_NOTE, false);
+            }
+            out.y -= LINE_HEIGHT;
+        }
+        out.y -= ROW_GAP;
+
+        day_total += seconds;
+        week_total += seconds;
+        grand_total += seconds;
+    }
+
+    if day.is_some() {
+        out.total_row("Day total", day_total);
+        out.total_row("Week total", week_total);
+    }
+    out.total_row("Total", grand_total);
+
+    Ok((out.doc, out.page))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_breaks_on_spaces_and_long_words() {
+        assert_eq!(wrap("a bb ccc", 4), vec!["a bb", "ccc"]);
+        assert_eq!(wrap("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("one\ntwo", 10), vec!["one", "two"]);
+        assert_eq!(wrap("", 10), vec![""]);
+    }
+
+    #[test]
+    fn render_paginates_long_timesheets() {
+        let fonts = Fonts {
+            regular: include_bytes!("../../fonts/DejaVuSans.ttf").to_vec(),
+            bold: include_bytes!("../../fonts/DejaVuSans-Bold.ttf").to_vec(),
+        };
+        let note = "Reviewed the quarterly report with the client and agreed on next steps ".repeat(4);
+        let entries: Vec<Entry> = (0..80)
+            .map(|i| Entry {
+                id: i,
+                project: "Acme".to_string(),
+                note: note.clone(),
+                start_ts: 1743465600 + i * 7200,
+                end_ts: Some(1743465600 + i * 7200 + 3600),
+            })
+            .collect();
+        let (from_ts, to_ts) = (1743465600, 1744070400);
+        let range = (from_ts, to_ts);
+        let (_, pages) = layout(&entries, &Tz::UTC, Weekday::Mon, range, None, &fonts).unwrap();
+        assert!(pages > 1);
+        let pdf = render(&entries, &Tz::UTC, Weekday::Mon, range, Some(from_ts), &fonts).unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager};
+
+use crate::db::Database;
+use crate::settings;
+use crate::widget;
+
+pub const DND_SETTING: &str = "focus_mode_dnd";
+
+// macOS Shortcuts the user creates to turn a Focus on and off; Focus has no
+// public API, so the Shortcuts CLI is the supported scripting bridge
+#[cfg(target_os = "macos")]
+const DND_ON_SHORTCUT: &str = "TimeGrid Focus On";
+#[cfg(target_os = "macos")]
+const DND_OFF_SHORTCUT: &str = "TimeGrid Focus Off";
+
+// Visibility of every window when focus mode was entered, keyed by label.
+// `None` while focus mode is off.
+#[derive(Default)]
+pub struct FocusMode(Mutex<Option<HashMap<String, bool>>>);
+
+impl FocusMode {
+    pub fn is_active(&self) -> bool {
+        self.0.lock().map(|saved| saved.is_some()).unwrap_or(false)
+    }
+}
+
+fn dnd_enabled(app: &AppHandle) -> bool {
+    app.state::<Database>()
+        .lock()
+        .and_then(|conn| settings::get(&conn, DND_SETTING))
+        .ok()
+        .flatten()
+        .unwrap_or(false)
+}
+
+// Runs in the background: a shortcut can take seconds, and this is called
+// from the main thread
+#[cfg(target_os = "macos")]
+fn set_do_not_disturb(on: bool) {
+    let shortcut = if on { DND_ON_SHORTCUT } else { DND_OFF_SHORTCUT };
+    tauri::async_runtime::spawn_blocking(move || {
+        match std::process::Command::new("shortcuts")
+            .args(["run", shortcut])
+            .status()
+        {
+            Ok(status) if status.success() => {}
+            Ok(_) => log::warn!("shortcut '{}' failed; create it in the Shortcuts app", shortcut),
+            Err(e) => log::warn!("failed to run shortcut '{}': {}", shortcut, e),
+        }
+    });
+}
+
+#[cfg(not(target_os = "macos"))]
+fn set_do_not_disturb(_on: bool) {}
+
+// Enter focus mode: remember which windows are visible, hide everything but
+// the widget and show the widget. Leaving puts every remembered window back
+// exactly as it was on entry, whatever was toggled in between.
+pub fn toggle(app: &AppHandle) -> Result<bool, String> {
+    let state = app.state::<FocusMode>();
+    // Never hold the lock across window calls: the widget's blur handler reads it
+    let lock = || state.0.lock().map_err(|_| "focus mode lock poisoned".to_string());
+    let saved = lock()?.take();
+
+    match saved {
+        None => {
+            let windows = app.webview_windows();
+            let visibility = windows
+                .iter()
+                .map(|(label, window)| (label.clone(), window.is_visible().unwrap_or(false)))
+                .collect();
+            // Mark focus mode active first so the widget isn't auto-hidden meanwhile
+            *lock()? = Some(visibility);
+            for (label, window) in &windows {
+                if label != widget::WIDGET_LABEL {
+                    window.hide().map_err(|e| e.to_string())?;
+                }
+            }
+            widget::show(app);
+            if dnd_enabled(app) {
+                set_do_not_disturb(true);
+            }
+            Ok(true)
+        }
+        Some(visibility) => {
+            for (label, visible) in visibility {
+                let Some(window) = app.get_webview_window(&label) else {
+                    continue;
+                };
+                match (label == widget::WIDGET_LABEL, visible) {
+                    (true, true) => widget::show(app),
+                    (true, false) => widget::hide(&window),
+                    (false, true) => window.show().map_err(|e| e.to_string())?,
+                    (false, false) => window.hide().map_err(|e| e.to_string())?,
+                }
+            }
+            if dnd_enabled(app) {
+                set_do_not_disturb(false);
+            }
+            Ok(false)
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use chrono::{NaiveDate, NaiveTime, TimeZone};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::recurrence::parse_hhmm;
+use crate::settings;
+
+// Local working day as {"start": "HH:MM", "end": "HH:MM"}; gaps outside it are ignored
+pub const WORKING_HOURS_SETTING: &str = "working_hours";
+// Refuse to start timers outside the working hours and stop running ones
+// when they end, instead of only warning
+pub const BLOCK_OUTSIDE_HOURS_SETTING: &str = "block_outside_hours";
+
+pub const OUTSIDE_HOURS_EVENT: &str = "outside-hours";
+
+// Longest range searched for gaps, about a year
+const MAX_RANGE_SECONDS: i64 = 366 * 86_400;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Gap {
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub duration_seconds: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkingHours {
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutsideHoursAction {
+    // A timer was started outside the working hours
+    Warned,
+    // A running timer was stopped when the working hours ended
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutsideHours {
+    pub project: String,
+    pub hours: WorkingHours,
+    pub action: OutsideHoursAction,
+}
+
+impl WorkingHours {
+    pub fn new(start_hhmm: &str, end_hhmm: &str) -> Result<Self, String> {
+        let (start, end) = (parse_hhmm(start_hhmm)?, parse_hhmm(end_hhmm)?);
+        if start >= end {
+            return Err("working hours must end after they start".to_string());
+        }
+        Ok(Self {
+            start: start.format("%H:%M").to_string(),
+            end: end.format("%H:%M").to_string(),
+        })
+    }
+
+    fn bounds(&self) -> Result<(NaiveTime, NaiveTime), String> {
+        Ok((parse_hhmm(&self.start)?, parse_hhmm(&self.end)?))
+    }
+
+    // Whether `ts` falls within the working hours of its local day
+    pub fn contains<Tz: TimeZone>(&self, tz: &Tz, ts: i64) -> Result<bool, String> {
+        let (open, close) = self.bounds()?;
+        let time = local_datetime(tz, ts)?.time();
+        Ok(open <= time && time < close)
+    }
+
+    // The latest end of the working hours at or before `ts`
+    pub fn last_close<Tz: TimeZone>(&self, tz: &Tz, ts: i64) -> Result<Option<i64>, String> {
+        let (_, close) = self.bounds()?;
+        let today = local_datetime(tz, ts)?.date_naive();
+        Ok(match local_ts(tz, today, close) {
+            Some(close_ts) if close_ts <= ts => Some(close_ts),
+            _ => today.pred_opt().and_then(|yesterday| local_ts(tz, yesterday, close)),
+        })
+    }
+}
+
+pub fn working_hours(conn: &Connection) -> Result<Option<WorkingHours>, String> {
+    settings::get(conn, WORKING_HOURS_SETTING)
+}
+
+pub fn block_outside_hours(conn: &Connection) -> Result<bool, String> {
+    Ok(settings::get(conn, BLOCK_OUTSIDE_HOURS_SETTING)?.unwrap_or(false))
+}
+
+pub fn set_block_outside_hours(conn: &Connection, block: bool) -> Result<(), String> {
+    settings::set(conn, BLOCK_OUTSIDE_HOURS_SETTING, &block)
+}
+
+// The working hours when they are set and `ts` lies outside them
+pub fn outside_hours<Tz: TimeZone>(conn: &Connection, tz: &Tz, ts: i64) -> Result<Option<WorkingHours>, String> {
+    match working_hours(conn)? {
+        Some(hours) if !hours.contains(tz, ts)? => Ok(Some(hours)),
+        _ => Ok(None),
+    }
+}
+
+// `None` removes the working hours so whole days are searched again
+pub fn set_working_hours(conn: &Connection, hours: Option<&WorkingHours>) -> Result<(), String> {
+    match hours {
+        Some(hours) => settings::set(conn, WORKING_HOURS_SETTING, hours),
+        None => settings::remove(conn, WORKING_HOURS_SETTING),
+    }
+}
+
+fn local_datetime<Tz: TimeZone>(tz: &Tz, ts: i64) -> Result<chrono::DateTime<Tz>, String> {
+    tz.timestamp_opt(ts, 0)
+        .single()
+        .ok_or_else(|| format!("invalid timestamp {}", ts))
+}
+
+fn local_ts<Tz: TimeZone>(tz: &Tz, date: NaiveDate, time: NaiveTime) -> Option<i64> {
+    tz.from_local_datetime(&date.and_time(time))
+        .earliest()
+        .map(|dt| dt.timestamp())
+}
+
+// Split a span into its parts inside the working hours of each local day
+fn clip_to_hours<Tz: TimeZone>(
+    tz: &Tz,
+    (start_ts, end_ts): (i64, i64),
+    (open, close): (NaiveTime, NaiveTime),
+) -> Result<Vec<(i64, i64)>, String> {
+    let date = |ts: i64| local_datetime(tz, ts).map(|dt| dt.date_naive());
+    let mut spans = Vec::new();
+    let mut day = Some(date(start_ts)?);
+    let last = date(end_ts)?;
+    while let Some(current) = day.filter(|day| *day <= last) {
+        if let (Some(from), Some(to)) = (local_ts(tz, current, open), local_ts(tz, current, close)) {
+            let (from, to) = (from.max(start_ts), to.min(end_ts));
+            if from < to {
+                spans.push((from, to));
+            }
+        }
+        day = current.succ_opt();
+    }
+    Ok(spans)
+}
+
+// Untracked spans in [from_ts, to_ts) longer than `min_gap_seconds`, ignoring
+// the future and, when given, anything outside the working hours. Running
+// entries count as tracked up to `now`.
+pub fn find_gaps<Tz: TimeZone>(
+    conn: &Connection,
+    tz: &Tz,
+    from_ts: i64,
+    to_ts: i64,
+    min_gap_seconds: u64,
+    hours: Option<&WorkingHours>,
+    now: i64,
+) -> Result<Vec<Gap>, String> {
+    if from_ts >= to_ts {
+        return Err(format!("empty time range {}..{}", from_ts, to_ts));
+    }
+    if to_ts - from_ts > MAX_RANGE_SECONDS {
+        return Err("gap search is limited to one year".to_string());
+    }
+    let to_ts = to_ts.min(now);
+    let mut stmt = conn
+        .prepare(
+            "SELECT start_ts, COALESCE(end_ts, ?3) FROM entries
+             WHERE start_ts < ?2 AND COALESCE(end_ts, ?3) > ?1
+             ORDER BY start_ts",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_ts, to_ts, now], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))
+        .map_err(|e| e.to_string())?;
+
+    let mut spans = Vec::new();
+    let mut cursor = from_ts;
+    for row in rows {
+        let (start_ts, end_ts) = row.map_err(|e| e.to_string())?;
+        if start_ts > cursor {
+            spans.push((cursor, start_ts));
+        }
+        cursor = cursor.max(end_ts);
+    }
+    if cursor < to_ts {
+        spans.push((cursor, to_ts));
+    }
+
+    if let Some(hours) = hours {
+        let bounds = hours.bounds()?;
+        spans = spans
+            .into_iter()
+            .map(|span| clip_to_hours(tz, span, bounds))
+            .collect::<Result<Vec<_>, _>>()?
+            .concat();
+    }
+    Ok(spans
+        .into_iter()
+        .filter(|(start_ts, end_ts)| end_ts - start_ts > min_gap_seconds as i64)
+        .map(|(start_ts, end_ts)| Gap {
+            start_ts,
+            end_ts,
+            duration_seconds: end_ts - start_ts,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    use crate::{db, entries};
+
+    fn ts(d: u32, h: u32, m: u32) -> i64 {
+        Utc.with_ymd_and_hms(2025, 3, d, h, m, 0).unwrap().timestamp()
+    }
+
+    #[test]
+    fn gaps_between_entries_within_working_hours() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        entries::insert_entry(&conn, "Acme", "", ts(3, 9, 0), Some(ts(3, 10, 0))).unwrap();
+        // Overlaps the first entry, so no gap between them
+        entries::insert_entry(&conn, "Acme", "", ts(3, 9, 30), Some(ts(3, 11, 0))).unwrap();
+        entries::insert_entry(&conn, "Acme", "", ts(3, 11, 3), Some(ts(3, 12, 0))).unwrap();
+        entries::insert_entry(&conn, "Acme", "", ts(3, 14, 0), None).unwrap();
+
+        let gaps = find_gaps(&conn, &Utc, ts(3, 0, 0), ts(4, 0, 0), 300, None, ts(3, 15, 0)).unwrap();
+        let spans: Vec<(i64, i64)> = gaps.iter().map(|gap| (gap.start_ts, gap.end_ts)).collect();
+        // The three-minute gap is too short, and the running entry covers the rest of the day so far
+        assert_eq!(spans, vec![(ts(3, 0, 0), ts(3, 9, 0)), (ts(3, 12, 0), ts(3, 14, 0))]);
+
+        let hours = WorkingHours::new("08:00", "18:00").unwrap();
+        let gaps = find_gaps(&conn, &Utc, ts(3, 0, 0), ts(5, 0, 0), 300, Some(&hours), ts(4, 9, 0)).unwrap();
+        let spans: Vec<(i64, i64)> = gaps.iter().map(|gap| (gap.start_ts, gap.end_ts)).collect();
+        assert_eq!(
+            spans,
+            vec![(ts(3, 8, 0), ts(3, 9, 0)), (ts(3, 12, 0), ts(3, 14, 0))]
+        );
+        assert!(WorkingHours::new("18:00", "08:00").is_err());
+    }
+
+    #[test]
+    fn working_hours_contain_and_last_close() {
+        let hours = WorkingHours::new("08:00", "18:00").unwrap();
+        assert!(hours.contains(&Utc, ts(3, 8, 0)).unwrap());
+        assert!(!hours.contains(&Utc, ts(3, 18, 0)).unwrap());
+        assert!(!hours.contains(&Utc, ts(3, 7, 59)).unwrap());
+        assert_eq!(hours.last_close(&Utc, ts(3, 20, 0)).unwrap(), Some(ts(3, 18, 0)));
+        assert_eq!(hours.last_close(&Utc, ts(4, 6, 0)).unwrap(), Some(ts(3, 18, 0)));
+    }
+}
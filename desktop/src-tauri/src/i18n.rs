@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+pub const LOCALE_SETTING: &str = "locale";
+const FALLBACK: &str = "en";
+
+// Translation tables for menu and tray strings, embedded at compile time
+const TABLES: &[(&str, &str)] = &[
+    ("en", include_str!("locales/en.json")),
+    ("de", include_str!("locales/de.json")),
+    ("tr", include_str!("locales/tr.json")),
+];
+
+pub struct I18n {
+    tables: HashMap<&'static str, HashMap<String, String>>,
+    language: RwLock<&'static str>,
+}
+
+// Map a locale code such as `de-DE` or `tr_TR` to a bundled table
+fn resolve_language(code: &str) -> Option<&'static str> {
+    let language = code
+        .split(['-', '_', '.'])
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    TABLES
+        .iter()
+        .map(|(lang, _)| *lang)
+        .find(|lang| *lang == language)
+}
+
+// OS locale, used when the user hasn't chosen one
+pub fn system_locale() -> String {
+    sys_locale::get_locale().unwrap_or_else(|| FALLBACK.to_string())
+}
+
+impl I18n {
+    pub fn new(code: &str) -> Self {
+        let tables = TABLES
+            .iter()
+            .map(|(lang, raw)| {
+                let table = serde_json::from_str(raw).expect("bundled locale table is valid JSON");
+                (*lang, table)
+            })
+            .collect();
+        Self {
+            tables,
+            language: RwLock::new(resolve_language(code).unwrap_or(FALLBACK)),
+        }
+    }
+
+    pub fn set_locale(&self, code: &str) -> Result<&'static str, String> {
+        let language = resolve_language(code).ok_or_else(|| format!("unsupported locale '{}'", code))?;
+        if let Ok(mut current) = self.language.write() {
+            *current = language;
+        }
+        Ok(language)
+    }
+
+    pub fn language(&self) -> &'static str {
+        self.language.read().map(|lang| *lang).unwrap_or(FALLBACK)
+    }
+
+    // Look up a string in the current language, falling back to English and
+    // finally to the key itself
+    pub fn t(&self, key: &str) -> String {
+        [self.language(), FALLBACK]
+            .iter()
+            .find_map(|lang| self.tables.get(lang).and_then(|table| table.get(key)))
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+}
@@ -0,0 +1,205 @@
+use chrono::{NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use rusqlite::Connection;
+use serde::Deserialize;
+
+use crate::entries;
+use crate::import::{parse_csv, toggl, ImportSummary};
+
+// A column by 0-based index or by its header text (case-insensitive)
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum Column {
+    Index(usize),
+    Header(String),
+}
+
+// Where each field is in a CSV from some other time tracker. Exactly one of
+// `end` and `duration` is required; durations are "HH:MM:SS" or decimal hours.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ColumnMapping {
+    pub project: Column,
+    pub start: Column,
+    pub end: Option<Column>,
+    pub duration: Option<Column>,
+    pub note: Option<Column>,
+    // chrono format of the start and end cells, e.g. "%d.%m.%Y %H:%M"
+    pub date_format: String,
+    // Whether the first row holds column names; required to map by header
+    pub has_header: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Stop {
+    End(usize),
+    Duration(usize),
+}
+
+struct Columns {
+    project: usize,
+    start: usize,
+    stop: Stop,
+    note: Option<usize>,
+}
+
+impl Columns {
+    fn resolve(mapping: &ColumnMapping, header: Option<&[String]>) -> Result<Self, String> {
+        let index = |column: &Column| match (column, header) {
+            (Column::Index(i), _) => Ok(*i),
+            (Column::Header(name), Some(header)) => header
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| format!("missing '{}' column", name)),
+            (Column::Header(name), None) => Err(format!("column '{}' is mapped by name but the file has no header", name)),
+        };
+        let stop = match (&mapping.end, &mapping.duration) {
+            (Some(end), None) => Stop::End(index(end)?),
+            (None, Some(duration)) => Stop::Duration(index(duration)?),
+            _ => return Err("map exactly one of end and duration".to_string()),
+        };
+        Ok(Self {
+            project: index(&mapping.project)?,
+            start: index(&mapping.start)?,
+            stop,
+            note: mapping.note.as_ref().map(index).transpose()?,
+        })
+    }
+}
+
+fn parse_duration(value: &str) -> Option<i64> {
+    toggl::parse_duration(value).or_else(|| {
+        let hours: f64 = value.trim().parse().ok()?;
+        (hours.is_finite() && hours >= 0.0).then(|| (hours * 3600.0).round() as i64)
+    })
+}
+
+fn local_ts(value: &str, format: &str, tz: &Tz) -> Result<i64, String> {
+    let datetime = NaiveDateTime::parse_from_str(value, format)
+        .map_err(|_| format!("'{}' does not match the date format", value))?;
+    tz.from_local_datetime(&datetime)
+        .earliest()
+        .map(|dt| dt.timestamp())
+        .ok_or_else(|| format!("{} does not exist in {}", datetime, tz.name()))
+}
+
+// Insert every row that parses with `mapping` in one transaction, reading
+// local times in `tz`. `progress` is told (processed, total) after each row;
+// an error from it aborts the import and rolls everything back.
+pub fn import(
+    conn: &mut Connection,
+    text: &str,
+    mapping: &ColumnMapping,
+    tz: &Tz,
+    progress: &mut dyn FnMut(u64, u64) -> Result<(), String>,
+) -> Result<(ImportSummary, Vec<i64>), String> {
+    let mut rows = parse_csv(text).into_iter();
+    let header = if mapping.has_header {
+        Some(rows.next().ok_or("the file is empty")?)
+    } else {
+        None
+    };
+    let columns = Columns::resolve(mapping, header.as_deref())?;
+    let first_line = if mapping.has_header { 2 } else { 1 };
+
+    let field = |row: &[String], index: usize| row.get(index).map(|f| f.trim().to_string()).unwrap_or_default();
+    let mut records = Vec::new();
+    let mut skipped_reasons = Vec::new();
+    for (index, row) in rows.enumerate() {
+        let line = index + first_line;
+        let project = field(&row, columns.project);
+        if project.is_empty() {
+            skipped_reasons.push(format!("line {}: no project", line));
+            continue;
+        }
+        let bounds = local_ts(&field(&row, columns.start), &mapping.date_format, tz).and_then(|start_ts| {
+            let end_ts = match columns.stop {
+                Stop::End(i) => local_ts(&field(&row, i), &mapping.date_format, tz)?,
+                Stop::Duration(i) => {
+                    let value = field(&row, i);
+                    let seconds = parse_duration(&value).ok_or_else(|| format!("invalid duration '{}'", value))?;
+                    start_ts
+                        .checked_add(seconds)
+                        .ok_or_else(|| format!("duration '{}' is too long", value))?
+                }
+            };
+            if end_ts < start_ts {
+                return Err("ends before it starts".to_string());
+            }
+            Ok((start_ts, end_ts))
+        });
+        match bounds {
+            Ok((start_ts, end_ts)) => records.push((
+                project,
+                columns.note.map(|i| field(&row, i)).unwrap_or_default(),
+                start_ts,
+                end_ts,
+            )),
+            Err(e) => skipped_reasons.push(format!("line {}: {}", line, e)),
+        }
+    }
+
+    let total = records.len() as u64;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut ids = Vec::with_capacity(records.len());
+    for (project, note, start_ts, end_ts) in &records {
+        ids.push(entries::insert_entry(&tx, project, note, *start_ts, Some(*end_ts))?.id);
+        progress(ids.len() as u64, total)?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    let summary = ImportSummary {
+        imported: ids.len(),
+        skipped: skipped_reasons.len(),
+        skipped_reasons,
+    };
+    Ok((summary, ids))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn imports_by_header_or_index_and_reports_skipped_rows() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let text = "Client,Began,Hours,What\n\
+            Acme,2025-01-15 09:00,1.5,\"Design, round 2\"\n\
+            Acme,15.01.2025 11:00,1,Wrong format\n\
+            Beta,2025-01-15 13:00,00:45:00,Call\n\
+            Beta,2025-01-15 14:00,soon,Bad duration\n\
+            ,2025-01-15 15:00,1,No client\n\
+            Beta,2025-01-15 16:00,1e300,Forever\n";
+        let mut mapping = ColumnMapping {
+            project: Column::Header("client".to_string()),
+            start: Column::Header("Began".to_string()),
+            end: None,
+            duration: Some(Column::Index(2)),
+            note: Some(Column::Index(3)),
+            date_format: "%Y-%m-%d %H:%M".to_string(),
+            has_header: true,
+        };
+        let (summary, ids) = import(&mut conn, text, &mapping, &Tz::UTC, &mut |_, _| Ok(())).unwrap();
+        assert_eq!((summary.imported, summary.skipped), (2, 4));
+        assert_eq!(
+            summary.skipped_reasons,
+            vec![
+                "line 3: '15.01.2025 11:00' does not match the date format",
+                "line 5: invalid duration 'soon'",
+                "line 6: no project",
+                "line 7: duration '1e300' is too long",
+            ]
+        );
+        let first = entries::get_entry(&conn, ids[0]).unwrap();
+        assert_eq!((first.project.as_str(), first.note.as_str()), ("Acme", "Design, round 2"));
+        // 2025-01-15 09:00 UTC for an hour and a half
+        assert_eq!((first.start_ts, first.end_ts), (1736931600, Some(1736937000)));
+        assert_eq!(entries::get_entry(&conn, ids[1]).unwrap().end_ts, Some(1736946000 + 2700));
+
+        mapping.end = Some(Column::Index(2));
+        assert!(import(&mut conn, text, &mapping, &Tz::UTC, &mut |_, _| Ok(())).is_err());
+        mapping.end = None;
+        mapping.has_header = false;
+        assert!(import(&mut conn, text, &mapping, &Tz::UTC, &mut |_, _| Ok(())).is_err());
+    }
+}
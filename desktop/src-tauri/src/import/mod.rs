@@ -0,0 +1,188 @@
+use std::fs;
+
+use chrono_tz::Tz;
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::settings;
+
+pub mod mapped;
+pub mod toggl;
+
+// Parsed records included in a preview
+const SAMPLE_SIZE: usize = 5;
+// Warning messages included in a preview; the counts cover every row
+const MAX_WARNINGS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportKind {
+    TogglCsv,
+    Settings,
+}
+
+impl ImportKind {
+    pub fn parse(kind: &str) -> Result<Self, String> {
+        match kind {
+            "toggl_csv" => Ok(Self::TogglCsv),
+            "settings" => Ok(Self::Settings),
+            other => Err(format!("unknown import kind '{}', expected toggl_csv or settings", other)),
+        }
+    }
+}
+
+// What an import would do, without writing anything. Rows with warnings are
+// imported; invalid rows are skipped.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportPreview {
+    pub valid_rows: usize,
+    pub warning_rows: usize,
+    pub invalid_rows: usize,
+    pub date_format: Option<String>,
+    pub warnings: Vec<String>,
+    pub sample: Vec<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub skipped: usize,
+    // Why each skipped row or key was left out, e.g. "line 4: invalid date"
+    pub skipped_reasons: Vec<String>,
+}
+
+pub fn read_file(path: &str) -> Result<String, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+    // Spreadsheet exports often start with a byte order mark
+    Ok(contents.trim_start_matches('\u{feff}').to_string())
+}
+
+// Rows of a comma-separated file with RFC 4180 quoting. Blank lines are skipped.
+pub fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') => quoted = true,
+            (false, ',') => row.push(std::mem::take(&mut field)),
+            (false, '\r') => {}
+            (false, '\n') => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|f| !f.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                } else {
+                    row.clear();
+                }
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    row.push(field);
+    if row.iter().any(|f| !f.is_empty()) {
+        rows.push(row);
+    }
+    rows
+}
+
+// A settings file is a JSON object of setting keys and values
+pub fn parse_settings(text: &str) -> Result<Map<String, Value>, String> {
+    match serde_json::from_str(text).map_err(|e| format!("invalid settings file: {}", e))? {
+        Value::Object(map) => Ok(map),
+        _ => Err("invalid settings file: expected a JSON object".to_string()),
+    }
+}
+
+pub fn preview(kind: ImportKind, text: &str, tz: &Tz) -> Result<ImportPreview, String> {
+    match kind {
+        ImportKind::TogglCsv => {
+            let parsed = toggl::parse(text, tz)?;
+            let mut preview = ImportPreview {
+                valid_rows: 0,
+                warning_rows: 0,
+                invalid_rows: parsed.invalid.len(),
+                date_format: parsed.date_format.map(str::to_string),
+                warnings: Vec::new(),
+                sample: Vec::new(),
+            };
+            for (line, message) in &parsed.invalid {
+                preview.warnings.push(format!("line {}: skipped, {}", line, message));
+            }
+            for row in &parsed.rows {
+                if row.warnings.is_empty() {
+                    preview.valid_rows += 1;
+                } else {
+                    preview.warning_rows += 1;
+                    for message in &row.warnings {
+                        preview.warnings.push(format!("line {}: {}", row.line, message));
+                    }
+                }
+            }
+            preview.warnings.truncate(MAX_WARNINGS);
+            preview.sample = parsed
+                .rows
+                .iter()
+                .take(SAMPLE_SIZE)
+                .map(|row| serde_json::to_value(&row.record).map_err(|e| e.to_string()))
+                .collect::<Result<_, _>>()?;
+            Ok(preview)
+        }
+        ImportKind::Settings => {
+            let map = parse_settings(text)?;
+            let (valid, invalid): (Vec<_>, Vec<_>) = map
+                .iter()
+                .partition(|(key, value)| settings::check_preference(key, value).is_ok());
+            Ok(ImportPreview {
+                valid_rows: valid.len(),
+                warning_rows: 0,
+                invalid_rows: invalid.len(),
+                date_format: None,
+                warnings: invalid
+                    .iter()
+                    .take(MAX_WARNINGS)
+                    .filter_map(|(key, value)| settings::check_preference(key, value).err())
+                    .map(|e| format!("skipped: {}", e))
+                    .collect(),
+                sample: valid
+                    .into_iter()
+                    .take(SAMPLE_SIZE)
+                    .map(|(key, value)| serde_json::json!({ "key": key, "value": value }))
+                    .collect(),
+            })
+        }
+    }
+}
+
+// Write every preference from the file in one transaction; app state and
+// values of the wrong type are skipped
+pub fn import_settings(conn: &mut Connection, text: &str) -> Result<ImportSummary, String> {
+    let map = parse_settings(text)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut summary = ImportSummary {
+        imported: 0,
+        skipped: 0,
+        skipped_reasons: Vec::new(),
+    };
+    for (key, value) in &map {
+        match settings::check_preference(key, value) {
+            Ok(()) => {
+                settings::set(&tx, key, value)?;
+                summary.imported += 1;
+            }
+            Err(e) => {
+                summary.skipped += 1;
+                summary.skipped_reasons.push(e);
+            }
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
@@ -0,0 +1,245 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use chrono_tz::Tz;
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::entries;
+use crate::import::{parse_csv, ImportSummary};
+use crate::tags::{self, BILLABLE_TAG};
+
+// Date layouts seen in Toggl exports across locales, tried in this order
+const DATE_FORMATS: [&str; 4] = ["%Y-%m-%d", "%m/%d/%Y", "%d/%m/%Y", "%d.%m.%Y"];
+// A Duration column further off than this from end - start gets a warning
+const DURATION_TOLERANCE_SECONDS: i64 = 60;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportRecord {
+    pub project: String,
+    pub note: String,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParsedRow {
+    // 1-based line in the file, counting the header
+    pub line: usize,
+    pub record: ImportRecord,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Parsed {
+    pub rows: Vec<ParsedRow>,
+    pub invalid: Vec<(usize, String)>,
+    pub date_format: Option<&'static str>,
+}
+
+struct Columns {
+    project: Option<usize>,
+    description: Option<usize>,
+    start_date: usize,
+    start_time: usize,
+    end_date: usize,
+    end_time: usize,
+    duration: Option<usize>,
+    tags: Option<usize>,
+    billable: Option<usize>,
+}
+
+impl Columns {
+    fn find(header: &[String]) -> Result<Self, String> {
+        let position = |name: &str| header.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+        let required = |name: &str| position(name).ok_or_else(|| format!("missing '{}' column", name));
+        Ok(Self {
+            project: position("Project"),
+            description: position("Description"),
+            start_date: required("Start date")?,
+            start_time: required("Start time")?,
+            end_date: required("End date")?,
+            end_time: required("End time")?,
+            duration: position("Duration"),
+            tags: position("Tags"),
+            billable: position("Billable"),
+        })
+    }
+}
+
+// The first format every date in the file parses with
+fn detect_date_format(dates: &[&str]) -> Option<&'static str> {
+    DATE_FORMATS.into_iter().find(|format| {
+        dates
+            .iter()
+            .all(|date| NaiveDate::parse_from_str(date, format).is_ok())
+    })
+}
+
+// "HH:MM:SS" as seconds
+pub fn parse_duration(value: &str) -> Option<i64> {
+    let mut parts = value.trim().split(':').map(|part| part.parse::<i64>().ok());
+    let (h, m, s) = (parts.next()??, parts.next()??, parts.next()??);
+    h.checked_mul(3600)?.checked_add(m.checked_mul(60)?)?.checked_add(s)
+}
+
+fn local_ts(date: &str, time: &str, format: &str, tz: &Tz) -> Result<i64, String> {
+    let date = NaiveDate::parse_from_str(date.trim(), format).map_err(|_| format!("invalid date '{}'", date))?;
+    let time = NaiveTime::parse_from_str(time.trim(), "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(time.trim(), "%H:%M"))
+        .map_err(|_| format!("invalid time '{}'", time))?;
+    tz.from_local_datetime(&NaiveDateTime::new(date, time))
+        .earliest()
+        .map(|dt| dt.timestamp())
+        .ok_or_else(|| format!("{} {} does not exist in {}", date, time, tz.name()))
+}
+
+// Parse a Toggl Track detailed CSV export, reading local times in `tz`
+pub fn parse(text: &str, tz: &Tz) -> Result<Parsed, String> {
+    let mut rows = parse_csv(text).into_iter();
+    let header = rows.next().ok_or("the file is empty")?;
+    let columns = Columns::find(&header)?;
+    let rows: Vec<Vec<String>> = rows.collect();
+
+    let field = |row: &[String], index: usize| row.get(index).map(|f| f.trim().to_string()).unwrap_or_default();
+    let dates: Vec<String> = rows
+        .iter()
+        .flat_map(|row| [field(row, columns.start_date), field(row, columns.end_date)])
+        .filter(|date| !date.is_empty())
+        .collect();
+    let date_format = detect_date_format(&dates.iter().map(String::as_str).collect::<Vec<_>>());
+
+    let mut parsed = Parsed {
+        date_format,
+        ..Default::default()
+    };
+    for (index, row) in rows.iter().enumerate() {
+        let line = index + 2;
+        let Some(format) = date_format else {
+            parsed.invalid.push((line, "unrecognized date format".to_string()));
+            continue;
+        };
+        let bounds = local_ts(&field(row, columns.start_date), &field(row, columns.start_time), format, tz)
+            .and_then(|start| {
+                local_ts(&field(row, columns.end_date), &field(row, columns.end_time), format, tz).map(|end| (start, end))
+            });
+        let (start_ts, end_ts) = match bounds {
+            Ok((start, end)) if end < start => {
+                parsed.invalid.push((line, "ends before it starts".to_string()));
+                continue;
+            }
+            Ok(bounds) => bounds,
+            Err(e) => {
+                parsed.invalid.push((line, e));
+                continue;
+            }
+        };
+
+        let mut warnings = Vec::new();
+        let project = columns.project.map(|i| field(row, i)).unwrap_or_default();
+        if project.is_empty() {
+            warnings.push("no project".to_string());
+        }
+        if let Some(duration) = columns.duration.and_then(|i| parse_duration(&field(row, i))) {
+            let difference = duration - (end_ts - start_ts);
+            if difference.abs() > DURATION_TOLERANCE_SECONDS {
+                warnings.push(format!("duration column is off from start and end by {}s", difference));
+            }
+        }
+        let mut tag_list: Vec<String> = columns
+            .tags
+            .map(|i| field(row, i).split(',').map(str::to_string).collect())
+            .unwrap_or_default();
+        if columns.billable.is_some_and(|i| field(row, i).eq_ignore_ascii_case("yes")) {
+            tag_list.push(BILLABLE_TAG.to_string());
+        }
+        parsed.rows.push(ParsedRow {
+            line,
+            record: ImportRecord {
+                project,
+                note: columns.description.map(|i| field(row, i)).unwrap_or_default(),
+                start_ts,
+                end_ts,
+                tags: tags::normalize(&tag_list),
+            },
+            warnings,
+        });
+    }
+    Ok(parsed)
+}
+
+// Insert every importable row, with its tags, in one transaction. `progress`
+// is told (processed, total) after each row; an error from it aborts the
+// import and rolls everything back.
+pub fn import(
+    conn: &mut Connection,
+    text: &str,
+    tz: &Tz,
+    progress: &mut dyn FnMut(u64, u64) -> Result<(), String>,
+) -> Result<(ImportSummary, Vec<i64>), String> {
+    let parsed = parse(text, tz)?;
+    let total = parsed.rows.len() as u64;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut ids = Vec::with_capacity(parsed.rows.len());
+    for row in &parsed.rows {
+        let record = &row.record;
+        let entry = entries::insert_entry(&tx, &record.project, &record.note, record.start_ts, Some(record.end_ts))?;
+        tags::add_tags(&tx, entry.id, &record.tags)?;
+        ids.push(entry.id);
+        progress(ids.len() as u64, total)?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    let summary = ImportSummary {
+        imported: ids.len(),
+        skipped: parsed.invalid.len(),
+        skipped_reasons: parsed
+            .invalid
+            .iter()
+            .map(|(line, message)| format!("line {}: {}", line, message))
+            .collect(),
+    };
+    Ok((summary, ids))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::import::{preview, ImportKind};
+
+    const EXPORT: &str = "User,Project,Description,Billable,Start date,Start time,End date,End time,Duration,Tags\n\
+        Ana,Acme,\"Design, round 2\",Yes,15.01.2025,09:00:00,15.01.2025,10:30:00,01:30:00,\"design, Review\"\n\
+        Ana,,Inbox,No,16.01.2025,08:00:00,16.01.2025,08:15:00,00:15:00,\n\
+        Ana,Acme,Oops,No,17.01.2025,10:00:00,17.01.2025,09:00:00,00:00:00,\n";
+
+    #[test]
+    fn previews_then_imports_a_toggl_export() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let tz: Tz = "Europe/Berlin".parse().unwrap();
+        let preview = preview(ImportKind::TogglCsv, EXPORT, &tz).unwrap();
+        assert_eq!((preview.valid_rows, preview.warning_rows, preview.invalid_rows), (1, 1, 1));
+        assert_eq!(preview.date_format.as_deref(), Some("%d.%m.%Y"));
+        assert_eq!(preview.sample[0]["note"], "Design, round 2");
+        assert_eq!(preview.sample[0]["start_ts"], 1736928000);
+        assert_eq!(entries::list_range(&conn, 0, i64::MAX).unwrap().len(), 0);
+
+        // Cancelling partway leaves nothing behind
+        let cancelled = import(&mut conn, EXPORT, &tz, &mut |processed, _| match processed {
+            1 => Ok(()),
+            _ => Err("cancelled".to_string()),
+        });
+        assert!(cancelled.is_err());
+        assert_eq!(entries::list_range(&conn, 0, i64::MAX).unwrap().len(), 0);
+
+        let mut reports = Vec::new();
+        let (summary, ids) = import(&mut conn, EXPORT, &tz, &mut |processed, total| {
+            reports.push((processed, total));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(reports, vec![(1, 2), (2, 2)]);
+        assert_eq!((summary.imported, summary.skipped), (2, 1));
+        assert_eq!(summary.skipped_reasons, vec!["line 4: ends before it starts"]);
+        assert_eq!(tags::entry_tags(&conn, ids[0]).unwrap(), vec!["billable", "design", "review"]);
+    }
+}
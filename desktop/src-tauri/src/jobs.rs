@@ -0,0 +1,320 @@
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{async_runtime, AppHandle, Emitter, Manager};
+
+use crate::db::{self, Database};
+use crate::events::{self, EntryChange};
+use crate::gaps::{self, OutsideHours, OutsideHoursAction};
+use crate::i18n::I18n;
+use crate::notify;
+use crate::progress;
+use crate::recovery;
+use crate::recurrence;
+use crate::timefmt;
+use crate::timer::{self, Timer};
+use crate::tray;
+
+const RECURRENCE_INTERVAL: Duration = Duration::from_secs(300);
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+pub const TICK_EVENT: &str = "tick";
+pub const CLOCK_ADJUSTED_EVENT: &str = "clock-adjusted";
+pub const TIMER_CAPPED_EVENT: &str = "timer-capped";
+
+// Wall-clock time falling this far behind boot time between two ticks
+// means the system clock was set back
+const CLOCK_JUMP_THRESHOLD_SECONDS: i64 = 5;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClockAdjusted {
+    // Wall-clock jump the running timers were corrected for; always
+    // negative, as only a clock set back is corrected
+    pub drift_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Tick {
+    pub elapsed_seconds: i64,
+    pub paused: bool,
+}
+
+// Periodically turn elapsed recurring occurrences into entries. Runs once
+// right away so occurrences missed while the app was closed are caught up.
+pub fn spawn_recurrence_materializer(app: AppHandle) {
+    async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(RECURRENCE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let db = app.state::<Database>();
+            let result = db.lock().and_then(|mut conn| {
+                let tz = timefmt::configured_zone(&conn)?;
+                recurrence::materialize(&mut conn, &tz, db::now_ts())
+            });
+            match result {
+                Ok(created) if created.is_empty() => {}
+                Ok(created) => {
+                    log::info!("created {} recurring entries", created.len());
+                    events::emit_entries_changed(&app, EntryChange::Insert, created);
+                }
+                // Catch up once the user unlocks the database
+                Err(e) if db::is_unavailable(&e) => {}
+                Err(e) => log::warn!("failed to materialize recurring entries: {}", e),
+            }
+        }
+    });
+}
+
+// Drive the tray title and the UI's elapsed display from the Rust timer, so
+// both stay in sync even while the webview is backgrounded or throttled
+pub fn spawn_timer_ticker(app: AppHandle) {
+    async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let started = Instant::now();
+        let mut last_tick = (since_boot(started), db::now_ts());
+        loop {
+            interval.tick().await;
+            let (last_boot, last_wall) = last_tick;
+            last_tick = (since_boot(started), db::now_ts());
+            if let Some(drift) = backward_jump(last_tick.1 - last_wall, last_tick.0.saturating_sub(last_boot)) {
+                correct_clock_jump(&app, drift);
+            }
+            enforce_entry_cap(&app);
+            enforce_working_hours(&app);
+
+            let (status, active) = match app.state::<Timer>().lock() {
+                Ok(timers) => (timer::status(&timers, None, db::now_ts()), timers.any_active()),
+                Err(e) => {
+                    log::warn!("{}", e);
+                    continue;
+                }
+            };
+            if !status.running {
+                continue;
+            }
+            if let Err(e) = tray::show_status(&app, &status) {
+                log::warn!("failed to update tray title: {}", e);
+            }
+            let _ = app.emit(
+                TICK_EVENT,
+                Tick {
+                    elapsed_seconds: status.elapsed_seconds,
+                    paused: status.paused,
+                },
+            );
+            if active {
+                app.state::<recovery::TickWriter>().record(db::now_ts());
+                let result = app
+                    .state::<Database>()
+                    .lock()
+                    .and_then(|conn| progress::report(&app, &conn, db::now_ts()));
+                if let Err(e) = result {
+                    log::warn!("failed to report daily progress: {}", e);
+                }
+                report_targets(&app);
+            }
+        }
+    });
+}
+
+// Notify once when a timer started from a preset runs past its target
+fn report_targets(app: &AppHandle) {
+    let reached = match app.state::<Timer>().lock() {
+        Ok(mut timers) => timers.reached_targets(db::now_ts()),
+        Err(e) => {
+            log::warn!("{}", e);
+            return;
+        }
+    };
+    if reached.is_empty() {
+        return;
+    }
+    let db = app.state::<Database>();
+    let conn = db.lock().ok();
+    let i18n = app.state::<I18n>();
+    for project in &reached {
+        let body = i18n.t("notify.preset_target_body").replace("{project}", project);
+        notify::show(app, conn.as_deref(), &i18n.t("notify.preset_target_title"), &body);
+    }
+}
+
+// Safety net for forgotten timers: stop any that ran past the configured
+// maximum, keeping the entry up to the cap
+fn enforce_entry_cap(app: &AppHandle) {
+    let timer_state = app.state::<Timer>();
+    let db = app.state::<Database>();
+    let now = db::now_ts();
+    let result = timer_state.lock().and_then(|mut timers| {
+        if !timers.any_active() {
+            return Ok(None);
+        }
+        let conn = db.lock()?;
+        let Some(max_seconds) = timer::max_entry_seconds(&conn)? else {
+            return Ok(None);
+        };
+        let capped = timer::cap_overlong(&conn, &mut timers, max_seconds, now)?;
+        Ok(Some((capped, timer::status(&timers, None, now))))
+    });
+    match result {
+        Ok(Some((capped, status))) if !capped.is_empty() => {
+            for capped in &capped {
+                log::warn!(
+                    "stopped timer for '{}' after it passed the maximum entry duration",
+                    capped.project
+                );
+                let _ = app.emit(TIMER_CAPPED_EVENT, capped);
+            }
+            app.state::<recovery::TickWriter>().persist_now(now);
+            events::emit_entries_changed(app, EntryChange::Update, capped.iter().map(|c| c.entry.id).collect());
+            if let Err(e) = tray::show_status(app, &status) {
+                log::warn!("failed to update tray title: {}", e);
+            }
+        }
+        Ok(_) => {}
+        Err(e) if db::is_unavailable(&e) => {}
+        Err(e) => log::warn!("failed to enforce the maximum entry duration: {}", e),
+    }
+}
+
+// In strict mode, stop timers still running once the working hours are over,
+// ending their entries when the hours ended
+fn enforce_working_hours(app: &AppHandle) {
+    let timer_state = app.state::<Timer>();
+    let db = app.state::<Database>();
+    let now = db::now_ts();
+    let result = timer_state.lock().and_then(|mut timers| {
+        if !timers.any_active() {
+            return Ok(None);
+        }
+        let conn = db.lock()?;
+        if !gaps::block_outside_hours(&conn)? {
+            return Ok(None);
+        }
+        let tz = timefmt::configured_zone(&conn)?;
+        let Some(hours) = gaps::outside_hours(&conn, &tz, now)? else {
+            return Ok(None);
+        };
+        let end_ts = hours.last_close(&tz, now)?.unwrap_or(now);
+        let stopped = timer::stop_active_at(&conn, &mut timers, end_ts, now)?;
+        Ok(Some((stopped, hours, timer::status(&timers, None, now))))
+    });
+    match result {
+        Ok(Some((stopped, hours, status))) if !stopped.is_empty() => {
+            for stopped in &stopped {
+                log::info!("stopped timer for '{}' at the end of the working hours", stopped.project);
+                let _ = app.emit(
+                    gaps::OUTSIDE_HOURS_EVENT,
+                    OutsideHours {
+                        project: stopped.project.clone(),
+                        hours: hours.clone(),
+                        action: OutsideHoursAction::Stopped,
+                    },
+                );
+            }
+            app.state::<recovery::TickWriter>().persist_now(now);
+            events::emit_entries_changed(app, EntryChange::Update, stopped.iter().map(|s| s.entry.id).collect());
+            if let Err(e) = tray::show_status(app, &status) {
+                log::warn!("failed to update tray title: {}", e);
+            }
+        }
+        Ok(_) => {}
+        Err(e) if db::is_unavailable(&e) => {}
+        Err(e) => log::warn!("failed to enforce the working hours: {}", e),
+    }
+}
+
+// Time since boot including suspend, so a sleeping machine doesn't read as
+// the wall clock jumping ahead. Falls back to `Instant` (which stops during
+// suspend on most platforms) where there is no such clock; forward jumps
+// are never corrected, so that only loses the suspend distinction.
+fn since_boot(started: Instant) -> Duration {
+    boot_clock().unwrap_or_else(|| started.elapsed())
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn boot_clock() -> Option<Duration> {
+    clock_gettime(libc::CLOCK_BOOTTIME)
+}
+
+// CLOCK_MONOTONIC keeps counting while asleep on Apple platforms, like
+// mach_continuous_time
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn boot_clock() -> Option<Duration> {
+    clock_gettime(libc::CLOCK_MONOTONIC)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "ios"))]
+fn clock_gettime(clock: libc::clockid_t) -> Option<Duration> {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    if unsafe { libc::clock_gettime(clock, &mut ts) } != 0 {
+        return None;
+    }
+    Some(Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+}
+
+// Milliseconds since boot, counting sleep and hibernation
+#[cfg(target_os = "windows")]
+fn boot_clock() -> Option<Duration> {
+    Some(Duration::from_millis(unsafe {
+        windows_sys::Win32::System::SystemInformation::GetTickCount64()
+    }))
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "windows"
+)))]
+fn boot_clock() -> Option<Duration> {
+    None
+}
+
+// How far the wall clock was set back between two ticks, if it was. A
+// forward jump is left alone: it is as likely a wake from suspend as a
+// clock change, and rewriting start times for it would lose tracked time.
+fn backward_jump(wall_elapsed: i64, boot_elapsed: Duration) -> Option<i64> {
+    let drift = wall_elapsed - boot_elapsed.as_secs() as i64;
+    (drift < -CLOCK_JUMP_THRESHOLD_SECONDS).then_some(drift)
+}
+
+// Elapsed time follows the boot clock: shift running timers back by the
+// jump so a clock set back (by NTP or by hand) doesn't shrink them.
+// Absolute timestamps stay wall-clock.
+fn correct_clock_jump(app: &AppHandle, drift: i64) {
+    let timer_state = app.state::<Timer>();
+    let db = app.state::<Database>();
+    let result = timer_state.lock().and_then(|mut timers| {
+        if !timers.any_active() {
+            return Ok(Vec::new());
+        }
+        timer::shift_running(&db.lock()?, &mut timers, drift)
+    });
+    match result {
+        Ok(shifted) if shifted.is_empty() => {}
+        Ok(shifted) => {
+            log::warn!("system clock jumped by {}s; adjusted running timers", drift);
+            let _ = app.emit(CLOCK_ADJUSTED_EVENT, ClockAdjusted { drift_seconds: drift });
+            events::emit_entries_changed(app, EntryChange::Update, shifted);
+        }
+        Err(e) if db::is_unavailable(&e) => {}
+        Err(e) => log::warn!("failed to adjust timers for a clock jump: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_clock_set_back_is_a_jump() {
+        // A normal tick, and a wake after an hour asleep
+        assert_eq!(backward_jump(1, Duration::from_secs(1)), None);
+        assert_eq!(backward_jump(3_601, Duration::from_secs(3_601)), None);
+        // Forward jumps are never corrected, even without a boot clock
+        assert_eq!(backward_jump(3_601, Duration::from_secs(1)), None);
+        assert_eq!(backward_jump(-3_599, Duration::from_secs(1)), Some(-3_600));
+    }
+}
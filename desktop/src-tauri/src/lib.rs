@@ -1,13 +1,75 @@
-use tauri::{
-    Manager, WindowEvent, PhysicalPosition, Position, Size,
-};
+use tauri::{Manager, WindowEvent};
+
+mod about;
+#[cfg(desktop)]
+mod app_menu;
+#[cfg(desktop)]
+mod appearance;
+mod archive;
+#[cfg(desktop)]
+mod autostart;
+mod billing;
+mod budget;
+mod categorize;
+#[cfg(desktop)]
+mod chime;
+mod colors;
+mod commands;
+mod daynotes;
+mod db;
+#[cfg(desktop)]
+mod dbwatch;
+#[cfg(desktop)]
+mod displays;
+mod entries;
+mod events;
+mod export;
+#[cfg(desktop)]
+mod focus;
+mod gaps;
+mod i18n;
+mod import;
+mod jobs;
+mod logging;
+#[cfg(desktop)]
+mod menubar;
+mod notify;
+mod presets;
+mod profiles;
+mod progress;
+mod projects;
+#[cfg(desktop)]
+mod quit;
+mod recovery;
+mod recurrence;
+mod repair;
+mod reset;
+#[cfg(desktop)]
+mod rotation;
+mod session;
+mod settings;
+mod shortcuts;
+mod stats;
+mod tags;
+mod timefmt;
+mod timer;
+mod transfer;
+mod tray;
+mod undo;
+#[cfg(desktop)]
+mod updater;
+mod webhook;
+mod widget;
+
+use db::Database;
+use i18n::I18n;
+use timer::Timer;
+use undo::UndoStack;
+use widget::WidgetState;
 
 // Desktop-only imports (not available on mobile builds)
 #[cfg(desktop)]
-use tauri::{
-    menu::{Menu, MenuItem, PredefinedMenuItem, Submenu},
-    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent, TrayIconId},
-};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent, TrayIconId};
 
 // Command to update the tray title with timer info
 #[cfg(desktop)]
@@ -17,24 +79,14 @@ fn update_tray_title(
     elapsed: String,
     project: String,
 ) -> Result<(), String> {
-    println!("update_tray_title called with elapsed='{}', project='{}'", elapsed, project);
-    
-    if let Some(tray) = app.tray_by_id(&TrayIconId::new("main-tray")) {
-        let title = if !elapsed.is_empty() && !project.is_empty() {
-            format!("⏱ {} • {}", elapsed, project)
-        } else if !elapsed.is_empty() {
-            format!("⏱ {}", elapsed)
-        } else {
-            "TimeGrid".to_string()
-        };
-        
-        tray.set_title(Some(&title))
-            .map_err(|e| e.to_string())?;
-        println!("Tray title updated successfully");
-    } else {
-        println!("WARNING: Tray icon not found!");
+    // While any Rust timer runs its ticker owns the title, and a rotation
+    // owns it otherwise
+    let timers = app.state::<Timer>();
+    let status = timer::status(&*timers.lock()?, None, db::now_ts());
+    if status.running || rotation::owns_title(&app, &status) {
+        return Ok(());
     }
-    Ok(())
+    tray::set_title(&app, &elapsed, &project)
 }
 
 // Mobile: noop implementation to keep the command available
@@ -48,201 +100,147 @@ fn update_tray_title(
     Ok(())
 }
 
-// Helper function to position widget window below tray icon
-#[cfg(desktop)]
-fn position_widget_window(app: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
-    if let Some(widget) = app.get_webview_window("timer-widget") {
-        if let Some(tray) = app.tray_by_id(&TrayIconId::new("main-tray")) {
-            // Get tray icon position
-            if let Ok(Some(tray_rect)) = tray.rect() {
-                let window_width = 320.0;
-                
-                // Extract physical positions from Tauri Position/Size enums
-                let (tray_x, tray_y) = match tray_rect.position {
-                    Position::Physical(pos) => (pos.x as f64, pos.y as f64),
-                    Position::Logical(pos) => (pos.x, pos.y),
-                };
-                
-                let (tray_width, tray_height) = match tray_rect.size {
-                    Size::Physical(size) => (size.width as f64, size.height as f64),
-                    Size::Logical(size) => (size.width, size.height),
-                };
-                
-                // Position window below the tray icon, centered horizontally
-                let x = tray_x + (tray_width / 2.0) - (window_width / 2.0);
-                let y = tray_y + tray_height + 8.0; // 8px gap below tray
-                
-                widget.set_position(PhysicalPosition::new(x as i32, y as i32))?;
-            }
-        }
-    }
-    Ok(())
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
-            }
-            #[cfg(desktop)]
-            {
-            // Create native application menu (macOS standard menus)
-            let app_name = "TimeGrid";
-            
-            // App Menu (macOS standard first menu)
-            let about = PredefinedMenuItem::about(app, Some(app_name), None)?;
-            let settings = MenuItem::with_id(app, "settings", "Settings...", true, Some("Cmd+,"))?;
-            let hide = PredefinedMenuItem::hide(app, Some("Hide TimeGrid"))?;
-            let hide_others = PredefinedMenuItem::hide_others(app, Some("Hide Others"))?;
-            let show_all = PredefinedMenuItem::show_all(app, Some("Show All"))?;
-            let quit = PredefinedMenuItem::quit(app, Some("Quit TimeGrid"))?;
-            
-            let app_menu = Submenu::with_items(
-                app,
-                app_name,
-                true,
-                &[
-                    &about,
-                    &settings,
-                    &hide,
-                    &hide_others,
-                    &show_all,
-                    &quit,
-                ],
-            )?;
-
-            // File Menu
-            let new_entry = MenuItem::with_id(app, "new_entry", "New Time Entry", true, Some("Cmd+N"))?;
-            let close_window = PredefinedMenuItem::close_window(app, Some("Close Window"))?;
-            
-            let file_menu = Submenu::with_items(
-                app,
-                "File",
-                true,
-                &[
-                    &new_entry,
-                    &close_window,
-                ],
-            )?;
+            // Open the local database in the app data directory
+            let data_dir = app.path().app_data_dir()?;
+            std::fs::create_dir_all(&data_dir)?;
+            let database = Database::open(&data_dir.join("timegrid.db"))?;
 
-            // Edit Menu (standard macOS edit operations)
-            let undo = PredefinedMenuItem::undo(app, Some("Undo"))?;
-            let redo = PredefinedMenuItem::redo(app, Some("Redo"))?;
-            let cut = PredefinedMenuItem::cut(app, Some("Cut"))?;
-            let copy = PredefinedMenuItem::copy(app, Some("Copy"))?;
-            let paste = PredefinedMenuItem::paste(app, Some("Paste"))?;
-            let select_all = PredefinedMenuItem::select_all(app, Some("Select All"))?;
-            
-            let edit_menu = Submenu::with_items(
-                app,
-                "Edit",
-                true,
-                &[
-                    &undo,
-                    &redo,
-                    &cut,
-                    &copy,
-                    &paste,
-                    &select_all,
-                ],
-            )?;
+            // Warn (Info in debug builds) to stdout; file logging is opt-in
+            let (log_level, file_logging) = database
+                .lock()
+                .and_then(|conn| logging::load(&conn))
+                .unwrap_or((logging::default_level(), false));
+            app.handle().plugin(logging::plugin(&data_dir))?;
+            logging::apply(log_level, file_logging);
 
-            // View Menu
-            let toggle_timer = MenuItem::with_id(app, "toggle_timer", "Quick Timer", true, Some("Cmd+T"))?;
-            let toggle_fullscreen = PredefinedMenuItem::fullscreen(app, Some("Enter Full Screen"))?;
-            
-            let view_menu = Submenu::with_items(
-                app,
-                "View",
-                true,
-                &[
-                    &toggle_timer,
-                    &toggle_fullscreen,
-                ],
-            )?;
+            // An encrypted database stays locked until `unlock`, so fall back to defaults
+            let autohide_ms = database
+                .lock()
+                .and_then(|conn| settings::get(&conn, widget::AUTOHIDE_SETTING))
+                .ok()
+                .flatten()
+                .unwrap_or(0);
+            let widget_size = database
+                .lock()
+                .and_then(|conn| settings::get(&conn, widget::SIZE_SETTING))
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+            let widget_offset = database
+                .lock()
+                .and_then(|conn| settings::get(&conn, widget::OFFSET_SETTING))
+                .ok()
+                .flatten();
+            // Menu and tray strings follow the saved locale, else the OS locale
+            let locale = database
+                .lock()
+                .and_then(|conn| settings::get::<String>(&conn, i18n::LOCALE_SETTING))
+                .ok()
+                .flatten()
+                .unwrap_or_else(i18n::system_locale);
+            app.manage(database);
+            app.manage(I18n::new(&locale));
+            #[cfg(desktop)]
+            app.manage(tray::TrayEmoji::new(
+                app.state::<Database>()
+                    .lock()
+                    .and_then(|conn| settings::get(&conn, tray::EMOJI_SETTING))
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| tray::DEFAULT_EMOJI.to_string()),
+            ));
+            app.manage(Timer::default());
+            let duration_display = timer::DurationDisplay::default();
+            if let Ok(conn) = app.state::<Database>().lock() {
+                duration_display.reload(&conn);
+            }
+            app.manage(duration_display);
+            app.manage(UndoStack::default());
+            app.manage(reset::ResetToken::default());
+            app.manage(transfer::Transfer::default());
+            app.manage(recovery::Recovery::default());
+            app.manage(recovery::TickWriter::new(
+                app.state::<Database>()
+                    .lock()
+                    .and_then(|conn| recovery::tick_persist_seconds(&conn))
+                    .unwrap_or(recovery::DEFAULT_TICK_PERSIST_SECONDS),
+            ));
+            app.manage(WidgetState::new(autohide_ms, widget_size, widget_offset));
+            #[cfg(desktop)]
+            app.manage(focus::FocusMode::default());
+            #[cfg(desktop)]
+            app.manage(quit::QuitState::default());
+            #[cfg(desktop)]
+            app.manage(rotation::TrayRotation::default());
+            #[cfg(desktop)]
+            app.manage(tray::PendingClick::default());
+            #[cfg(desktop)]
+            app.manage(dbwatch::DbWatcher::default());
+            app.manage(session::LockState::default());
+            session::listen(app.handle());
+            jobs::spawn_recurrence_materializer(app.handle().clone());
+            jobs::spawn_timer_ticker(app.handle().clone());
+            recovery::spawn_tick_writer(app.handle().clone());
 
-            // Window Menu (standard macOS window management)
-            let minimize = PredefinedMenuItem::minimize(app, Some("Minimize"))?;
-            let zoom = PredefinedMenuItem::maximize(app, Some("Zoom"))?;
-            
-            let window_menu = Submenu::with_items(
-                app,
-                "Window",
-                true,
-                &[
-                    &minimize,
-                    &zoom,
-                ],
-            )?;
+            #[cfg(desktop)]
+            {
+            app.handle().plugin(tauri_plugin_updater::Builder::new().build())?;
+            chime::spawn_hourly(app.handle().clone());
+            autostart::spawn_watcher(app.handle().clone());
+            categorize::spawn_sampler(app.handle().clone());
+            widget::spawn_tray_watcher(app.handle().clone());
+            displays::spawn_watcher(app.handle().clone());
+            let watch_database = app
+                .state::<Database>()
+                .lock()
+                .and_then(|conn| settings::get(&conn, dbwatch::WATCH_SETTING))
+                .ok()
+                .flatten()
+                .unwrap_or(false);
+            if watch_database {
+                if let Err(e) = dbwatch::set_enabled(app.handle(), true) {
+                    log::warn!("{}", e);
+                }
+            }
 
-            // Build the native menu bar
-            let menu = Menu::with_items(
-                app,
-                &[
-                    &app_menu,
-                    &file_menu,
-                    &edit_menu,
-                    &view_menu,
-                    &window_menu,
-                ],
-            )?;
+            // Create native application menu (macOS standard menus)
+            let menu = app_menu::build(app)?;
 
             // Set the application menu
-            app.set_menu(menu.clone())?;
+            app.set_menu(menu)?;
 
             // Handle menu events
             app.on_menu_event(|app, event| match event.id.as_ref() {
-                "settings" => {
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                        // Navigate to settings page
-                        let _ = window.eval("window.location.hash = '#/settings'");
-                    }
-                }
-                "new_entry" => {
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                        // Navigate to timer page
-                        let _ = window.eval("window.location.hash = '#/timer'");
-                    }
-                }
+                // Navigate to the settings or timer page, or hand the action
+                // to the widget in menu-bar-only mode
+                "settings" => menubar::open_page(app, "#/settings", "settings"),
+                "new_entry" => menubar::open_page(app, "#/timer", "new_entry"),
                 "toggle_timer" => {
                     // Show timer widget
-                    if let Some(widget) = app.get_webview_window("timer-widget") {
-                        if widget.is_visible().unwrap_or(false) {
-                            let _ = widget.hide();
-                        } else {
-                            let _ = position_widget_window(&app);
-                            let _ = widget.show();
-                            let _ = widget.set_focus();
-                        }
-                    }
+                    widget::toggle(app);
+                }
+                "check_updates" => {
+                    updater::spawn_check(app.clone());
                 }
                 _ => {}
             });
 
             // Create system tray menu (simplified)
-            let timer_item = MenuItem::with_id(app, "tray_timer", "Quick Timer", true, None::<&str>)?;
-            let show_item = MenuItem::with_id(app, "tray_show", "Show Main Window", true, None::<&str>)?;
-            let quit_item = MenuItem::with_id(app, "tray_quit", "Quit", true, None::<&str>)?;
-
-            let tray_menu = Menu::with_items(app, &[&timer_item, &show_item, &quit_item])?;
+            let tray_menu = tray::build_menu(app)?;
 
             // Build system tray
-            let tray_id = TrayIconId::new("main-tray");
+            let tray_id = TrayIconId::new(tray::TRAY_ID);
             let _tray = TrayIconBuilder::with_id(tray_id)
                 .menu(&tray_menu)
-                .show_menu_on_left_click(false)
-                .title("TimeGrid")
+                .show_menu_on_left_click(tray::click_action(app) == tray::ClickAction::Menu)
+                .title(app.state::<I18n>().t("tray.title"))
                 .on_tray_icon_event(|tray, event| {
                     match event {
                         TrayIconEvent::Click {
@@ -250,16 +248,7 @@ pub fn run() {
                             button_state: MouseButtonState::Up,
                             ..
                         } => {
-                            let app = tray.app_handle();
-                            if let Some(widget) = app.get_webview_window("timer-widget") {
-                                if widget.is_visible().unwrap_or(false) {
-                                    let _ = widget.hide();
-                                } else {
-                                    let _ = position_widget_window(&app);
-                                    let _ = widget.show();
-                                    let _ = widget.set_focus();
-                                }
-                            }
+                            tray::handle_left_click(tray.app_handle());
                         }
                         _ => {}
                     }
@@ -267,58 +256,272 @@ pub fn run() {
                 .on_menu_event(move |app, event| match event.id.as_ref() {
                     "tray_timer" => {
                         // Show timer widget
-                        if let Some(widget) = app.get_webview_window("timer-widget") {
-                            if widget.is_visible().unwrap_or(false) {
-                                let _ = widget.hide();
-                            } else {
-                                let _ = position_widget_window(&app);
-                                let _ = widget.show();
-                                let _ = widget.set_focus();
-                            }
-                        }
-                    }
-                    "tray_show" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                        }
+                        widget::toggle(app);
                     }
+                    "tray_show" => menubar::show_main(app),
                     "tray_quit" => {
-                        app.exit(0);
+                        quit::request_quit(app);
+                    }
+                    id => {
+                        if let Some(name) = id.strip_prefix(tray::PRESET_ITEM_PREFIX) {
+                            if let Err(e) = commands::start_preset_by_name(app, name) {
+                                log::warn!("failed to start preset '{}': {}", name, e);
+                            }
+                        }
                     }
-                    _ => {}
                 })
                 .build(app)?;
+            tray::detect_title_support(app.handle());
+
+            // Apply the saved widget size before it is first shown
+            if widget_size != widget::WidgetSize::default() {
+                widget::apply_size(app.handle(), widget_size)?;
+            }
+            let nonactivating = app
+                .state::<Database>()
+                .lock()
+                .and_then(|conn| settings::get(&conn, widget::NONACTIVATING_SETTING))
+                .ok()
+                .flatten()
+                .unwrap_or(false);
+            if nonactivating {
+                widget::set_nonactivating(app.handle(), true)?;
+            }
 
             // Handle window events for the timer widget
-            if let Some(widget) = app.get_webview_window("timer-widget") {
+            if let Some(widget) = app.get_webview_window(widget::WIDGET_LABEL) {
                 let widget_clone = widget.clone();
                 widget.on_window_event(move |event| {
                     match event {
                         WindowEvent::Focused(false) => {
-                            // Hide widget when it loses focus (user clicks outside)
-                            let _ = widget_clone.hide();
+                            // Hide widget when it loses focus (user clicks outside),
+                            // after the configured auto-hide delay
+                            widget::schedule_hide(&widget_clone);
+                        }
+                        WindowEvent::Focused(true) => {
+                            // Regaining focus within the delay keeps it open
+                            widget_clone.state::<WidgetState>().cancel_hide();
+                        }
+                        WindowEvent::ThemeChanged(theme) => {
+                            // The widget follows the OS appearance (the main window pins its own)
+                            appearance::on_theme_changed(widget_clone.app_handle(), *theme);
+                        }
+                        #[cfg(desktop)]
+                        WindowEvent::Moved(position) => {
+                            widget::on_moved(widget_clone.app_handle(), *position);
                         }
                         _ => {}
                     }
                 });
             }
 
-            // Handle window close for main window - minimize to tray instead of quitting
+            // Match the tray glyph to the current appearance
+            if let Ok(theme) = appearance::system_theme(app.handle()) {
+                let _ = tray::apply_theme(app.handle(), theme);
+            }
+
+            // Handle window close for main window - minimize to tray instead of quitting,
+            // unless the user turned that off
             if let Some(window) = app.get_webview_window("main") {
                 let window_clone = window.clone();
                 window.on_window_event(move |event| {
+                    // Fullscreen changes arrive as resizes
+                    #[cfg(desktop)]
+                    if let WindowEvent::Resized(_) = event {
+                        widget::on_main_resized(window_clone.app_handle(), &window_clone);
+                    }
                     if let WindowEvent::CloseRequested { api, .. } = event {
+                        // The widget keeps the app alive, so quit explicitly; this
+                        // goes through the same confirmation and shutdown as the tray
+                        #[cfg(desktop)]
+                        if !quit::close_to_tray(window_clone.app_handle()) {
+                            api.prevent_close();
+                            quit::request_quit(window_clone.app_handle());
+                            return;
+                        }
                         // Prevent default close and hide instead
                         window_clone.hide().unwrap();
                         api.prevent_close();
                     }
                 });
             }
+
+            // Offer to recover a timer orphaned by a crash; an encrypted
+            // database is checked on unlock instead
+            if !app.state::<Database>().is_locked()? {
+                recovery::check(app.handle())?;
+            }
+
+            // The main window starts hidden and is only shown outside
+            // menu-bar-only mode, so it never flashes up at login
+            menubar::apply(app.handle(), menubar::is_enabled(app.handle()))?;
+
+            // Reopen the widget if it was visible when the app last quit
+            widget::restore_visibility(app.handle())?;
+
+            // Optional update check on launch; results arrive as events
+            let check_on_launch = app
+                .state::<Database>()
+                .lock()
+                .and_then(|conn| settings::get(&conn, updater::CHECK_ON_LAUNCH_SETTING))
+                .ok()
+                .flatten()
+                .unwrap_or(false);
+            if check_on_launch {
+                updater::spawn_check(app.handle().clone());
+            }
             }
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![update_tray_title])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .invoke_handler(tauri::generate_handler![
+            update_tray_title,
+            commands::list_entries,
+            commands::list_projects,
+            commands::rename_project,
+            commands::find_duplicate_projects,
+            commands::merge_project_group,
+            commands::delete_project,
+            commands::delete_entries,
+            commands::shift_entries,
+            commands::repair_entries,
+            commands::undo,
+            commands::peek_undo,
+            commands::split_entry,
+            commands::start_timer,
+            commands::get_recent_projects,
+            commands::start_last_project,
+            commands::pause_timer,
+            commands::resume_timer,
+            commands::stop_timer,
+            commands::get_timer_status,
+            commands::list_running_timers,
+            commands::set_min_entry_seconds,
+            commands::set_max_entry_seconds,
+            commands::set_note_template,
+            commands::set_daily_target,
+            commands::switch_project,
+            commands::set_widget_autohide_ms,
+            commands::reposition_widget,
+            commands::reset_widget_position,
+            commands::get_widget_diagnostics,
+            commands::set_widget_size,
+            commands::set_widget_nonactivating,
+            commands::save_preset,
+            commands::list_presets,
+            commands::delete_preset,
+            commands::start_preset,
+            commands::add_recurring,
+            commands::list_recurrences,
+            commands::delete_recurrence,
+            commands::unlock,
+            commands::set_encryption_passphrase,
+            commands::is_database_locked,
+            commands::compact_database,
+            commands::set_watch_database,
+            commands::set_pause_on_lock,
+            commands::get_locked_spans,
+            commands::request_reset_token,
+            commands::reset_all_data,
+            commands::get_stats,
+            commands::compare_ranges,
+            commands::get_longest_session,
+            commands::get_activity_heatmap,
+            commands::get_hourly_distribution,
+            commands::get_timeline,
+            commands::find_gaps,
+            commands::set_activity_sampling,
+            commands::add_categorization_rule,
+            commands::list_categorization_rules,
+            commands::remove_categorization_rule,
+            commands::suggest_project,
+            commands::confirm_suggestion,
+            commands::set_working_hours,
+            commands::clear_working_hours,
+            commands::set_block_outside_hours,
+            commands::set_quiet_hours,
+            commands::set_entry_tags,
+            commands::tag_entries,
+            commands::get_project_color,
+            commands::set_project_color,
+            commands::set_project_rate,
+            commands::set_project_budget,
+            commands::get_budget_burndown,
+            commands::set_rounding_minutes,
+            commands::set_duration_format,
+            commands::get_display_elapsed,
+            commands::get_invoice,
+            commands::set_week_start,
+            commands::set_locale,
+            commands::get_app_info,
+            commands::check_for_updates,
+            commands::install_update,
+            commands::set_check_updates_on_launch,
+            commands::set_hourly_chime,
+            commands::set_autostart_prompt,
+            commands::toggle_focus_mode,
+            commands::get_system_theme,
+            commands::set_focus_mode_dnd,
+            commands::play_test_chime,
+            commands::set_menu_shortcut,
+            commands::validate_accelerator,
+            commands::set_tray_click_action,
+            commands::set_tray_double_click_action,
+            commands::set_idle_tray_mode,
+            commands::set_confirm_quit_while_running,
+            commands::confirm_quit,
+            commands::set_close_to_tray,
+            commands::set_menubar_only,
+            commands::get_menubar_only,
+            commands::tray_supports_title,
+            commands::set_tray_emoji,
+            commands::set_tray_rotation,
+            commands::clear_tray_rotation,
+            commands::get_data_dir,
+            commands::open_data_dir,
+            commands::set_log_level,
+            commands::set_file_logging,
+            commands::open_log_file,
+            commands::get_storage_info,
+            commands::set_webhook,
+            commands::test_webhook,
+            commands::set_timezone,
+            commands::get_timezone,
+            commands::preview_import,
+            commands::import_toggl_csv,
+            commands::import_csv_mapped,
+            commands::cancel_import,
+            commands::backup_data,
+            commands::restore_data,
+            commands::import_settings,
+            commands::save_profile,
+            commands::load_profile,
+            commands::list_profiles,
+            commands::delete_profile,
+            commands::export_csv,
+            commands::export_json,
+            commands::export_week_matrix,
+            commands::export_ics,
+            commands::export_pdf,
+            commands::export_summary_image,
+            commands::export_markdown,
+            commands::set_day_note,
+            commands::get_day_note,
+            commands::export_project,
+            commands::archive_entries,
+            commands::restore_archive,
+            commands::list_archives,
+            commands::trigger_action,
+            commands::get_crash_recovery,
+            commands::recover_timer,
+            commands::set_tick_persist_interval,
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, _event| match &_event {
+            // Hold quits (including the native Quit item) for confirmation
+            #[cfg(desktop)]
+            tauri::RunEvent::ExitRequested { api, .. } => quit::on_exit_requested(_app, api),
+            tauri::RunEvent::Exit => recovery::on_exit(_app),
+            _ => {}
+        });
 }
@@ -0,0 +1,88 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use log::LevelFilter;
+use rusqlite::Connection;
+use tauri::plugin::TauriPlugin;
+use tauri::Runtime;
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+
+use crate::settings;
+
+pub const LOG_LEVEL_SETTING: &str = "log_level";
+pub const FILE_LOGGING_SETTING: &str = "file_logging";
+
+const LOG_DIR: &str = "logs";
+const LOG_FILE_NAME: &str = "timegrid";
+// The file is rotated at this size, keeping one previous file
+const MAX_FILE_BYTES: u128 = 2 * 1024 * 1024;
+
+// The file target stays installed and is switched with this flag, so file
+// logging can be turned on without a restart
+static FILE_LOGGING: AtomicBool = AtomicBool::new(false);
+
+pub fn default_level() -> LevelFilter {
+    if cfg!(debug_assertions) {
+        LevelFilter::Info
+    } else {
+        LevelFilter::Warn
+    }
+}
+
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    level
+        .parse()
+        .map_err(|_| format!("unknown log level '{}', expected off, error, warn, info, debug or trace", level))
+}
+
+// Saved level and file logging flag; unset (or a locked database) means the
+// build's defaults
+pub fn load(conn: &Connection) -> Result<(LevelFilter, bool), String> {
+    let level = match settings::get::<String>(conn, LOG_LEVEL_SETTING)? {
+        Some(level) => parse_level(&level)?,
+        None => default_level(),
+    };
+    let file = settings::get(conn, FILE_LOGGING_SETTING)?.unwrap_or(false);
+    Ok((level, file))
+}
+
+pub fn log_file(data_dir: &Path) -> PathBuf {
+    data_dir.join(LOG_DIR).join(format!("{}.log", LOG_FILE_NAME))
+}
+
+// Stdout plus a rotating file in the data directory. Records are filtered by
+// `log::max_level` instead of the plugin's level so it can change at runtime;
+// call `apply` once the plugin is registered, as registering resets it.
+pub fn plugin<R: Runtime>(data_dir: &Path) -> TauriPlugin<R> {
+    tauri_plugin_log::Builder::default()
+        .level(LevelFilter::Trace)
+        .targets([
+            Target::new(TargetKind::Stdout),
+            Target::new(TargetKind::Folder {
+                path: data_dir.join(LOG_DIR),
+                file_name: Some(LOG_FILE_NAME.to_string()),
+            })
+            .filter(|_| FILE_LOGGING.load(Ordering::Relaxed)),
+        ])
+        .max_file_size(MAX_FILE_BYTES)
+        .rotation_strategy(RotationStrategy::KeepOne)
+        .build()
+}
+
+pub fn apply(level: LevelFilter, file: bool) {
+    log::set_max_level(level);
+    FILE_LOGGING.store(file, Ordering::Relaxed);
+}
+
+pub fn set_level(conn: &Connection, level: &str) -> Result<(), String> {
+    let filter = parse_level(level)?;
+    settings::set(conn, LOG_LEVEL_SETTING, &filter.to_string().to_lowercase())?;
+    log::set_max_level(filter);
+    Ok(())
+}
+
+pub fn set_file_logging(conn: &Connection, enabled: bool) -> Result<(), String> {
+    settings::set(conn, FILE_LOGGING_SETTING, &enabled)?;
+    FILE_LOGGING.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db::Database;
+use crate::settings;
+use crate::widget;
+
+// When on, the tray and widget are the whole UI: the main window is never
+// shown and macOS hides the Dock icon
+pub const MENUBAR_ONLY_SETTING: &str = "menubar_only";
+// Sent to the widget with "settings" or "new_entry" when a menu action that
+// would open the main window is routed to it instead
+pub const MENUBAR_ACTION_EVENT: &str = "menubar-action";
+
+pub const MAIN_LABEL: &str = "main";
+
+// Unset or unreadable (e.g. a locked database, which needs the main window
+// to unlock) means off
+pub fn is_enabled(app: &AppHandle) -> bool {
+    app.try_state::<Database>()
+        .and_then(|db| db.lock().ok())
+        .and_then(|conn| settings::get(&conn, MENUBAR_ONLY_SETTING).ok().flatten())
+        .unwrap_or(false)
+}
+
+pub fn set_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set(&*app.state::<Database>().lock()?, MENUBAR_ONLY_SETTING, &enabled)?;
+    apply(app, enabled)
+}
+
+// Hide or show the main window and the Dock icon without persisting
+pub fn apply(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(MAIN_LABEL) {
+        if enabled {
+            window.hide().map_err(|e| e.to_string())?;
+        } else {
+            window.show().map_err(|e| e.to_string())?;
+        }
+    }
+    #[cfg(target_os = "macos")]
+    app.set_activation_policy(if enabled {
+        tauri::ActivationPolicy::Accessory
+    } else {
+        tauri::ActivationPolicy::Regular
+    })
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// The window that is the UI: the widget in menu-bar-only mode, else main
+pub fn ui_label(app: &AppHandle) -> &'static str {
+    if is_enabled(app) {
+        widget::WIDGET_LABEL
+    } else {
+        MAIN_LABEL
+    }
+}
+
+// Show and focus the main window, or the widget in menu-bar-only mode
+pub fn show_main(app: &AppHandle) {
+    if is_enabled(app) {
+        widget::show(app);
+        return;
+    }
+    if let Some(window) = app.get_webview_window(MAIN_LABEL) {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+// Open the main window on a page, e.g. "#/settings". In menu-bar-only mode
+// the widget is shown and told which `action` was asked for instead.
+pub fn open_page(app: &AppHandle, hash: &str, action: &str) {
+    if is_enabled(app) {
+        widget::show(app);
+        let _ = app.emit_to(widget::WIDGET_LABEL, MENUBAR_ACTION_EVENT, action);
+        return;
+    }
+    if let Some(window) = app.get_webview_window(MAIN_LABEL) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        let _ = window.eval(format!("window.location.hash = '{}'", hash));
+    }
+}
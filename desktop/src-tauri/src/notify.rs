@@ -0,0 +1,99 @@
+use chrono::NaiveTime;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::recurrence::parse_hhmm;
+use crate::settings;
+use crate::timefmt;
+
+// {"start": "HH:MM", "end": "HH:MM", "enabled": bool} in the configured zone
+pub const QUIET_HOURS_SETTING: &str = "quiet_hours";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+    pub enabled: bool,
+}
+
+impl QuietHours {
+    pub fn new(start_hhmm: &str, end_hhmm: &str, enabled: bool) -> Result<Self, String> {
+        let (start, end) = (parse_hhmm(start_hhmm)?, parse_hhmm(end_hhmm)?);
+        if start == end {
+            return Err("quiet hours must end at a different time than they start".to_string());
+        }
+        Ok(Self {
+            start: start.format("%H:%M").to_string(),
+            end: end.format("%H:%M").to_string(),
+            enabled,
+        })
+    }
+
+    // An end before the start wraps past midnight, so 22:00–07:00 covers the night
+    pub fn contains(&self, time: NaiveTime) -> Result<bool, String> {
+        let (start, end) = (parse_hhmm(&self.start)?, parse_hhmm(&self.end)?);
+        Ok(if start < end {
+            start <= time && time < end
+        } else {
+            time >= start || time < end
+        })
+    }
+}
+
+pub fn quiet_hours(conn: &Connection) -> Result<Option<QuietHours>, String> {
+    settings::get(conn, QUIET_HOURS_SETTING)
+}
+
+pub fn set_quiet_hours(conn: &Connection, hours: &QuietHours) -> Result<(), String> {
+    settings::set(conn, QUIET_HOURS_SETTING, hours)
+}
+
+// Whether quiet hours are on and `now` falls inside them
+pub fn is_quiet(conn: &Connection, now: i64) -> Result<bool, String> {
+    match quiet_hours(conn)? {
+        Some(hours) if hours.enabled => {
+            let tz = timefmt::configured_zone(conn)?;
+            hours.contains(timefmt::to_local(now, &tz).time())
+        }
+        _ => Ok(false),
+    }
+}
+
+// Show an OS notification unless quiet hours are on. Without a connection
+// (e.g. while the database is locked) quiet hours are unknown and it is shown.
+pub fn show(app: &AppHandle, conn: Option<&Connection>, title: &str, body: &str) {
+    let quiet = conn.map_or(Ok(false), |conn| is_quiet(conn, crate::db::now_ts()));
+    match quiet {
+        Ok(true) => {
+            log::info!("quiet hours: suppressed notification '{}'", title);
+            return;
+        }
+        Ok(false) => {}
+        Err(e) => log::warn!("failed to read quiet hours: {}", e),
+    }
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("failed to show notification '{}': {}", title, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quiet_hours_wrap_past_midnight() {
+        let at = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let night = QuietHours::new("22:00", "07:00", true).unwrap();
+        assert!(night.contains(at(23, 30)).unwrap());
+        assert!(night.contains(at(2, 0)).unwrap());
+        assert!(!night.contains(at(7, 0)).unwrap());
+        assert!(!night.contains(at(12, 0)).unwrap());
+
+        let lunch = QuietHours::new("12:00", "13:00", true).unwrap();
+        assert!(lunch.contains(at(12, 30)).unwrap());
+        assert!(!lunch.contains(at(13, 0)).unwrap());
+        assert!(QuietHours::new("09:00", "09:00", true).is_err());
+    }
+}
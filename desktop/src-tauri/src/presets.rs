@@ -0,0 +1,75 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+// A saved timer template that can be started in one click
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub project: String,
+    pub default_note: String,
+    pub target_minutes: Option<u32>,
+}
+
+impl Preset {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            name: row.get(0)?,
+            project: row.get(1)?,
+            default_note: row.get(2)?,
+            target_minutes: row.get(3)?,
+        })
+    }
+}
+
+pub fn save_preset(conn: &Connection, preset: &Preset) -> Result<(), String> {
+    if preset.name.trim().is_empty() {
+        return Err("preset name cannot be empty".to_string());
+    }
+    conn.execute(
+        "INSERT INTO presets (name, project, default_note, target_minutes) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(name) DO UPDATE SET
+            project = excluded.project,
+            default_note = excluded.default_note,
+            target_minutes = excluded.target_minutes",
+        params![
+            preset.name,
+            preset.project,
+            preset.default_note,
+            preset.target_minutes
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn list_presets(conn: &Connection) -> Result<Vec<Preset>, String> {
+    let mut stmt = conn
+        .prepare("SELECT name, project, default_note, target_minutes FROM presets ORDER BY name")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], Preset::from_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())
+}
+
+pub fn get_preset(conn: &Connection, name: &str) -> Result<Preset, String> {
+    conn.query_row(
+        "SELECT name, project, default_note, target_minutes FROM presets WHERE name = ?1",
+        params![name],
+        Preset::from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("preset '{}' not found", name))
+}
+
+pub fn delete_preset(conn: &Connection, name: &str) -> Result<(), String> {
+    let removed = conn
+        .execute("DELETE FROM presets WHERE name = ?1", params![name])
+        .map_err(|e| e.to_string())?;
+    if removed == 0 {
+        return Err(format!("preset '{}' not found", name));
+    }
+    Ok(())
+}
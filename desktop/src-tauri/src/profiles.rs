@@ -0,0 +1,194 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Manager};
+
+use crate::db::Database;
+use crate::i18n::{self, I18n};
+use crate::logging;
+use crate::recovery::{self, TickWriter};
+use crate::settings;
+use crate::timer::DurationDisplay;
+use crate::tray;
+use crate::widget::{self, WidgetState};
+#[cfg(desktop)]
+use crate::{dbwatch, menubar};
+
+// Sent after a profile is loaded so open windows re-read their settings
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Profile {
+    pub name: String,
+    pub saved_ts: i64,
+    pub settings: usize,
+}
+
+fn preferences(conn: &Connection) -> Result<Map<String, Value>, String> {
+    let mut stmt = conn
+        .prepare("SELECT key, value FROM settings ORDER BY key")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+    let mut map = Map::new();
+    for row in rows {
+        let (key, raw) = row.map_err(|e| e.to_string())?;
+        // App state stays out of profiles
+        if settings::preference_kind(&key).is_none() {
+            continue;
+        }
+        let value = serde_json::from_str(&raw).map_err(|e| format!("invalid value for setting '{}': {}", key, e))?;
+        map.insert(key, value);
+    }
+    Ok(map)
+}
+
+// Save every current preference under `name`, replacing a profile of that name
+pub fn save_profile(conn: &Connection, name: &str, now: i64) -> Result<Profile, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("profile name cannot be empty".to_string());
+    }
+    let settings = preferences(conn)?;
+    conn.execute(
+        "INSERT INTO settings_profiles (name, settings, saved_ts) VALUES (?1, ?2, ?3)
+         ON CONFLICT(name) DO UPDATE SET settings = excluded.settings, saved_ts = excluded.saved_ts",
+        params![name, Value::Object(settings.clone()).to_string(), now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(Profile {
+        name: name.to_string(),
+        saved_ts: now,
+        settings: settings.len(),
+    })
+}
+
+// Replace every preference with the profile's in one transaction, so
+// preferences the profile didn't have go back to their defaults. Keys that
+// are no longer preferences, or have the wrong type, are ignored.
+pub fn load_profile(conn: &mut Connection, name: &str) -> Result<Profile, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let (raw, saved_ts): (String, i64) = tx
+        .query_row(
+            "SELECT settings, saved_ts FROM settings_profiles WHERE name = ?1",
+            params![name],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("profile '{}' not found", name))?;
+    let settings: Map<String, Value> =
+        serde_json::from_str(&raw).map_err(|e| format!("invalid profile '{}': {}", name, e))?;
+    for key in preferences(&tx)?.keys() {
+        settings::remove(&tx, key)?;
+    }
+    let mut loaded = 0;
+    for (key, value) in &settings {
+        if settings::check_preference(key, value).is_ok() {
+            settings::set(&tx, key, value)?;
+            loaded += 1;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(Profile {
+        name: name.to_string(),
+        saved_ts,
+        settings: loaded,
+    })
+}
+
+// Push the stored preferences into the running app after something rewrote
+// them in bulk (loading a profile, importing settings, a reset): caches,
+// logging, the tick writer, the widget, database watching, menu-bar-only
+// mode, the locale and the menus
+pub fn apply_preferences(app: &AppHandle) -> Result<(), String> {
+    let conn = app.state::<Database>().lock()?;
+    app.state::<DurationDisplay>().reload(&conn);
+    let (log_level, file_logging) = logging::load(&conn)?;
+    let tick_seconds = recovery::tick_persist_seconds(&conn)?;
+    let autohide_ms = settings::get(&conn, widget::AUTOHIDE_SETTING)?.unwrap_or(0);
+    let size = settings::get(&conn, widget::SIZE_SETTING)?.unwrap_or_default();
+    let locale = settings::get::<String>(&conn, i18n::LOCALE_SETTING)?;
+    #[cfg(desktop)]
+    let (emoji, nonactivating, watch) = (
+        settings::get(&conn, tray::EMOJI_SETTING)?.unwrap_or_else(|| tray::DEFAULT_EMOJI.to_string()),
+        settings::get(&conn, widget::NONACTIVATING_SETTING)?.unwrap_or(false),
+        settings::get(&conn, dbwatch::WATCH_SETTING)?.unwrap_or(false),
+    );
+    drop(conn);
+
+    logging::apply(log_level, file_logging);
+    app.state::<TickWriter>().set_interval(tick_seconds);
+    app.state::<WidgetState>().set_autohide_ms(autohide_ms);
+    widget::apply_size(app, size)?;
+    app.state::<I18n>().set_locale(&locale.unwrap_or_else(i18n::system_locale))?;
+    #[cfg(desktop)]
+    {
+        app.state::<tray::TrayEmoji>().set(emoji);
+        widget::set_nonactivating(app, nonactivating)?;
+        dbwatch::set_enabled(app, watch)?;
+        menubar::apply(app, menubar::is_enabled(app))?;
+        crate::app_menu::rebuild(app)?;
+    }
+    tray::refresh_menu(app)
+}
+
+pub fn list_profiles(conn: &Connection) -> Result<Vec<Profile>, String> {
+    let mut stmt = conn
+        .prepare("SELECT name, settings, saved_ts FROM settings_profiles ORDER BY name")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?))
+        })
+        .map_err(|e| e.to_string())?;
+    let mut profiles = Vec::new();
+    for row in rows {
+        let (name, raw, saved_ts) = row.map_err(|e| e.to_string())?;
+        let settings = serde_json::from_str::<Map<String, Value>>(&raw).map_or(0, |map| map.len());
+        profiles.push(Profile { name, saved_ts, settings });
+    }
+    Ok(profiles)
+}
+
+pub fn delete_profile(conn: &Connection, name: &str) -> Result<(), String> {
+    let deleted = conn
+        .execute("DELETE FROM settings_profiles WHERE name = ?1", params![name])
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("profile '{}' not found", name));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{billing, db};
+
+    #[test]
+    fn loading_a_profile_replaces_preferences_but_keeps_state() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        settings::set(&conn, billing::ROUNDING_SETTING, &15).unwrap();
+        settings::set(&conn, widget::VISIBLE_SETTING, &true).unwrap();
+        assert_eq!(save_profile(&conn, " Client work ", 100).unwrap().settings, 1);
+
+        settings::set(&conn, billing::ROUNDING_SETTING, &5).unwrap();
+        settings::set(&conn, i18n::LOCALE_SETTING, &"de").unwrap();
+        settings::set(&conn, widget::VISIBLE_SETTING, &false).unwrap();
+        save_profile(&conn, "Personal", 200).unwrap();
+
+        load_profile(&mut conn, "Client work").unwrap();
+        assert_eq!(settings::get::<u32>(&conn, billing::ROUNDING_SETTING).unwrap(), Some(15));
+        assert_eq!(settings::get::<String>(&conn, i18n::LOCALE_SETTING).unwrap(), None);
+        assert_eq!(settings::get::<bool>(&conn, widget::VISIBLE_SETTING).unwrap(), Some(false));
+
+        let names: Vec<String> = list_profiles(&conn).unwrap().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["Client work", "Personal"]);
+        delete_profile(&conn, "Personal").unwrap();
+        assert!(delete_profile(&conn, "Personal").is_err());
+        assert!(load_profile(&mut conn, "Personal").is_err());
+    }
+}
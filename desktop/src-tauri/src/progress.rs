@@ -0,0 +1,145 @@
+use chrono::{NaiveDate, TimeZone};
+use chrono_tz::Tz;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::i18n::I18n;
+use crate::notify;
+use crate::settings;
+use crate::timefmt;
+
+pub const DAILY_TARGET_SETTING: &str = "daily_target_seconds";
+// Local date the target was last celebrated, so it fires once per day
+const CELEBRATED_SETTING: &str = "daily_target_celebrated";
+pub const DAILY_PROGRESS_EVENT: &str = "daily-progress";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DailyProgress {
+    pub tracked: i64,
+    pub target: i64,
+    pub percent: f64,
+}
+
+// Configured target in seconds; None (or 0) disables progress reporting
+pub fn daily_target(conn: &Connection) -> Result<Option<u64>, String> {
+    Ok(settings::get::<u64>(conn, DAILY_TARGET_SETTING)?.filter(|target| *target > 0))
+}
+
+// [start, end) of the local day containing `now`. Midnight can fall in a DST
+// gap in some zones, in which case the day starts at the first valid instant.
+pub fn day_bounds(tz: &Tz, now: i64) -> (NaiveDate, i64, i64) {
+    let today = timefmt::to_local(now, tz).date_naive();
+    let start_of = |date: NaiveDate| {
+        let midnight = date.and_hms_opt(0, 0, 0).expect("midnight is valid");
+        tz.from_local_datetime(&midnight)
+            .earliest()
+            .or_else(|| tz.from_local_datetime(&(midnight + chrono::Duration::hours(1))).earliest())
+            .map(|dt| dt.timestamp())
+            .unwrap_or(now)
+    };
+    let tomorrow = today.succ_opt().unwrap_or(today);
+    (today, start_of(today), start_of(tomorrow))
+}
+
+// Local date, start and end of the seven days from the one containing `ts`
+pub fn week_days(tz: &Tz, ts: i64) -> Vec<(NaiveDate, i64, i64)> {
+    let mut days = Vec::with_capacity(7);
+    let mut cursor = ts;
+    for _ in 0..7 {
+        let day = day_bounds(tz, cursor);
+        cursor = day.2;
+        days.push(day);
+    }
+    days
+}
+
+// Time tracked during the local day so far, clipping entries that cross
+// midnight and counting a running entry up to `now`
+pub fn tracked_today(conn: &Connection, tz: &Tz, now: i64) -> Result<i64, String> {
+    let (_, day_start, day_end) = day_bounds(tz, now);
+    conn.query_row(
+        "SELECT COALESCE(SUM(MIN(COALESCE(end_ts, ?3), ?2, ?3) - MAX(start_ts, ?1)), 0)
+         FROM entries
+         WHERE start_ts < ?2 AND COALESCE(end_ts, ?3) > ?1",
+        params![day_start, day_end, now],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+pub fn today_progress(conn: &Connection, tz: &Tz, now: i64) -> Result<Option<DailyProgress>, String> {
+    let Some(target) = daily_target(conn)? else {
+        return Ok(None);
+    };
+    let tracked = tracked_today(conn, tz, now)?.max(0);
+    let target = target as i64;
+    Ok(Some(DailyProgress {
+        tracked,
+        target,
+        percent: (tracked as f64 / target as f64 * 100.0).min(100.0),
+    }))
+}
+
+// Record that today's target was reached; true only the first time each day
+fn first_celebration(conn: &Connection, today: NaiveDate) -> Result<bool, String> {
+    let today = today.format("%Y-%m-%d").to_string();
+    if settings::get::<String>(conn, CELEBRATED_SETTING)?.as_deref() == Some(today.as_str()) {
+        return Ok(false);
+    }
+    settings::set(conn, CELEBRATED_SETTING, &today)?;
+    Ok(true)
+}
+
+// Emit today's progress and celebrate the first time the target is reached.
+// Does nothing while no target is set.
+pub fn report(app: &AppHandle, conn: &Connection, now: i64) -> Result<(), String> {
+    let tz = timefmt::configured_zone(conn)?;
+    let Some(progress) = today_progress(conn, &tz, now)? else {
+        return Ok(());
+    };
+    let reached = progress.tracked >= progress.target;
+    let _ = app.emit(DAILY_PROGRESS_EVENT, &progress);
+
+    if reached && first_celebration(conn, day_bounds(&tz, now).0)? {
+        let i18n = app.state::<I18n>();
+        notify::show(
+            app,
+            Some(conn),
+            &i18n.t("notify.daily_target_title"),
+            &i18n.t("notify.daily_target_body"),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db, entries};
+    use chrono::Utc;
+
+    #[test]
+    fn tracked_today_clips_to_the_local_day() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        // New York is UTC-4 in June, so the local day starts at 04:00 UTC
+        let tz: Tz = "America/New_York".parse().unwrap();
+        let at = |h, m| Utc.with_ymd_and_hms(2025, 6, 2, h, m, 0).unwrap().timestamp();
+        entries::insert_entry(&conn, "A", "", at(3, 0), Some(at(5, 0))).unwrap();
+        entries::insert_entry(&conn, "A", "", at(12, 0), None).unwrap();
+        let now = at(12, 30);
+
+        assert_eq!(tracked_today(&conn, &tz, now).unwrap(), 3600 + 1800);
+        assert_eq!(today_progress(&conn, &tz, now).unwrap(), None);
+
+        settings::set(&conn, DAILY_TARGET_SETTING, &10_800u64).unwrap();
+        let progress = today_progress(&conn, &tz, now).unwrap().unwrap();
+        assert_eq!((progress.tracked, progress.target), (5400, 10_800));
+        assert_eq!(progress.percent, 50.0);
+
+        let today = day_bounds(&tz, now).0;
+        assert!(first_celebration(&conn, today).unwrap());
+        assert!(!first_celebration(&conn, today).unwrap());
+    }
+}
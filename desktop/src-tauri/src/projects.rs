@@ -0,0 +1,489 @@
+use std::collections::BTreeMap;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::billing;
+use crate::budget;
+use crate::entries::{self, Entry, EntryFilter};
+use crate::presets::{self, Preset};
+use crate::recurrence::{self, Recurrence};
+use crate::settings;
+use crate::tags;
+use crate::timer;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProjectInfo {
+    pub name: String,
+    pub entry_count: i64,
+    // Finished entries only; a running timer counts once stopped
+    pub total_seconds: i64,
+    // Start of the most recent entry; None for projects only configured so far
+    pub last_used_ts: Option<i64>,
+}
+
+// Every project name in use, from entries and from presets, recurrences,
+// rates and budgets that may not have an entry yet. Most recently used first.
+pub fn list_projects(conn: &Connection) -> Result<Vec<ProjectInfo>, String> {
+    let mut stmt = conn
+        .prepare(
+            "WITH names AS (
+                 SELECT project FROM entries
+                 UNION SELECT project FROM presets
+                 UNION SELECT project FROM recurrences
+                 UNION SELECT key FROM json_each((SELECT value FROM settings WHERE key = ?1))
+                 UNION SELECT key FROM json_each((SELECT value FROM settings WHERE key = ?2))
+             )
+             SELECT n.project,
+                    COUNT(e.id),
+                    COALESCE(SUM(MAX(e.end_ts - e.start_ts, 0)), 0),
+                    MAX(e.start_ts)
+             FROM names n
+             LEFT JOIN entries e ON e.project = n.project
+             WHERE n.project != ''
+             GROUP BY n.project
+             ORDER BY MAX(e.start_ts) IS NULL, MAX(e.start_ts) DESC, n.project",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![billing::RATES_SETTING, budget::BUDGETS_SETTING], |row| {
+            Ok(ProjectInfo {
+                name: row.get(0)?,
+                entry_count: row.get(1)?,
+                total_seconds: row.get(2)?,
+                last_used_ts: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())
+}
+
+// Whether any entry, preset, recurrence, rate or budget refers to `name`
+pub fn project_exists(conn: &Connection, name: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM entries WHERE project = ?1)
+             OR EXISTS (SELECT 1 FROM presets WHERE project = ?1)
+             OR EXISTS (SELECT 1 FROM recurrences WHERE project = ?1)
+             OR EXISTS (SELECT 1 FROM json_each((SELECT value FROM settings WHERE key = ?2)) WHERE key = ?1)
+             OR EXISTS (SELECT 1 FROM json_each((SELECT value FROM settings WHERE key = ?3)) WHERE key = ?1)",
+        params![name, billing::RATES_SETTING, budget::BUDGETS_SETTING],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+// Rename `old` on every entry, preset and recurrence in one transaction and
+// return the ids of the renamed entries. Renaming onto an existing project is
+// refused unless `merge` is set, which folds `old` into it.
+pub fn rename_project(conn: &mut Connection, old: &str, new: &str, merge: bool) -> Result<Vec<i64>, String> {
+    let new = new.trim();
+    if new.is_empty() {
+        return Err("project name cannot be empty".to_string());
+    }
+    if old == new {
+        return Ok(Vec::new());
+    }
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    if !project_exists(&tx, old)? {
+        return Err(format!("no project named '{}'", old));
+    }
+    if !merge && project_exists(&tx, new)? {
+        return Err(format!("project '{}' already exists; pass merge to combine them", new));
+    }
+
+    let ids = reassign(&tx, old, new)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(ids)
+}
+
+// Projects whose names only differ by case or surrounding whitespace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DuplicateGroup {
+    // Trimmed, lowercased name shared by the group
+    pub normalized: String,
+    // Most entries first, the usual pick for the name to keep
+    pub projects: Vec<ProjectInfo>,
+}
+
+pub fn find_duplicates(conn: &Connection) -> Result<Vec<DuplicateGroup>, String> {
+    let mut groups: BTreeMap<String, Vec<ProjectInfo>> = BTreeMap::new();
+    for project in list_projects(conn)? {
+        groups.entry(project.name.trim().to_lowercase()).or_default().push(project);
+    }
+    Ok(groups
+        .into_iter()
+        .filter(|(_, projects)| projects.len() > 1)
+        .map(|(normalized, mut projects)| {
+            projects.sort_by(|a, b| b.entry_count.cmp(&a.entry_count).then_with(|| a.name.cmp(&b.name)));
+            DuplicateGroup { normalized, projects }
+        })
+        .collect())
+}
+
+// Fold every project in `others` into `canonical` in one transaction,
+// returning the ids of the moved entries
+pub fn merge_group(conn: &mut Connection, canonical: &str, others: &[String]) -> Result<Vec<i64>, String> {
+    if canonical.trim().is_empty() {
+        return Err("project name cannot be empty".to_string());
+    }
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut ids = Vec::new();
+    for other in others.iter().filter(|other| other.as_str() != canonical) {
+        if !project_exists(&tx, other)? {
+            return Err(format!("no project named '{}'", other));
+        }
+        ids.extend(reassign(&tx, other, canonical)?);
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(ids)
+}
+
+// Move entries, presets, recurrences and per-project settings from `old` to
+// `new`, returning the moved entry ids. Where both have a color, rate or
+// budget, the one `new` already has is kept.
+fn reassign(conn: &Connection, old: &str, new: &str) -> Result<Vec<i64>, String> {
+    let ids = {
+        let mut stmt = conn
+            .prepare("SELECT id FROM entries WHERE project = ?1 ORDER BY id")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![old], |row| row.get::<_, i64>(0))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| e.to_string())?
+    };
+    for table in ["entries", "presets", "recurrences"] {
+        conn.execute(
+            &format!("UPDATE {} SET project = ?1 WHERE project = ?2", table),
+            params![new, old],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    // On a merge the project being merged into keeps its color
+    conn.execute(
+        "UPDATE OR IGNORE project_colors SET project = ?1 WHERE project = ?2",
+        params![new, old],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM project_colors WHERE project = ?1", params![old])
+        .map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE categorization_rules SET project = ?1 WHERE project = ?2",
+        params![new, old],
+    )
+    .map_err(|e| e.to_string())?;
+    let mut rates = billing::rates(conn)?;
+    if let Some(rate) = rates.remove(old) {
+        rates.entry(new.to_string()).or_insert(rate);
+        settings::set(conn, billing::RATES_SETTING, &rates)?;
+    }
+    let mut budgets = budget::budgets(conn)?;
+    if let Some(seconds) = budgets.remove(old) {
+        budgets.entry(new.to_string()).or_insert(seconds);
+        settings::set(conn, budget::BUDGETS_SETTING, &budgets)?;
+    }
+    timer::rename_recent(conn, old, new)?;
+    Ok(ids)
+}
+
+// What `delete_project` removes; settings are the project's presets,
+// recurrences, color, rate and budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteMode {
+    EntriesOnly,
+    SettingsOnly,
+    All,
+}
+
+impl DeleteMode {
+    pub fn parse(mode: &str) -> Result<Self, String> {
+        match mode {
+            "entries_only" => Ok(Self::EntriesOnly),
+            "settings_only" => Ok(Self::SettingsOnly),
+            "all" => Ok(Self::All),
+            other => Err(format!(
+                "unknown delete mode '{}', expected entries_only, settings_only or all",
+                other
+            )),
+        }
+    }
+}
+
+// A project's explicit color, hourly rate and weekly budget, where set
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectSettings {
+    pub project: String,
+    pub color: Option<String>,
+    pub rate: Option<i64>,
+    pub budget: Option<i64>,
+}
+
+impl ProjectSettings {
+    fn count(&self) -> usize {
+        [self.color.is_some(), self.rate.is_some(), self.budget.is_some()]
+            .iter()
+            .filter(|set| **set)
+            .count()
+    }
+}
+
+// Everything `delete_project` removed, kept so it can be undone
+#[derive(Debug, Clone, Default)]
+pub struct DeletedProject {
+    pub entries: Vec<Entry>,
+    // (entry_id, tags) of the deleted entries that had tags
+    pub tags: Vec<(i64, Vec<String>)>,
+    pub presets: Vec<Preset>,
+    pub recurrences: Vec<Recurrence>,
+    pub runs: Vec<(i64, i64)>,
+    pub settings: ProjectSettings,
+}
+
+impl DeletedProject {
+    pub fn count(&self) -> usize {
+        self.entries.len() + self.presets.len() + self.recurrences.len() + self.settings.count()
+    }
+}
+
+// Remove a project's finished entries with their tags, its settings (presets,
+// recurrences, color, rate and budget), or both, in one transaction. A running
+// timer's open entry is left alone.
+pub fn delete_project(conn: &mut Connection, name: &str, mode: DeleteMode) -> Result<DeletedProject, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut deleted = DeletedProject::default();
+
+    if mode != DeleteMode::SettingsOnly {
+        let filter = EntryFilter {
+            project: Some(name.to_string()),
+            ..Default::default()
+        };
+        deleted.entries = entries::list_entries(&tx, &filter, entries::EntrySort::StartAsc, u32::MAX, 0)?
+            .items
+            .into_iter()
+            .filter(|entry| entry.end_ts.is_some())
+            .collect();
+        for entry in &deleted.entries {
+            let entry_tags = tags::entry_tags(&tx, entry.id)?;
+            if !entry_tags.is_empty() {
+                tags::remove_tags(&tx, entry.id)?;
+                deleted.tags.push((entry.id, entry_tags));
+            }
+        }
+        tx.execute(
+            "DELETE FROM entries WHERE project = ?1 AND end_ts IS NOT NULL",
+            params![name],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    if mode != DeleteMode::EntriesOnly {
+        deleted.presets = presets::list_presets(&tx)?
+            .into_iter()
+            .filter(|preset| preset.project == name)
+            .collect();
+        deleted.recurrences = recurrence::list_recurrences(&tx)?
+            .into_iter()
+            .filter(|r| r.project == name)
+            .collect();
+        {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT r.recurrence_id, r.occurrence_ts FROM recurrence_runs r
+                     JOIN recurrences c ON c.id = r.recurrence_id
+                     WHERE c.project = ?1",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![name], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| e.to_string())?;
+            deleted.runs = rows
+                .collect::<rusqlite::Result<Vec<_>>>()
+                .map_err(|e| e.to_string())?;
+        }
+        tx.execute(
+            "DELETE FROM recurrence_runs WHERE recurrence_id IN
+             (SELECT id FROM recurrences WHERE project = ?1)",
+            params![name],
+        )
+        .map_err(|e| e.to_string())?;
+        for table in ["recurrences", "presets"] {
+            tx.execute(&format!("DELETE FROM {} WHERE project = ?1", table), params![name])
+                .map_err(|e| e.to_string())?;
+        }
+        deleted.settings = remove_settings(&tx, name)?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(deleted)
+}
+
+// Remove a project's color, rate and budget, returning what it had
+fn remove_settings(conn: &Connection, name: &str) -> Result<ProjectSettings, String> {
+    let color = conn
+        .query_row("SELECT color FROM project_colors WHERE project = ?1", params![name], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM project_colors WHERE project = ?1", params![name])
+        .map_err(|e| e.to_string())?;
+    let mut rates = billing::rates(conn)?;
+    let rate = rates.remove(name);
+    if rate.is_some() {
+        settings::set(conn, billing::RATES_SETTING, &rates)?;
+    }
+    let mut budgets = budget::budgets(conn)?;
+    let budget = budgets.remove(name);
+    if budget.is_some() {
+        settings::set(conn, budget::BUDGETS_SETTING, &budgets)?;
+    }
+    Ok(ProjectSettings {
+        project: name.to_string(),
+        color,
+        rate,
+        budget,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::undo::{UndoOp, UndoStack};
+
+    #[test]
+    fn merges_projects_differing_by_case_and_whitespace() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        entries::insert_entry(&conn, "Acme", "", 100, Some(200)).unwrap();
+        entries::insert_entry(&conn, "Acme", "", 300, Some(400)).unwrap();
+        let stray = entries::insert_entry(&conn, "acme ", "", 500, Some(600)).unwrap();
+        entries::insert_entry(&conn, "Beta", "", 700, Some(800)).unwrap();
+        billing::set_rate(&conn, "acme ", Some(9_000)).unwrap();
+
+        let groups = find_duplicates(&conn).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].normalized, "acme");
+        let names: Vec<&str> = groups[0].projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["Acme", "acme "]);
+
+        assert_eq!(merge_group(&mut conn, "Acme", &["acme ".to_string()]).unwrap(), vec![stray.id]);
+        assert!(find_duplicates(&conn).unwrap().is_empty());
+        assert_eq!(billing::rates(&conn).unwrap().get("Acme"), Some(&9_000));
+        assert!(merge_group(&mut conn, "Acme", &["Gamma".to_string()]).is_err());
+    }
+
+    #[test]
+    fn lists_used_projects_by_recency_then_configured_ones() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        entries::insert_entry(&conn, "Acme", "", 100, Some(400)).unwrap();
+        entries::insert_entry(&conn, "Acme", "", 1_000, None).unwrap();
+        entries::insert_entry(&conn, "Beta", "", 500, Some(560)).unwrap();
+        let preset = Preset {
+            name: "Standup".to_string(),
+            project: "Gamma".to_string(),
+            default_note: String::new(),
+            target_minutes: None,
+        };
+        presets::save_preset(&conn, &preset).unwrap();
+        billing::set_rate(&conn, "Delta", Some(9_000)).unwrap();
+        budget::set_budget(&conn, "Epsilon", Some(3_600)).unwrap();
+
+        let projects = list_projects(&conn).unwrap();
+        let summary: Vec<(&str, i64, i64, Option<i64>)> = projects
+            .iter()
+            .map(|p| (p.name.as_str(), p.entry_count, p.total_seconds, p.last_used_ts))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Acme", 2, 300, Some(1_000)),
+                ("Beta", 1, 60, Some(500)),
+                ("Delta", 0, 0, None),
+                ("Epsilon", 0, 0, None),
+                ("Gamma", 0, 0, None),
+            ]
+        );
+        assert!(project_exists(&conn, "Delta").unwrap());
+        assert!(project_exists(&conn, "Epsilon").unwrap());
+        assert!(!project_exists(&conn, "Zeta").unwrap());
+    }
+
+    #[test]
+    fn rename_refuses_existing_names_unless_merging() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let a = entries::insert_entry(&conn, "Acme", "", 100, Some(200)).unwrap();
+        let b = entries::insert_entry(&conn, "Beta", "", 300, Some(400)).unwrap();
+
+        let err = rename_project(&mut conn, "Acme", "Beta", false).unwrap_err();
+        assert!(err.contains("already exists"), "{}", err);
+        assert!(rename_project(&mut conn, "Nope", "Other", false).is_err());
+
+        assert_eq!(rename_project(&mut conn, "Acme", "Acme Corp", false).unwrap(), vec![a.id]);
+        assert_eq!(entries::get_entry(&conn, a.id).unwrap().project, "Acme Corp");
+
+        assert_eq!(rename_project(&mut conn, "Beta", "Acme Corp", true).unwrap(), vec![b.id]);
+        let projects = list_projects(&conn).unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].entry_count, 2);
+    }
+
+    #[test]
+    fn delete_project_modes_and_undo() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let done = entries::insert_entry(&conn, "Test", "", 100, Some(200)).unwrap();
+        let open = entries::insert_entry(&conn, "Test", "", 300, None).unwrap();
+        let preset = Preset {
+            name: "T".to_string(),
+            project: "Test".to_string(),
+            default_note: String::new(),
+            target_minutes: Some(25),
+        };
+        presets::save_preset(&conn, &preset).unwrap();
+        crate::colors::set_project_color(&conn, "Test", Some("#112233")).unwrap();
+        billing::set_rate(&conn, "Test", Some(9_000)).unwrap();
+        budget::set_budget(&conn, "Test", Some(3_600)).unwrap();
+
+        let deleted = delete_project(&mut conn, "Test", DeleteMode::SettingsOnly).unwrap();
+        assert_eq!((deleted.entries.len(), deleted.presets.len()), (0, 1));
+        assert_eq!(deleted.settings.count(), 3);
+        assert!(entries::get_entry(&conn, done.id).is_ok());
+        assert!(billing::rates(&conn).unwrap().is_empty());
+        assert!(budget::budgets(&conn).unwrap().is_empty());
+
+        let stack = UndoStack::default();
+        stack
+            .push(
+                "delete project",
+                vec![
+                    UndoOp::RestorePresets(deleted.presets),
+                    UndoOp::RestoreProjectSettings(deleted.settings),
+                ],
+            )
+            .unwrap();
+        stack.undo(&mut conn).unwrap();
+        assert_eq!(presets::get_preset(&conn, "T").unwrap(), preset);
+        assert_eq!(crate::colors::project_color(&conn, "Test").unwrap(), "#112233");
+        assert_eq!(billing::rates(&conn).unwrap().get("Test"), Some(&9_000));
+        assert_eq!(budget::budgets(&conn).unwrap().get("Test"), Some(&3_600));
+
+        tags::set_entry_tags(&mut conn, done.id, &["billable".to_string()]).unwrap();
+        let deleted = delete_project(&mut conn, "Test", DeleteMode::All).unwrap();
+        assert_eq!(deleted.count(), 5);
+        assert!(entries::get_entry(&conn, done.id).is_err());
+        assert!(tags::entry_tags(&conn, done.id).unwrap().is_empty());
+        // The running timer's entry survives
+        assert!(entries::get_entry(&conn, open.id).is_ok());
+        stack
+            .push(
+                "delete project",
+                vec![UndoOp::RestoreEntries(deleted.entries), UndoOp::RestoreTags(deleted.tags)],
+            )
+            .unwrap();
+        stack.undo(&mut conn).unwrap();
+        assert_eq!(tags::entry_tags(&conn, done.id).unwrap(), vec!["billable"]);
+        assert!(DeleteMode::parse("everything").is_err());
+    }
+}
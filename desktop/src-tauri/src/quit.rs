@@ -0,0 +1,112 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tauri::{AppHandle, Emitter, ExitRequestApi, Manager};
+
+use crate::db::{self, Database};
+use crate::menubar;
+use crate::settings;
+use crate::timer::{self, Timer, TimerId};
+use crate::widget;
+
+pub const CONFIRM_QUIT_SETTING: &str = "confirm_quit_while_running";
+pub const CONFIRM_QUIT_EVENT: &str = "confirm-quit";
+pub const CLOSE_TO_TRAY_SETTING: &str = "close_to_tray";
+
+// Set once the user has answered the prompt, so the exit it triggers goes
+// through, and whether they chose to quit without saving
+#[derive(Default)]
+pub struct QuitState {
+    confirmed: AtomicBool,
+    discard: AtomicBool,
+}
+
+// Whether running timers are saved when the app exits; false only after the
+// user chose to quit without saving
+pub fn saves_on_exit(app: &AppHandle) -> bool {
+    app.try_state::<QuitState>()
+        .map_or(true, |state| !state.discard.load(Ordering::SeqCst))
+}
+
+// Quitting needs the user's answer when the setting is on and a timer runs
+fn needs_confirmation(app: &AppHandle) -> bool {
+    if app.state::<QuitState>().confirmed.load(Ordering::SeqCst) {
+        return false;
+    }
+    let running = app
+        .state::<Timer>()
+        .lock()
+        .map(|timers| !timers.is_empty())
+        .unwrap_or(false);
+    running
+        && app
+            .state::<Database>()
+            .lock()
+            .and_then(|conn| settings::get(&conn, CONFIRM_QUIT_SETTING))
+            .ok()
+            .flatten()
+            .unwrap_or(false)
+}
+
+// Ask the frontend instead of exiting; the main window (the widget in
+// menu-bar-only mode) is shown and is the only one sent the prompt
+fn ask(app: &AppHandle) {
+    menubar::show_main(app);
+    let _ = app.emit_to(menubar::ui_label(app), CONFIRM_QUIT_EVENT, ());
+}
+
+// Whether closing the main window only hides it to the tray (the default)
+pub fn close_to_tray(app: &AppHandle) -> bool {
+    app.state::<Database>()
+        .lock()
+        .and_then(|conn| settings::get(&conn, CLOSE_TO_TRAY_SETTING))
+        .ok()
+        .flatten()
+        .unwrap_or(true)
+}
+
+// Quit from the tray or app menu
+pub fn request_quit(app: &AppHandle) {
+    if needs_confirmation(app) {
+        ask(app);
+    } else {
+        app.exit(0);
+    }
+}
+
+// Also catches quits that bypass our handlers, such as the native Quit item
+pub fn on_exit_requested(app: &AppHandle, api: &ExitRequestApi) {
+    if needs_confirmation(app) {
+        api.prevent_exit();
+        ask(app);
+        return;
+    }
+    widget::save_visibility(app);
+}
+
+fn stop_all(app: &AppHandle) -> Result<(), String> {
+    let timer_state = app.state::<Timer>();
+    let db = app.state::<Database>();
+    let mut timers = timer_state.lock()?;
+    let conn = db.lock()?;
+    let min_seconds = timer::min_entry_seconds(&conn)?;
+    let now = db::now_ts();
+    let ids: Vec<TimerId> = timers.iter().map(|(id, _)| id).collect();
+    for id in ids {
+        timer::stop(&conn, &mut timers, id, min_seconds, now)?;
+    }
+    Ok(())
+}
+
+// Answer to `confirm-quit`: stop and save every running timer first if
+// `save`, else have the exit drop their open entries. Not calling it
+// cancels the quit.
+pub fn confirm(app: &AppHandle, save: bool) -> Result<(), String> {
+    if save {
+        stop_all(app)?;
+    }
+    let state = app.state::<QuitState>();
+    state.discard.store(!save, Ordering::SeqCst);
+    state.confirmed.store(true, Ordering::SeqCst);
+    app.exit(0);
+    Ok(())
+}
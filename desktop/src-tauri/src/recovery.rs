@@ -0,0 +1,369 @@
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{async_runtime, AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+use crate::db::{self, Database};
+use crate::entries::{self, Entry};
+use crate::quit;
+use crate::settings;
+use crate::tags;
+use crate::timer::{self, Timer, TimerState};
+
+pub const LAST_TICK_SETTING: &str = "last_tick_ts";
+pub const CRASH_RECOVERY_EVENT: &str = "crash-recovery";
+
+// How often the last tick is written while a timer runs, i.e. the most time
+// `close_at_last_tick` can lose
+pub const TICK_PERSIST_SETTING: &str = "tick_persist_seconds";
+pub const DEFAULT_TICK_PERSIST_SECONDS: u64 = 30;
+const MAX_TICK_PERSIST_SECONDS: u64 = 3600;
+
+// Open entries left behind by a session that didn't exit cleanly
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Orphan {
+    pub entries: Vec<Entry>,
+    pub start_ts: i64,
+    pub last_tick_ts: Option<i64>,
+}
+
+// The orphan found at startup, until the user decides what to do with it
+#[derive(Default)]
+pub struct Recovery(Mutex<Option<Orphan>>);
+
+impl Recovery {
+    pub fn pending(&self) -> Option<Orphan> {
+        self.0.lock().ok().and_then(|pending| pending.clone())
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut pending) = self.0.lock() {
+            *pending = None;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoverAction {
+    Resume,
+    CloseAtLastTick,
+    Discard,
+}
+
+impl RecoverAction {
+    pub fn parse(action: &str) -> Result<Self, String> {
+        match action {
+            "resume" => Ok(Self::Resume),
+            "close_at_last_tick" => Ok(Self::CloseAtLastTick),
+            "discard" => Ok(Self::Discard),
+            other => Err(format!(
+                "unknown recovery action '{}', expected resume, close_at_last_tick or discard",
+                other
+            )),
+        }
+    }
+}
+
+// Coalesces tick writes: the ticker only stores the latest timestamp, and a
+// background task writes it at most once per interval, or right away when
+// flushed, so neither the tick loop nor commands wait on the disk
+pub struct TickWriter {
+    // 0 when nothing is waiting to be written
+    pending: AtomicI64,
+    interval_secs: AtomicU64,
+    flush: Notify,
+}
+
+impl TickWriter {
+    pub fn new(interval_secs: u64) -> Self {
+        Self {
+            pending: AtomicI64::new(0),
+            interval_secs: AtomicU64::new(interval_secs),
+            flush: Notify::new(),
+        }
+    }
+
+    pub fn record(&self, now: i64) {
+        self.pending.store(now, Ordering::Relaxed);
+    }
+
+    // Record `now` and write it without waiting for the interval
+    pub fn persist_now(&self, now: i64) {
+        self.record(now);
+        self.flush.notify_one();
+    }
+
+    pub fn set_interval(&self, seconds: u64) {
+        self.interval_secs.store(seconds, Ordering::Relaxed);
+        // Start the new interval right away
+        self.flush.notify_one();
+    }
+}
+
+pub fn tick_persist_seconds(conn: &Connection) -> Result<u64, String> {
+    Ok(settings::get(conn, TICK_PERSIST_SETTING)?.unwrap_or(DEFAULT_TICK_PERSIST_SECONDS))
+}
+
+pub fn set_tick_persist_seconds(app: &AppHandle, seconds: u64) -> Result<(), String> {
+    if !(1..=MAX_TICK_PERSIST_SECONDS).contains(&seconds) {
+        return Err(format!("interval must be between 1 and {} seconds", MAX_TICK_PERSIST_SECONDS));
+    }
+    settings::set(&*app.state::<Database>().lock()?, TICK_PERSIST_SETTING, &seconds)?;
+    app.state::<TickWriter>().set_interval(seconds);
+    Ok(())
+}
+
+pub fn spawn_tick_writer(app: AppHandle) {
+    async_runtime::spawn(async move {
+        loop {
+            let writer = app.state::<TickWriter>();
+            let interval = Duration::from_secs(writer.interval_secs.load(Ordering::Relaxed));
+            let _ = tokio::time::timeout(interval, writer.flush.notified()).await;
+            let ts = writer.pending.swap(0, Ordering::Relaxed);
+            if ts == 0 {
+                continue;
+            }
+            let result = app
+                .state::<Database>()
+                .lock()
+                .and_then(|conn| record_tick(&conn, ts));
+            match result {
+                Ok(()) => {}
+                // Nothing to recover into while the database is locked
+                Err(e) if db::is_unavailable(&e) => {}
+                Err(e) => log::warn!("failed to persist timer tick: {}", e),
+            }
+        }
+    });
+}
+
+// Open entries no running timer owns
+fn orphaned_entries(conn: &Connection, timers: &TimerState) -> Result<Vec<Entry>, String> {
+    let owned = timers.open_entries();
+    let mut entries = entries::open_entries(conn)?;
+    entries.retain(|entry| !owned.contains(&entry.id));
+    Ok(entries)
+}
+
+// Return any open entry no timer owns. A graceful exit closes every running
+// timer, so such an entry means the last session ended abnormally (or left
+// one behind some other way, which keeps growing in every report just the same).
+pub fn begin_session(conn: &Connection, timers: &TimerState) -> Result<Option<Orphan>, String> {
+    let entries = orphaned_entries(conn, timers)?;
+    let Some(start_ts) = entries.iter().map(|entry| entry.start_ts).min() else {
+        return Ok(None);
+    };
+    Ok(Some(Orphan {
+        entries,
+        start_ts,
+        last_tick_ts: settings::get(conn, LAST_TICK_SETTING)?,
+    }))
+}
+
+// Stop running timers at `now` so no open entry outlives the session and
+// record the last tick. Returns the closed entries. Without `save` (the
+// user quit without saving) the open entries are deleted instead.
+pub fn end_session(conn: &Connection, timers: &mut TimerState, now: i64, save: bool) -> Result<Vec<i64>, String> {
+    let closed = if save {
+        timer::stop_active_at(conn, timers, now, now)?
+            .into_iter()
+            .map(|capped| capped.entry.id)
+            .collect()
+    } else {
+        for entry_id in timers.open_entries() {
+            conn.execute("DELETE FROM entries WHERE id = ?1", params![entry_id])
+                .map_err(|e| e.to_string())?;
+            tags::remove_tags(conn, entry_id)?;
+        }
+        Vec::new()
+    };
+    timers.clear();
+    record_tick(conn, now)?;
+    Ok(closed)
+}
+
+pub fn record_tick(conn: &Connection, now: i64) -> Result<(), String> {
+    settings::set(conn, LAST_TICK_SETTING, &now)
+}
+
+// Apply the user's choice to the orphaned entries and return their ids.
+// Resuming turns each one back into a running timer; closing ends it at the
+// last persisted tick (or its start, if none came after it).
+pub fn recover(
+    conn: &mut Connection,
+    timers: &mut TimerState,
+    action: RecoverAction,
+    now: i64,
+) -> Result<Vec<i64>, String> {
+    let orphans = orphaned_entries(conn, timers)?;
+    let ids: Vec<i64> = orphans.iter().map(|entry| entry.id).collect();
+    match action {
+        RecoverAction::Resume => {
+            for entry in &orphans {
+                timer::adopt(timers, entry);
+            }
+        }
+        RecoverAction::CloseAtLastTick => {
+            let last_tick: Option<i64> = settings::get(conn, LAST_TICK_SETTING)?;
+            let tx = conn.transaction().map_err(|e| e.to_string())?;
+            for entry in &orphans {
+                let end_ts = last_tick.unwrap_or(entry.start_ts).clamp(entry.start_ts, now.max(entry.start_ts));
+                tx.execute("UPDATE entries SET end_ts = ?1 WHERE id = ?2", params![end_ts, entry.id])
+                    .map_err(|e| e.to_string())?;
+            }
+            tx.commit().map_err(|e| e.to_string())?;
+        }
+        RecoverAction::Discard => {
+            let tx = conn.transaction().map_err(|e| e.to_string())?;
+            for id in &ids {
+                tx.execute("DELETE FROM entries WHERE id = ?1", params![id])
+                    .map_err(|e| e.to_string())?;
+                tags::remove_tags(&tx, *id)?;
+            }
+            tx.commit().map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(ids)
+}
+
+// Run the startup check once the database is readable (at launch, or after
+// `unlock` for an encrypted one) and tell the frontend about any orphan
+pub fn check(app: &AppHandle) -> Result<(), String> {
+    let timer_state = app.state::<Timer>();
+    let db = app.state::<Database>();
+    let orphan = {
+        let timers = timer_state.lock()?;
+        let conn = db.lock()?;
+        begin_session(&conn, &timers)?
+    };
+    if let Some(orphan) = orphan {
+        log::warn!("previous session ended while tracking; {} orphaned entries", orphan.entries.len());
+        *app.state::<Recovery>().0.lock().map_err(|_| "recovery lock poisoned")? = Some(orphan.clone());
+        let _ = app.emit(CRASH_RECOVERY_EVENT, orphan);
+    }
+    Ok(())
+}
+
+pub fn resolve(app: &AppHandle, action: RecoverAction) -> Result<Vec<i64>, String> {
+    let timer_state = app.state::<Timer>();
+    let db = app.state::<Database>();
+    let mut timers = timer_state.lock()?;
+    let ids = recover(&mut db.lock()?, &mut timers, action, db::now_ts())?;
+    *app.state::<Recovery>().0.lock().map_err(|_| "recovery lock poisoned")? = None;
+    Ok(ids)
+}
+
+// Close running timers on a graceful exit, writing directly since the writer
+// task won't run again; a locked database keeps its open entries, which are
+// recovered on the next unlock
+pub fn on_exit(app: &AppHandle) {
+    if let Some(db) = app.try_state::<Database>() {
+        let timer_state = app.state::<Timer>();
+        let save = quit::saves_on_exit(app);
+        let result = timer_state.lock().and_then(|mut timers| {
+            let conn = db.lock()?;
+            end_session(&conn, &mut timers, db::now_ts(), save).map(|_| ())
+        });
+        if let Err(e) = result {
+            log::warn!("failed to close running timers on exit: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unclean_exit_leaves_an_orphan_to_close_at_the_last_tick() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let mut timers = TimerState::default();
+
+        // First launch ever: nothing to recover
+        assert_eq!(begin_session(&conn, &timers).unwrap(), None);
+        let id = timer::start(&conn, &mut timers, "Acme".to_string(), String::new(), None, 1_000).unwrap();
+        record_tick(&conn, 1_060).unwrap();
+
+        // The process dies: timers are gone, the entry is still open
+        let mut timers = TimerState::default();
+        let orphan = begin_session(&conn, &timers).unwrap().unwrap();
+        assert_eq!((orphan.start_ts, orphan.last_tick_ts), (1_000, Some(1_060)));
+        let entry_id = orphan.entries[0].id;
+
+        assert_eq!(
+            recover(&mut conn, &mut timers, RecoverAction::CloseAtLastTick, 5_000).unwrap(),
+            vec![entry_id]
+        );
+        assert_eq!(entries::get_entry(&conn, entry_id).unwrap().end_ts, Some(1_060));
+        assert!(timers.get(id).is_none());
+
+        // A graceful exit closes running timers and leaves nothing to recover
+        let mut timers = TimerState::default();
+        let running = timer::start(&conn, &mut timers, "Acme".to_string(), String::new(), None, 6_000).unwrap();
+        let entry_id = timers.get(running).unwrap().entry_id.unwrap();
+        assert_eq!(end_session(&conn, &mut timers, 6_600, true).unwrap(), vec![entry_id]);
+        assert_eq!(entries::get_entry(&conn, entry_id).unwrap().end_ts, Some(6_600));
+        assert_eq!(begin_session(&conn, &TimerState::default()).unwrap(), None);
+    }
+
+    #[test]
+    fn quitting_without_saving_drops_the_running_entries() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let mut timers = TimerState::default();
+        let running = timer::start(&conn, &mut timers, "Acme".to_string(), String::new(), None, 1_000).unwrap();
+        let entry_id = timers.get(running).unwrap().entry_id.unwrap();
+        tags::set_entry_tags(&mut conn, entry_id, &["billable".to_string()]).unwrap();
+
+        assert!(end_session(&conn, &mut timers, 1_600, false).unwrap().is_empty());
+        assert!(timers.is_empty());
+        let closed: i64 = conn
+            .query_row("SELECT COUNT(*) FROM entries WHERE end_ts IS NOT NULL", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(closed, 0);
+        assert!(entries::get_entry(&conn, entry_id).is_err());
+        assert!(tags::entry_tags(&conn, entry_id).unwrap().is_empty());
+        assert_eq!(begin_session(&conn, &TimerState::default()).unwrap(), None);
+    }
+
+    #[test]
+    fn open_entries_are_orphans_even_after_a_clean_exit() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let entry = entries::insert_entry(&conn, "Acme", "", 1_000, None).unwrap();
+        let orphan = begin_session(&conn, &TimerState::default()).unwrap().unwrap();
+        assert_eq!(orphan.entries, vec![entry]);
+    }
+
+    #[test]
+    fn resume_adopts_orphans_as_running_timers() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let entry = entries::insert_entry(&conn, "Acme", "notes", 1_000, None).unwrap();
+        let mut timers = TimerState::default();
+
+        recover(&mut conn, &mut timers, RecoverAction::Resume, 2_000).unwrap();
+        let status = timer::status(&timers, None, 2_000);
+        assert_eq!((status.project.as_deref(), status.elapsed_seconds), (Some("Acme"), 1_000));
+        assert_eq!(timers.open_entries(), vec![entry.id]);
+        assert!(RecoverAction::parse("restart").is_err());
+    }
+
+    #[test]
+    fn discarding_orphans_drops_their_tags() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let entry = entries::insert_entry(&conn, "Acme", "", 1_000, None).unwrap();
+        tags::set_entry_tags(&mut conn, entry.id, &["billable".to_string()]).unwrap();
+
+        let mut timers = TimerState::default();
+        assert_eq!(recover(&mut conn, &mut timers, RecoverAction::Discard, 2_000).unwrap(), vec![entry.id]);
+        assert!(entries::get_entry(&conn, entry.id).is_err());
+        assert!(tags::entry_tags(&conn, entry.id).unwrap().is_empty());
+    }
+}
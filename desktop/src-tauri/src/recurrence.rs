@@ -0,0 +1,174 @@
+use chrono::{Datelike, Duration, NaiveTime, TimeZone, Weekday};
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::entries;
+
+// A fixed weekly commitment that is turned into a concrete entry once it has elapsed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recurrence {
+    pub id: i64,
+    pub project: String,
+    pub note: String,
+    pub weekday: String,
+    pub start_hhmm: String,
+    pub duration_min: u32,
+    pub created_ts: i64,
+}
+
+impl Recurrence {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            project: row.get(1)?,
+            note: row.get(2)?,
+            weekday: row.get(3)?,
+            start_hhmm: row.get(4)?,
+            duration_min: row.get(5)?,
+            created_ts: row.get(6)?,
+        })
+    }
+}
+
+const RECURRENCE_COLUMNS: &str =
+    "id, project, note, weekday, start_hhmm, duration_min, created_ts";
+
+pub fn parse_weekday(day: &str) -> Result<Weekday, String> {
+    day.trim()
+        .parse::<Weekday>()
+        .map_err(|_| format!("invalid weekday '{}'", day))
+}
+
+pub fn parse_hhmm(hhmm: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(hhmm.trim(), "%H:%M")
+        .map_err(|_| format!("invalid time '{}', expected HH:MM", hhmm))
+}
+
+pub fn add_recurring(
+    conn: &Connection,
+    project: &str,
+    note: &str,
+    weekday: &str,
+    start_hhmm: &str,
+    duration_min: u32,
+    now: i64,
+) -> Result<Recurrence, String> {
+    let weekday = parse_weekday(weekday)?;
+    let start = parse_hhmm(start_hhmm)?;
+    if duration_min == 0 {
+        return Err("duration must be at least one minute".to_string());
+    }
+    conn.execute(
+        "INSERT INTO recurrences (project, note, weekday, start_hhmm, duration_min, created_ts)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            project,
+            note,
+            weekday.to_string(),
+            start.format("%H:%M").to_string(),
+            duration_min,
+            now
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.query_row(
+        &format!("SELECT {} FROM recurrences WHERE id = ?1", RECURRENCE_COLUMNS),
+        params![conn.last_insert_rowid()],
+        Recurrence::from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+pub fn list_recurrences(conn: &Connection) -> Result<Vec<Recurrence>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM recurrences ORDER BY id",
+            RECURRENCE_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], Recurrence::from_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())
+}
+
+// Entries already created from the recurrence are kept
+pub fn delete_recurrence(conn: &mut Connection, id: i64) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let removed = tx
+        .execute("DELETE FROM recurrences WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    if removed == 0 {
+        return Err(format!("recurrence {} not found", id));
+    }
+    tx.execute(
+        "DELETE FROM recurrence_runs WHERE recurrence_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+// Create entries for every occurrence that has fully elapsed since the
+// recurrence was added. Each occurrence is recorded in `recurrence_runs`, so
+// running this repeatedly (or after a restart) never duplicates an entry, and
+// an entry the user deleted is not recreated. Returns the ids of new entries.
+pub fn materialize<Tz: TimeZone>(
+    conn: &mut Connection,
+    tz: &Tz,
+    now: i64,
+) -> Result<Vec<i64>, String> {
+    let recurrences = list_recurrences(conn)?;
+    let today = tz
+        .timestamp_opt(now, 0)
+        .single()
+        .ok_or("invalid current time")?
+        .date_naive();
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut created = Vec::new();
+    for rec in recurrences {
+        let weekday = parse_weekday(&rec.weekday)?;
+        let start = parse_hhmm(&rec.start_hhmm)?;
+        let duration = rec.duration_min as i64 * 60;
+
+        let mut day = match tz.timestamp_opt(rec.created_ts, 0).single() {
+            Some(created) => created.date_naive(),
+            None => continue,
+        };
+        while day.weekday() != weekday {
+            day += Duration::days(1);
+        }
+
+        while day <= today {
+            // Occurrences inside a DST gap don't exist locally and are skipped
+            if let Some(local) = tz.from_local_datetime(&day.and_time(start)).earliest() {
+                let start_ts = local.timestamp();
+                let end_ts = start_ts + duration;
+                if start_ts >= rec.created_ts && end_ts <= now {
+                    let inserted = tx
+                        .execute(
+                            "INSERT OR IGNORE INTO recurrence_runs (recurrence_id, occurrence_ts)
+                             VALUES (?1, ?2)",
+                            params![rec.id, start_ts],
+                        )
+                        .map_err(|e| e.to_string())?;
+                    if inserted == 1 {
+                        let entry = entries::insert_entry(
+                            &tx,
+                            &rec.project,
+                            &rec.note,
+                            start_ts,
+                            Some(end_ts),
+                        )?;
+                        created.push(entry.id);
+                    }
+                }
+            }
+            day += Duration::days(7);
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(created)
+}
@@ -0,0 +1,127 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::entries::{self, Entry};
+
+// A dangling open entry is closed at the next entry's start, but never more
+// than this long after its own start
+pub const MAX_DANGLING_SECONDS: i64 = 8 * 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepairKind {
+    // end was before start; the two were swapped
+    Reversed,
+    // end was missing on an entry no timer owns; it was closed
+    Dangling,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RepairChange {
+    pub kind: RepairKind,
+    pub before: Entry,
+    pub after: Entry,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RepairReport {
+    pub changes: Vec<RepairChange>,
+}
+
+// Fix invalid time ranges in one transaction. Entries are only ever updated,
+// never deleted, and every change is reported with its previous state so it
+// can be reviewed and undone. `running_ids` are the running timers' entries,
+// which are legitimately open and left alone.
+pub fn repair_entries(
+    conn: &mut Connection,
+    running_ids: &[i64],
+    now: i64,
+) -> Result<RepairReport, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let broken = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT id, end_ts FROM entries
+                 WHERE end_ts IS NULL OR end_ts < start_ts
+                 ORDER BY start_ts, id",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<i64>>(1)?)))
+            .map_err(|e| e.to_string())?;
+        let rows = rows
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| e.to_string())?;
+        rows.into_iter()
+            .filter(|(id, end_ts)| end_ts.is_some() || !running_ids.contains(id))
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>()
+    };
+
+    let mut report = RepairReport::default();
+    for id in broken {
+        let before = entries::get_entry(&tx, id)?;
+        let (kind, start_ts, end_ts) = match before.end_ts {
+            Some(end_ts) => (RepairKind::Reversed, end_ts, before.start_ts),
+            None => {
+                let next_start: Option<i64> = tx
+                    .query_row(
+                        "SELECT MIN(start_ts) FROM entries WHERE start_ts > ?1 AND id != ?2",
+                        params![before.start_ts, id],
+                        |row| row.get(0),
+                    )
+                    .map_err(|e| e.to_string())?;
+                let end_ts = next_start
+                    .unwrap_or(i64::MAX)
+                    .min(before.start_ts + MAX_DANGLING_SECONDS)
+                    .min(now.max(before.start_ts));
+                (RepairKind::Dangling, before.start_ts, end_ts)
+            }
+        };
+        tx.execute(
+            "UPDATE entries SET start_ts = ?1, end_ts = ?2 WHERE id = ?3",
+            params![start_ts, end_ts, id],
+        )
+        .map_err(|e| e.to_string())?;
+        let after = entries::get_entry(&tx, id)?;
+        report.changes.push(RepairChange { kind, before, after });
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn repairs_reversed_and_dangling_but_not_running() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let reversed = entries::insert_entry(&conn, "A", "", 500, Some(400)).unwrap();
+        let dangling = entries::insert_entry(&conn, "A", "", 1000, None).unwrap();
+        let next = entries::insert_entry(&conn, "B", "", 1600, Some(1700)).unwrap();
+        let stale = entries::insert_entry(&conn, "C", "", 2000, None).unwrap();
+        let running = entries::insert_entry(&conn, "D", "", 90_000, None).unwrap();
+
+        let report = repair_entries(&mut conn, &[running.id], 100_000).unwrap();
+        let fixed: Vec<_> = report
+            .changes
+            .iter()
+            .map(|c| (c.kind, c.after.id, c.after.start_ts, c.after.end_ts))
+            .collect();
+        assert_eq!(
+            fixed,
+            vec![
+                (RepairKind::Reversed, reversed.id, 400, Some(500)),
+                (RepairKind::Dangling, dangling.id, 1000, Some(1600)),
+                (RepairKind::Dangling, stale.id, 2000, Some(2000 + MAX_DANGLING_SECONDS)),
+            ]
+        );
+        assert_eq!(entries::get_entry(&conn, next.id).unwrap(), next);
+        assert_eq!(entries::get_entry(&conn, running.id).unwrap().end_ts, None);
+        let again = repair_entries(&mut conn, &[running.id], 100_000).unwrap();
+        assert!(again.changes.is_empty());
+    }
+}
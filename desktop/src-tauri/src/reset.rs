@@ -0,0 +1,91 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rusqlite::Connection;
+
+use crate::db;
+
+pub const DATA_RESET_EVENT: &str = "data-reset";
+
+// A token must be used this soon after it was requested
+const TOKEN_TTL: Duration = Duration::from_secs(60);
+
+// The single outstanding reset token. Requiring it makes a reset a two-step
+// handshake, so one misfired IPC call can't wipe everything.
+#[derive(Default)]
+pub struct ResetToken(Mutex<Option<(String, Instant)>>);
+
+impl ResetToken {
+    // Issue a fresh token, replacing any earlier one
+    pub fn issue(&self) -> Result<String, String> {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_i64(db::now_ts());
+        hasher.write_u32(std::process::id());
+        let token = format!("{:016x}", hasher.finish());
+        *self.0.lock().map_err(|_| "reset token lock poisoned")? = Some((token.clone(), Instant::now()));
+        Ok(token)
+    }
+
+    // Check and spend the token; it is single-use either way
+    pub fn redeem(&self, token: &str) -> Result<(), String> {
+        let issued = self.0.lock().map_err(|_| "reset token lock poisoned")?.take();
+        match issued {
+            Some((expected, at)) if expected == token && at.elapsed() <= TOKEN_TTL => Ok(()),
+            Some((expected, _)) if expected == token => Err("reset token expired; request a new one".to_string()),
+            _ => Err("invalid reset token; request a new one".to_string()),
+        }
+    }
+}
+
+// Drop every table, including ones added by later migrations, then recreate
+// the schema so the app is usable right away
+pub fn wipe(conn: &mut Connection) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let tables: Vec<String> = {
+        let mut stmt = tx
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| row.get(0)).map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<_>>().map_err(|e| e.to_string())?
+    };
+    for table in tables {
+        tx.execute_batch(&format!("DROP TABLE \"{}\"", table.replace('"', "\"\"")))
+            .map_err(|e| e.to_string())?;
+    }
+    db::migrate(&tx)?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{entries, settings};
+
+    #[test]
+    fn wipe_leaves_an_empty_usable_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        entries::insert_entry(&conn, "Acme", "", 100, Some(200)).unwrap();
+        settings::set(&conn, "week_start", &"sunday").unwrap();
+
+        wipe(&mut conn).unwrap();
+        assert!(entries::list_range(&conn, 0, i64::MAX).unwrap().is_empty());
+        assert_eq!(settings::get::<String>(&conn, "week_start").unwrap(), None);
+        entries::insert_entry(&conn, "Acme", "", 100, Some(200)).unwrap();
+    }
+
+    #[test]
+    fn reset_tokens_are_single_use() {
+        let tokens = ResetToken::default();
+        assert!(tokens.redeem("").is_err());
+        let token = tokens.issue().unwrap();
+        assert!(tokens.redeem("wrong").is_err());
+        // A wrong guess spends the token too
+        assert!(tokens.redeem(&token).is_err());
+        let token = tokens.issue().unwrap();
+        assert!(tokens.redeem(&token).is_ok());
+        assert!(tokens.redeem(&token).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tauri::{async_runtime, AppHandle, Manager};
+
+use crate::db;
+use crate::timer::{self, Timer, TimerStatus};
+use crate::tray;
+
+const MIN_INTERVAL_MS: u64 = 1000;
+
+// Custom tray titles shown in turn instead of the elapsed/project title. Each
+// `set` bumps the generation, which stops the previous rotation task.
+#[derive(Default)]
+pub struct TrayRotation {
+    items: Mutex<Vec<String>>,
+    generation: AtomicU64,
+}
+
+impl TrayRotation {
+    fn is_active(&self) -> bool {
+        self.items.lock().map(|items| !items.is_empty()).unwrap_or(false)
+    }
+}
+
+// One running, unpaused timer dominates: its elapsed time is the most useful title
+fn single_active(status: &TimerStatus) -> bool {
+    status.running_count == 1 && !status.paused
+}
+
+// Whether the rotation, not the timer status, currently owns the tray title
+pub fn owns_title(app: &AppHandle, status: &TimerStatus) -> bool {
+    app.try_state::<TrayRotation>()
+        .is_some_and(|rotation| rotation.is_active() && !single_active(status))
+}
+
+fn current_status(app: &AppHandle) -> Option<TimerStatus> {
+    let timers = app.state::<Timer>();
+    let timers = timers.lock().ok()?;
+    Some(timer::status(&timers, None, db::now_ts()))
+}
+
+// Rotate through `items` every `interval_ms` (at least a second). An empty
+// list clears the rotation and restores the standard title.
+pub fn set(app: &AppHandle, items: Vec<String>, interval_ms: u64) -> Result<(), String> {
+    let items: Vec<String> = items
+        .into_iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect();
+    let rotation = app.state::<TrayRotation>();
+    *rotation.items.lock().map_err(|_| "tray rotation lock poisoned")? = items.clone();
+    let generation = rotation.generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+    if items.is_empty() {
+        return match current_status(app) {
+            Some(status) => tray::show_status(app, &status),
+            None => tray::set_title(app, "", ""),
+        };
+    }
+
+    let interval = Duration::from_millis(interval_ms.max(MIN_INTERVAL_MS));
+    let app = app.clone();
+    async_runtime::spawn(async move {
+        for item in items.iter().cycle() {
+            if app.state::<TrayRotation>().generation.load(Ordering::SeqCst) != generation {
+                break;
+            }
+            // The timer ticker keeps the title while one timer dominates
+            if !current_status(&app).is_some_and(|status| single_active(&status)) {
+                let _ = tray::set_text(&app, item);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+    Ok(())
+}
+
+pub fn clear(app: &AppHandle) -> Result<(), String> {
+    set(app, Vec::new(), 0)
+}
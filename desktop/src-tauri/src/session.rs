@@ -0,0 +1,356 @@
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db::{self, Database};
+use crate::events::{self, EntryChange};
+use crate::recovery::TickWriter;
+use crate::settings;
+use crate::timer::{self, Timer, TimerId, TimerState};
+use crate::tray;
+
+pub const PAUSE_ON_LOCK_SETTING: &str = "pause_on_lock";
+
+pub const SESSION_LOCKED_EVENT: &str = "session-locked";
+pub const SESSION_UNLOCKED_EVENT: &str = "session-unlocked";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionLocked {
+    pub locked_ts: i64,
+    pub paused: Vec<TimerId>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionUnlocked {
+    pub locked_ts: i64,
+    pub unlocked_ts: i64,
+    pub resumed: Vec<TimerId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LockedSpan {
+    pub start_ts: i64,
+    pub end_ts: i64,
+}
+
+// When the screen was locked and which timers that paused, so unlocking
+// resumes only those and not timers the user had paused themselves.
+// `None` while unlocked.
+#[derive(Default)]
+pub struct LockState(Mutex<Option<SessionLocked>>);
+
+pub fn pause_on_lock(conn: &Connection) -> Result<bool, String> {
+    Ok(settings::get(conn, PAUSE_ON_LOCK_SETTING)?.unwrap_or(false))
+}
+
+pub fn set_pause_on_lock(conn: &Connection, enabled: bool) -> Result<(), String> {
+    settings::set(conn, PAUSE_ON_LOCK_SETTING, &enabled)
+}
+
+// Pause every active timer; returns the ones that were paused
+pub fn pause_active(conn: &Connection, timers: &mut TimerState, now: i64) -> Result<Vec<TimerId>, String> {
+    let active: Vec<TimerId> = timers
+        .iter()
+        .filter(|(_, timer)| !timer.paused)
+        .map(|(id, _)| id)
+        .collect();
+    for id in &active {
+        timer::pause(conn, timers, *id, now)?;
+    }
+    Ok(active)
+}
+
+// Resume those of `ids` that still exist and are still paused
+pub fn resume_paused(
+    conn: &Connection,
+    timers: &mut TimerState,
+    ids: &[TimerId],
+    now: i64,
+) -> Result<Vec<TimerId>, String> {
+    let paused: Vec<TimerId> = ids
+        .iter()
+        .copied()
+        .filter(|id| timers.get(*id).is_some_and(|timer| timer.paused))
+        .collect();
+    for id in &paused {
+        timer::resume(conn, timers, *id, now)?;
+    }
+    Ok(paused)
+}
+
+pub fn record_locked_span(conn: &Connection, start_ts: i64, end_ts: i64) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO locked_spans (start_ts, end_ts) VALUES (?1, ?2)",
+        params![start_ts, end_ts],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Locked spans overlapping [from_ts, to_ts), oldest first
+pub fn locked_spans(conn: &Connection, from_ts: i64, to_ts: i64) -> Result<Vec<LockedSpan>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT start_ts, end_ts FROM locked_spans
+             WHERE start_ts < ?2 AND end_ts > ?1 ORDER BY start_ts",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_ts, to_ts], |row| {
+            Ok(LockedSpan {
+                start_ts: row.get(0)?,
+                end_ts: row.get(1)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn on_lock(app: &AppHandle) {
+    let lock_state = app.state::<LockState>();
+    let Ok(mut locked) = lock_state.0.lock() else {
+        return;
+    };
+    if locked.is_some() {
+        return;
+    }
+    let now = db::now_ts();
+    let timer_state = app.state::<Timer>();
+    let database = app.state::<Database>();
+    let result = timer_state.lock().and_then(|mut timers| {
+        let conn = database.lock()?;
+        if !pause_on_lock(&conn)? {
+            return Ok(None);
+        }
+        let closed = timers.open_entries();
+        let paused = pause_active(&conn, &mut timers, now)?;
+        Ok(Some((paused, closed, timer::status(&timers, None, now))))
+    });
+    let paused = match result {
+        Ok(Some((paused, closed, status))) => {
+            if !paused.is_empty() {
+                app.state::<TickWriter>().persist_now(now);
+                events::emit_entries_changed(app, EntryChange::Update, closed);
+                if let Err(e) = tray::show_status(app, &status) {
+                    log::warn!("failed to update tray title: {}", e);
+                }
+            }
+            paused
+        }
+        Ok(None) => return,
+        // A locked database keeps the timers running but still records the lock
+        Err(e) => {
+            log::warn!("failed to pause timers on screen lock: {}", e);
+            Vec::new()
+        }
+    };
+    let event = SessionLocked { locked_ts: now, paused };
+    let _ = app.emit(SESSION_LOCKED_EVENT, &event);
+    *locked = Some(event);
+}
+
+fn on_unlock(app: &AppHandle) {
+    let Some(locked) = app.state::<LockState>().0.lock().ok().and_then(|mut locked| locked.take()) else {
+        return;
+    };
+    let now = db::now_ts();
+    let timer_state = app.state::<Timer>();
+    let database = app.state::<Database>();
+    let result = timer_state.lock().and_then(|mut timers| {
+        let conn = database.lock()?;
+        record_locked_span(&conn, locked.locked_ts, now)?;
+        let resumed = resume_paused(&conn, &mut timers, &locked.paused, now)?;
+        let opened = resumed
+            .iter()
+            .filter_map(|id| timers.get(*id).and_then(|timer| timer.entry_id))
+            .collect();
+        Ok((resumed, opened, timer::status(&timers, None, now)))
+    });
+    let resumed = match result {
+        Ok((resumed, opened, status)) => {
+            if !resumed.is_empty() {
+                events::emit_entries_changed(app, EntryChange::Insert, opened);
+                if let Err(e) = tray::show_status(app, &status) {
+                    log::warn!("failed to update tray title: {}", e);
+                }
+            }
+            resumed
+        }
+        Err(e) => {
+            log::warn!("failed to resume timers on screen unlock: {}", e);
+            Vec::new()
+        }
+    };
+    let _ = app.emit(
+        SESSION_UNLOCKED_EVENT,
+        SessionUnlocked {
+            locked_ts: locked.locked_ts,
+            unlocked_ts: now,
+            resumed,
+        },
+    );
+}
+
+// Follow screen lock and unlock. This is separate from sleep: the machine
+// keeps running while locked, so the clock-jump handling never sees it.
+pub fn listen(app: &AppHandle) {
+    if let Err(e) = platform::listen(app) {
+        log::warn!("failed to listen for screen lock: {}", e);
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::c_void;
+
+    use core_foundation::base::TCFType;
+    use core_foundation::string::{CFString, CFStringRef};
+    use core_foundation_sys::dictionary::CFDictionaryRef;
+    use core_foundation_sys::notification_center::{
+        CFNotificationCenterAddObserver, CFNotificationCenterGetDistributedCenter, CFNotificationCenterRef,
+        CFNotificationSuspensionBehaviorDeliverImmediately,
+    };
+    use tauri::AppHandle;
+
+    const LOCKED: &str = "com.apple.screenIsLocked";
+    const UNLOCKED: &str = "com.apple.screenIsUnlocked";
+
+    extern "C" fn on_notification(
+        _center: CFNotificationCenterRef,
+        observer: *mut c_void,
+        name: CFStringRef,
+        _object: *const c_void,
+        _info: CFDictionaryRef,
+    ) {
+        if observer.is_null() || name.is_null() {
+            return;
+        }
+        // The observer is the handle leaked in `listen`, alive for the whole process
+        let app = unsafe { &*(observer as *const AppHandle) };
+        let name = unsafe { CFString::wrap_under_get_rule(name) }.to_string();
+        match name.as_str() {
+            LOCKED => super::on_lock(app),
+            UNLOCKED => super::on_unlock(app),
+            _ => {}
+        }
+    }
+
+    // Registered from setup, so callbacks arrive on the main run loop
+    pub fn listen(app: &AppHandle) -> Result<(), String> {
+        let observer = Box::into_raw(Box::new(app.clone())) as *const c_void;
+        for name in [LOCKED, UNLOCKED] {
+            let name = CFString::new(name);
+            unsafe {
+                CFNotificationCenterAddObserver(
+                    CFNotificationCenterGetDistributedCenter(),
+                    observer,
+                    on_notification,
+                    name.as_concrete_TypeRef(),
+                    std::ptr::null(),
+                    CFNotificationSuspensionBehaviorDeliverImmediately,
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use tauri::{AppHandle, Manager};
+    use windows_sys::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+    use windows_sys::Win32::System::RemoteDesktop::{WTSRegisterSessionNotification, NOTIFY_FOR_THIS_SESSION};
+    use windows_sys::Win32::UI::Shell::{DefSubclassProc, SetWindowSubclass};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{WM_WTSSESSION_CHANGE, WTS_SESSION_LOCK, WTS_SESSION_UNLOCK};
+
+    const SUBCLASS_ID: usize = 1;
+
+    unsafe extern "system" fn subclass_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+        _id: usize,
+        data: usize,
+    ) -> LRESULT {
+        if msg == WM_WTSSESSION_CHANGE {
+            // `data` is the handle leaked in `listen`, alive for the whole process
+            let app = &*(data as *const AppHandle);
+            match wparam as u32 {
+                WTS_SESSION_LOCK => super::on_lock(app),
+                WTS_SESSION_UNLOCK => super::on_unlock(app),
+                _ => {}
+            }
+        }
+        DefSubclassProc(hwnd, msg, wparam, lparam)
+    }
+
+    // Session notifications go to a window, so hook the main window's
+    // message loop; hidden windows still receive them
+    pub fn listen(app: &AppHandle) -> Result<(), String> {
+        let window = app
+            .get_webview_window("main")
+            .ok_or_else(|| "main window not found".to_string())?;
+        let hwnd = window.hwnd().map_err(|e| e.to_string())?.0 as HWND;
+        let data = Box::into_raw(Box::new(app.clone())) as usize;
+        unsafe {
+            if SetWindowSubclass(hwnd, Some(subclass_proc), SUBCLASS_ID, data) == 0 {
+                return Err("failed to hook the main window".to_string());
+            }
+            if WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION) == 0 {
+                return Err("failed to register for session notifications".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use tauri::AppHandle;
+
+    pub fn listen(_app: &AppHandle) -> Result<(), String> {
+        log::info!("screen lock detection is not supported on this platform");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::entries;
+
+    #[test]
+    fn unlock_resumes_only_timers_paused_by_the_lock() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let mut timers = TimerState::default();
+        let a = timer::start(&conn, &mut timers, "Acme".to_string(), String::new(), None, 1_000).unwrap();
+        let b = timer::start(&conn, &mut timers, "Beta".to_string(), String::new(), None, 1_000).unwrap();
+        timer::pause(&conn, &mut timers, b, 1_100).unwrap();
+
+        assert_eq!(pause_active(&conn, &mut timers, 1_200).unwrap(), vec![a]);
+        assert_eq!(resume_paused(&conn, &mut timers, &[a], 1_500).unwrap(), vec![a]);
+        assert!(!timers.get(a).unwrap().paused);
+        assert!(timers.get(b).unwrap().paused);
+        // The locked minutes are not tracked
+        assert_eq!(timers.get(a).unwrap().elapsed(1_600), 300);
+        let acme: Vec<_> = entries::list_overlapping(&conn, 0, 2_000, 1_600)
+            .unwrap()
+            .into_iter()
+            .filter(|entry| entry.project == "Acme")
+            .map(|entry| (entry.start_ts, entry.end_ts))
+            .collect();
+        assert_eq!(acme, vec![(1_000, Some(1_200)), (1_500, None)]);
+
+        record_locked_span(&conn, 1_200, 1_500).unwrap();
+        assert_eq!(
+            locked_spans(&conn, 1_400, 2_000).unwrap(),
+            vec![LockedSpan { start_ts: 1_200, end_ts: 1_500 }]
+        );
+        assert!(locked_spans(&conn, 1_500, 2_000).unwrap().is_empty());
+    }
+}
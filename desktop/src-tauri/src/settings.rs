@@ -0,0 +1,158 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+#[cfg(desktop)]
+use crate::{autostart, chime, dbwatch, focus, menubar, quit, updater};
+use crate::{
+    billing, budget, categorize, gaps, i18n, logging, notify, progress, recovery, session, shortcuts, stats,
+    timefmt, timer, tray, webhook, widget,
+};
+
+// Key/value preferences stored as JSON in the `settings` table
+
+// JSON type a preference is stored as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Bool,
+    // A non-negative integer
+    Count,
+    Text,
+    Object,
+}
+
+impl Kind {
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            Self::Bool => value.is_boolean(),
+            Self::Count => value.is_u64(),
+            Self::Text => value.is_string(),
+            Self::Object => value.is_object(),
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Bool => "true or false",
+            Self::Count => "a whole number",
+            Self::Text => "a string",
+            Self::Object => "an object",
+        }
+    }
+}
+
+// The user-facing preferences: all that settings import and profiles may
+// write. Every other key is app state (widget placement, crash recovery,
+// archives, recent projects, the webhook secret, daily target progress…).
+pub fn preference_kind(key: &str) -> Option<Kind> {
+    let kind = match key {
+        categorize::SAMPLING_SETTING
+        | gaps::BLOCK_OUTSIDE_HOURS_SETTING
+        | logging::FILE_LOGGING_SETTING
+        | session::PAUSE_ON_LOCK_SETTING
+        | widget::NONACTIVATING_SETTING => Kind::Bool,
+        #[cfg(desktop)]
+        autostart::ENABLED_SETTING
+        | chime::HOURLY_CHIME_SETTING
+        | dbwatch::WATCH_SETTING
+        | focus::DND_SETTING
+        | menubar::MENUBAR_ONLY_SETTING
+        | quit::CLOSE_TO_TRAY_SETTING
+        | quit::CONFIRM_QUIT_SETTING
+        | updater::CHECK_ON_LAUNCH_SETTING => Kind::Bool,
+        billing::ROUNDING_SETTING
+        | progress::DAILY_TARGET_SETTING
+        | recovery::TICK_PERSIST_SETTING
+        | timer::MAX_ENTRY_SETTING
+        | timer::MIN_ENTRY_SETTING
+        | widget::AUTOHIDE_SETTING => Kind::Count,
+        #[cfg(desktop)]
+        autostart::MINUTES_SETTING => Kind::Count,
+        i18n::LOCALE_SETTING
+        | logging::LOG_LEVEL_SETTING
+        | stats::WEEK_START_SETTING
+        | timefmt::TIMEZONE_SETTING
+        | timer::DURATION_FORMAT_SETTING
+        | timer::NOTE_TEMPLATE_SETTING
+        | tray::CLICK_ACTION_SETTING
+        | tray::DOUBLE_CLICK_ACTION_SETTING
+        | tray::EMOJI_SETTING
+        | tray::IDLE_MODE_SETTING
+        | webhook::URL_SETTING => Kind::Text,
+        billing::RATES_SETTING
+        | budget::BUDGETS_SETTING
+        | gaps::WORKING_HOURS_SETTING
+        | notify::QUIET_HOURS_SETTING
+        | shortcuts::MENU_SHORTCUTS_SETTING
+        | widget::SIZE_SETTING => Kind::Object,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+// Ok if `key` is a preference and `value` has its type
+pub fn check_preference(key: &str, value: &Value) -> Result<(), String> {
+    let kind = preference_kind(key).ok_or_else(|| format!("'{}' is not a preference", key))?;
+    if !kind.matches(value) {
+        return Err(format!("setting '{}' must be {}", key, kind.describe()));
+    }
+    Ok(())
+}
+
+pub fn get<T: DeserializeOwned>(conn: &Connection, key: &str) -> Result<Option<T>, String> {
+    let raw: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match raw {
+        Some(raw) => serde_json::from_str(&raw)
+            .map(Some)
+            .map_err(|e| format!("invalid value for setting '{}': {}", key, e)),
+        None => Ok(None),
+    }
+}
+
+pub fn set<T: Serialize>(conn: &Connection, key: &str, value: &T) -> Result<(), String> {
+    let raw = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, raw],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn remove(conn: &Connection, key: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM settings WHERE key = ?1", params![key])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn only_preferences_of_the_right_type_pass() {
+        assert!(check_preference(billing::ROUNDING_SETTING, &json!(15)).is_ok());
+        assert!(check_preference(billing::ROUNDING_SETTING, &json!(-15)).is_err());
+        assert!(check_preference(i18n::LOCALE_SETTING, &json!(true)).is_err());
+        assert!(check_preference(widget::SIZE_SETTING, &json!({"width": 320, "height": 440})).is_ok());
+        for state in [
+            recovery::LAST_TICK_SETTING,
+            crate::archive::ARCHIVES_SETTING,
+            timer::RECENT_PROJECTS_SETTING,
+            webhook::SECRET_SETTING,
+            widget::VISIBLE_SETTING,
+        ] {
+            assert!(check_preference(state, &json!(true)).is_err(), "{}", state);
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use rusqlite::Connection;
+
+use crate::settings;
+
+pub const MENU_SHORTCUTS_SETTING: &str = "menu_shortcuts";
+
+// Remappable menu items: (menu id, default accelerator)
+pub const MENU_ACTIONS: &[(&str, &str)] = &[
+    ("settings", "Cmd+,"),
+    ("new_entry", "Cmd+N"),
+    ("toggle_timer", "Cmd+T"),
+];
+
+// Accelerators owned by the predefined (native) menu items, which can't be remapped
+const RESERVED: &[(&str, &str)] = &[
+    ("hide", "Cmd+H"),
+    ("hide_others", "Cmd+Alt+H"),
+    ("quit", "Cmd+Q"),
+    ("close_window", "Cmd+W"),
+    ("undo", "Cmd+Z"),
+    ("redo", "Cmd+Shift+Z"),
+    ("cut", "Cmd+X"),
+    ("copy", "Cmd+C"),
+    ("paste", "Cmd+V"),
+    ("select_all", "Cmd+A"),
+    ("fullscreen", "Ctrl+Cmd+F"),
+    ("minimize", "Cmd+M"),
+];
+
+const NAMED_KEYS: &[&str] = &[
+    "Space", "Tab", "Enter", "Escape", "Backspace", "Delete", "Up", "Down", "Left", "Right",
+    "Home", "End", "PageUp", "PageDown",
+];
+
+const PUNCTUATION: &str = ",./;'[]\\-=`";
+
+fn canonical_modifier(name: &str) -> Option<&'static str> {
+    match name.to_ascii_lowercase().as_str() {
+        "cmd" | "command" | "super" | "meta" => Some("Cmd"),
+        "ctrl" | "control" => Some("Ctrl"),
+        // Resolved per platform so it compares equal to what it actually binds
+        "cmdorctrl" | "commandorcontrol" if cfg!(target_os = "macos") => Some("Cmd"),
+        "cmdorctrl" | "commandorcontrol" => Some("Ctrl"),
+        "alt" | "option" => Some("Alt"),
+        "shift" => Some("Shift"),
+        _ => None,
+    }
+}
+
+fn canonical_key(name: &str) -> Option<String> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_alphanumeric() {
+            return Some(c.to_ascii_uppercase().to_string());
+        }
+        if PUNCTUATION.contains(c) {
+            return Some(c.to_string());
+        }
+    }
+    let lower = name.to_ascii_lowercase();
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+        if (1..=24).contains(&n) {
+            return Some(format!("F{}", n));
+        }
+    }
+    let lower = match lower.as_str() {
+        "return" => "enter",
+        "esc" => "escape",
+        "arrowup" => "up",
+        "arrowdown" => "down",
+        "arrowleft" => "left",
+        "arrowright" => "right",
+        other => other,
+    };
+    NAMED_KEYS
+        .iter()
+        .find(|key| key.to_ascii_lowercase() == lower)
+        .map(|key| key.to_string())
+}
+
+// A parsed accelerator: modifiers in a fixed order, then the key. Displays
+// in canonical form, so equivalent spellings compare and print equal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Accelerator {
+    pub modifiers: Vec<&'static str>,
+    pub key: String,
+}
+
+impl fmt::Display for Accelerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for modifier in &self.modifiers {
+            write!(f, "{}+", modifier)?;
+        }
+        f.write_str(&self.key)
+    }
+}
+
+// Parse an accelerator such as `CmdOrCtrl+Shift+T`; every feature that takes
+// accelerators goes through here
+pub fn parse_accelerator(accelerator: &str) -> Result<Accelerator, String> {
+    let invalid = |reason: &str| format!("invalid accelerator '{}': {}", accelerator, reason);
+    let parts: Vec<&str> = accelerator.split('+').map(str::trim).collect();
+    // A trailing `+` means the key itself is plus, which menus can't bind reliably
+    if parts.iter().any(|part| part.is_empty()) {
+        return Err(invalid("empty component"));
+    }
+    let (key, modifiers) = parts.split_last().ok_or_else(|| invalid("missing key"))?;
+
+    let mut canonical = Vec::new();
+    for modifier in modifiers {
+        let modifier = canonical_modifier(modifier)
+            .ok_or_else(|| invalid(&format!("unknown modifier '{}'", modifier)))?;
+        if canonical.contains(&modifier) {
+            return Err(invalid(&format!("duplicate modifier '{}'", modifier)));
+        }
+        canonical.push(modifier);
+    }
+    canonical.sort_by_key(|m| ["Ctrl", "Alt", "Shift", "Cmd"].iter().position(|o| o == m));
+
+    let key = canonical_key(key).ok_or_else(|| invalid(&format!("unknown key '{}'", key)))?;
+    // Bare function keys are fine; anything else would swallow normal typing
+    let function_key =
+        key.len() > 1 && key.starts_with('F') && key[1..].bytes().all(|b| b.is_ascii_digit());
+    if canonical.is_empty() && !function_key {
+        return Err(invalid("needs at least one modifier"));
+    }
+    Ok(Accelerator {
+        modifiers: canonical,
+        key,
+    })
+}
+
+fn default_accelerator(action: &str) -> Option<&'static str> {
+    MENU_ACTIONS
+        .iter()
+        .find(|(id, _)| *id == action)
+        .map(|(_, accelerator)| *accelerator)
+}
+
+// User overrides keyed by menu id
+fn overrides(conn: &Connection) -> Result<BTreeMap<String, String>, String> {
+    Ok(settings::get(conn, MENU_SHORTCUTS_SETTING)?.unwrap_or_default())
+}
+
+pub fn defaults() -> BTreeMap<String, String> {
+    MENU_ACTIONS
+        .iter()
+        .map(|(action, accelerator)| (action.to_string(), accelerator.to_string()))
+        .collect()
+}
+
+// Current accelerator of every remappable menu item, defaults filled in
+pub fn menu_shortcuts(conn: &Connection) -> Result<BTreeMap<String, String>, String> {
+    let mut shortcuts = defaults();
+    shortcuts.extend(overrides(conn)?);
+    Ok(shortcuts)
+}
+
+// An accelerator currently in use and the feature that owns it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub feature: &'static str,
+    pub name: String,
+    pub accelerator: String,
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} ({})", self.feature, self.name, self.accelerator)
+    }
+}
+
+// Every bound accelerator: the native menu items, then the remappable ones
+pub fn registry(conn: &Connection) -> Result<Vec<Binding>, String> {
+    let reserved = RESERVED.iter().map(|(name, accelerator)| Binding {
+        feature: "system menu item",
+        name: name.to_string(),
+        accelerator: accelerator.to_string(),
+    });
+    let menu = menu_shortcuts(conn)?.into_iter().map(|(name, accelerator)| Binding {
+        feature: "menu item",
+        name,
+        accelerator,
+    });
+    Ok(reserved.chain(menu).collect())
+}
+
+// Parse `accelerator` and make sure no binding other than `owner`'s uses it
+pub fn validate(conn: &Connection, accelerator: &str, owner: Option<&str>) -> Result<Accelerator, String> {
+    let parsed = parse_accelerator(accelerator)?;
+    let conflict = registry(conn)?.into_iter().find(|binding| {
+        owner != Some(binding.name.as_str())
+            && parse_accelerator(&binding.accelerator).is_ok_and(|existing| existing == parsed)
+    });
+    match conflict {
+        Some(binding) => Err(format!("accelerator '{}' is already bound to {}", accelerator, binding)),
+        None => Ok(parsed),
+    }
+}
+
+// Validate and persist a new accelerator for a menu item. Rejects accelerators
+// already bound elsewhere, naming the conflicting binding.
+pub fn set_menu_shortcut(
+    conn: &Connection,
+    action: &str,
+    accelerator: &str,
+) -> Result<String, String> {
+    if default_accelerator(action).is_none() {
+        let known: Vec<&str> = MENU_ACTIONS.iter().map(|(id, _)| *id).collect();
+        return Err(format!(
+            "unknown menu action '{}', expected one of {}",
+            action,
+            known.join(", ")
+        ));
+    }
+    let canonical = validate(conn, accelerator, Some(action))?.to_string();
+    let mut overrides = overrides(conn)?;
+    overrides.insert(action.to_string(), canonical.clone());
+    settings::set(conn, MENU_SHORTCUTS_SETTING, &overrides)?;
+    Ok(canonical)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn parse_normalizes_spelling_and_order() {
+        assert_eq!(parse_accelerator("shift+command+t").unwrap().to_string(), "Shift+Cmd+T");
+        assert_eq!(parse_accelerator("Option + Return").unwrap().to_string(), "Alt+Enter");
+        assert_eq!(parse_accelerator("F5").unwrap().to_string(), "F5");
+        assert!(parse_accelerator("T").is_err());
+        assert!(parse_accelerator("Cmd+Hyper+T").is_err());
+        assert!(parse_accelerator("Cmd+Cmd+T").is_err());
+        assert!(parse_accelerator("Cmd+").is_err());
+    }
+
+    #[test]
+    fn set_rejects_conflicts_by_name() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+
+        let err = set_menu_shortcut(&conn, "toggle_timer", "Command+N").unwrap_err();
+        assert!(err.contains("new_entry (Cmd+N)"), "{}", err);
+        let err = set_menu_shortcut(&conn, "toggle_timer", "Cmd+Q").unwrap_err();
+        assert!(err.contains("system menu item quit"), "{}", err);
+        assert!(validate(&conn, "Cmd+N", None).is_err());
+        assert!(validate(&conn, "Cmd+N", Some("new_entry")).is_ok());
+
+        assert_eq!(set_menu_shortcut(&conn, "toggle_timer", "Cmd+Shift+T").unwrap(), "Shift+Cmd+T");
+        // Rebinding an action to its own accelerator is not a conflict
+        set_menu_shortcut(&conn, "toggle_timer", "Cmd+Shift+T").unwrap();
+        let bindings = menu_shortcuts(&conn).unwrap();
+        assert_eq!(bindings["toggle_timer"], "Shift+Cmd+T");
+        assert_eq!(bindings["settings"], "Cmd+,");
+    }
+}
@@ -0,0 +1,618 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, NaiveDate, TimeZone, Timelike, Weekday};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::archive;
+use crate::entries::{self, Entry};
+use crate::progress;
+use crate::recurrence::parse_weekday;
+use crate::settings;
+
+pub const WEEK_START_SETTING: &str = "week_start";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StatsBucket {
+    pub key: String,
+    pub seconds: i64,
+    pub entries: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Stats {
+    pub total_seconds: i64,
+    pub buckets: Vec<StatsBucket>,
+    // Set when the range reaches into archived data, which is not counted
+    pub archived_before: Option<i64>,
+}
+
+// Configured first day of the week, Monday unless the user changed it
+pub fn week_start(conn: &Connection) -> Result<Weekday, String> {
+    match settings::get::<String>(conn, WEEK_START_SETTING)? {
+        Some(day) => parse_weekday(&day),
+        None => Ok(Weekday::Mon),
+    }
+}
+
+pub fn set_week_start(conn: &Connection, day: &str) -> Result<Weekday, String> {
+    let weekday = parse_weekday(day)?;
+    settings::set(conn, WEEK_START_SETTING, &weekday.to_string())?;
+    Ok(weekday)
+}
+
+// First day of the locale week containing `date`. With a Monday start this is
+// the same boundary as the ISO week; with any other start day the buckets are
+// locale weeks and are keyed by their first date rather than an ISO week number.
+pub fn start_of_week(date: NaiveDate, week_start: Weekday) -> NaiveDate {
+    let offset = (7 + date.weekday().num_days_from_monday() - week_start.num_days_from_monday()) % 7;
+    date - Duration::days(offset as i64)
+}
+
+// Aggregate tracked time for entries starting in [from_ts, to_ts), grouped by
+// local `day`, `week` (keyed by the week's first date) or `project`. Entries
+// count toward the bucket containing their start; a running entry counts up to
+// `now`.
+pub fn get_stats<Tz: TimeZone>(
+    conn: &Connection,
+    tz: &Tz,
+    from_ts: i64,
+    to_ts: i64,
+    group_by: &str,
+    now: i64,
+) -> Result<Stats, String> {
+    let first_day = week_start(conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT project, start_ts, COALESCE(end_ts, ?3) FROM entries
+             WHERE start_ts >= ?1 AND start_ts < ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_ts, to_ts, now], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut buckets: BTreeMap<String, StatsBucket> = BTreeMap::new();
+    let mut total_seconds = 0;
+    for row in rows {
+        let (project, start_ts, end_ts) = row.map_err(|e| e.to_string())?;
+        let seconds = (end_ts - start_ts).max(0);
+        let local_day = || {
+            tz.timestamp_opt(start_ts, 0)
+                .single()
+                .map(|dt| dt.date_naive())
+                .ok_or_else(|| format!("invalid timestamp {}", start_ts))
+        };
+        let key = match group_by {
+            "day" => local_day()?.format("%Y-%m-%d").to_string(),
+            "week" => start_of_week(local_day()?, first_day)
+                .format("%Y-%m-%d")
+                .to_string(),
+            "project" => project,
+            other => return Err(format!("unknown group_by '{}'", other)),
+        };
+        let bucket = buckets.entry(key.clone()).or_insert(StatsBucket {
+            key,
+            seconds: 0,
+            entries: 0,
+        });
+        bucket.seconds += seconds;
+        bucket.entries += 1;
+        total_seconds += seconds;
+    }
+
+    Ok(Stats {
+        total_seconds,
+        buckets: buckets.into_values().collect(),
+        archived_before: archive::archived_before(conn)?.filter(|before| from_ts < *before),
+    })
+}
+
+// Longest range `activity_heatmap` fills in, about ten years
+const MAX_HEATMAP_DAYS: i64 = 3660;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DayActivity {
+    pub date: String,
+    pub seconds: i64,
+    pub entries: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ActivityHeatmap {
+    // Every local day in the range, including ones without entries
+    pub days: Vec<DayActivity>,
+    pub current_streak: u32,
+}
+
+fn local_date<Tz: TimeZone>(tz: &Tz, ts: i64) -> Result<NaiveDate, String> {
+    tz.timestamp_opt(ts, 0)
+        .single()
+        .map(|dt| dt.date_naive())
+        .ok_or_else(|| format!("invalid timestamp {}", ts))
+}
+
+// Tracked time and entry counts per local day in [from_ts, to_ts), bucketed
+// by start like `get_stats`, plus the current tracking streak
+pub fn activity_heatmap<Tz: TimeZone>(
+    conn: &Connection,
+    tz: &Tz,
+    from_ts: i64,
+    to_ts: i64,
+    now: i64,
+) -> Result<ActivityHeatmap, String> {
+    if to_ts <= from_ts {
+        return Err("the range end must be after its start".to_string());
+    }
+    let first = local_date(tz, from_ts)?;
+    let last = local_date(tz, to_ts - 1)?;
+    if (last - first).num_days() >= MAX_HEATMAP_DAYS {
+        return Err(format!("the range can span at most {} days", MAX_HEATMAP_DAYS));
+    }
+
+    let mut days: BTreeMap<NaiveDate, DayActivity> = first
+        .iter_days()
+        .take_while(|date| *date <= last)
+        .map(|date| {
+            let day = DayActivity {
+                date: date.format("%Y-%m-%d").to_string(),
+                seconds: 0,
+                entries: 0,
+            };
+            (date, day)
+        })
+        .collect();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT start_ts, COALESCE(end_ts, ?3) FROM entries
+             WHERE start_ts >= ?1 AND start_ts < ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_ts, to_ts, now], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        let (start_ts, end_ts) = row.map_err(|e| e.to_string())?;
+        if let Some(day) = days.get_mut(&local_date(tz, start_ts)?) {
+            day.seconds += (end_ts - start_ts).max(0);
+            day.entries += 1;
+        }
+    }
+
+    Ok(ActivityHeatmap {
+        days: days.into_values().collect(),
+        current_streak: current_streak(conn, tz, now)?,
+    })
+}
+
+// One entry's portion of a day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TimelineItem {
+    pub entry: Entry,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    // The entry started the day before or runs into the next one
+    pub continues_before: bool,
+    pub continues_after: bool,
+    pub running: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DayTimeline {
+    pub date: String,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub items: Vec<TimelineItem>,
+    pub total_seconds: i64,
+}
+
+// Entries of the local day containing `date_ts`, clipped to its bounds, so an
+// entry crossing midnight shows up in both days with its part of each. A
+// running entry is drawn up to `now`.
+pub fn day_timeline(conn: &Connection, tz: &chrono_tz::Tz, date_ts: i64, now: i64) -> Result<DayTimeline, String> {
+    let (date, day_start, day_end) = progress::day_bounds(tz, date_ts);
+    let items: Vec<TimelineItem> = entries::list_overlapping(conn, day_start, day_end, now)?
+        .into_iter()
+        .map(|entry| {
+            let end_ts = entry.end_ts.unwrap_or(now);
+            TimelineItem {
+                start_ts: entry.start_ts.max(day_start),
+                end_ts: end_ts.min(day_end),
+                continues_before: entry.start_ts < day_start,
+                continues_after: end_ts > day_end,
+                running: entry.end_ts.is_none(),
+                entry,
+            }
+        })
+        .collect();
+    Ok(DayTimeline {
+        date: date.format("%Y-%m-%d").to_string(),
+        start_ts: day_start,
+        end_ts: day_end,
+        total_seconds: items.iter().map(|item| (item.end_ts - item.start_ts).max(0)).sum(),
+        items,
+    })
+}
+
+// Tracked seconds per local hour of day over [from_ts, to_ts). Entries are
+// clipped to the range and split at every hour boundary they cross; running
+// ones count up to `now`.
+pub fn hourly_distribution<Tz: TimeZone>(
+    conn: &Connection,
+    tz: &Tz,
+    from_ts: i64,
+    to_ts: i64,
+    now: i64,
+) -> Result<[u64; 24], String> {
+    if from_ts >= to_ts {
+        return Err(format!("empty time range {}..{}", from_ts, to_ts));
+    }
+    let mut hours = [0u64; 24];
+    for entry in entries::list_overlapping(conn, from_ts, to_ts, now)? {
+        let end_ts = entry.end_ts.unwrap_or(now).min(to_ts);
+        let mut cursor = entry.start_ts.max(from_ts);
+        while cursor < end_ts {
+            let local = tz
+                .timestamp_opt(cursor, 0)
+                .single()
+                .ok_or_else(|| format!("invalid timestamp {}", cursor))?;
+            let next_hour = cursor + 3600 - (local.minute() * 60 + local.second()) as i64;
+            let until = next_hour.min(end_ts);
+            hours[local.hour() as usize] += (until - cursor) as u64;
+            cursor = until;
+        }
+    }
+    Ok(hours)
+}
+
+// Seconds tracked since local midnight, counting running entries up to `now`
+pub fn today_seconds<Tz: TimeZone>(conn: &Connection, tz: &Tz, now: i64) -> Result<i64, String> {
+    let midnight = local_date(tz, now)?
+        .and_hms_opt(0, 0, 0)
+        .and_then(|dt| tz.from_local_datetime(&dt).earliest())
+        .map(|dt| dt.timestamp())
+        .ok_or("local midnight does not exist")?;
+    Ok(get_stats(conn, tz, midnight, now + 1, "project", now)?.total_seconds)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectComparison {
+    pub project: String,
+    pub a_seconds: i64,
+    pub b_seconds: i64,
+    pub delta_seconds: i64,
+    // Change from b to a in percent; None when b has no time to compare with
+    pub percent_change: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RangeComparison {
+    pub projects: Vec<ProjectComparison>,
+    pub a_total_seconds: i64,
+    pub b_total_seconds: i64,
+    pub delta_seconds: i64,
+    pub percent_change: Option<f64>,
+}
+
+fn percent_change(a: i64, b: i64) -> Option<f64> {
+    (b != 0).then(|| (a - b) as f64 / b as f64 * 100.0)
+}
+
+// Per-project totals of range a against range b, e.g. this week against last
+// week. Projects tracked in only one range show zero on the other side.
+pub fn compare_ranges<Tz: TimeZone>(
+    conn: &Connection,
+    tz: &Tz,
+    a: (i64, i64),
+    b: (i64, i64),
+    now: i64,
+) -> Result<RangeComparison, String> {
+    for (from_ts, to_ts) in [a, b] {
+        if from_ts >= to_ts {
+            return Err(format!("empty time range {}..{}", from_ts, to_ts));
+        }
+    }
+    let a = get_stats(conn, tz, a.0, a.1, "project", now)?;
+    let b = get_stats(conn, tz, b.0, b.1, "project", now)?;
+    let mut totals: BTreeMap<String, (i64, i64)> = BTreeMap::new();
+    for bucket in a.buckets {
+        totals.entry(bucket.key).or_default().0 = bucket.seconds;
+    }
+    for bucket in b.buckets {
+        totals.entry(bucket.key).or_default().1 = bucket.seconds;
+    }
+    Ok(RangeComparison {
+        projects: totals
+            .into_iter()
+            .map(|(project, (a_seconds, b_seconds))| ProjectComparison {
+                project,
+                a_seconds,
+                b_seconds,
+                delta_seconds: a_seconds - b_seconds,
+                percent_change: percent_change(a_seconds, b_seconds),
+            })
+            .collect(),
+        a_total_seconds: a.total_seconds,
+        b_total_seconds: b.total_seconds,
+        delta_seconds: a.total_seconds - b.total_seconds,
+        percent_change: percent_change(a.total_seconds, b.total_seconds),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionInfo {
+    pub project: String,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    // Tracked time; gaps merged over are not counted
+    pub seconds: i64,
+    pub entry_ids: Vec<i64>,
+}
+
+// The longest session among entries starting in [from_ts, to_ts), running
+// ones counting up to `now`. Every entry is its own session, so a pause (which
+// ends an entry) breaks it, unless `merge_gaps_under` is given: then entries
+// of the same project less than that many seconds apart, with no other
+// project's entry between them, form one session.
+pub fn longest_session(
+    conn: &Connection,
+    from_ts: i64,
+    to_ts: i64,
+    merge_gaps_under: Option<u64>,
+    now: i64,
+) -> Result<Option<SessionInfo>, String> {
+    let mut longest: Option<SessionInfo> = None;
+    let mut current: Option<SessionInfo> = None;
+    for entry in entries::list_range(conn, from_ts, to_ts)? {
+        let end_ts = entry.end_ts.unwrap_or(now).max(entry.start_ts);
+        match current.as_mut() {
+            Some(run)
+                if run.project == entry.project
+                    && merge_gaps_under.is_some_and(|under| entry.start_ts - run.end_ts < under as i64) =>
+            {
+                run.seconds += end_ts - entry.start_ts.max(run.end_ts).min(end_ts);
+                run.end_ts = run.end_ts.max(end_ts);
+                run.entry_ids.push(entry.id);
+            }
+            _ => {
+                if let Some(run) = current.take() {
+                    if longest.as_ref().map_or(true, |best| run.seconds > best.seconds) {
+                        longest = Some(run);
+                    }
+                }
+                current = Some(SessionInfo {
+                    project: entry.project,
+                    start_ts: entry.start_ts,
+                    end_ts,
+                    seconds: end_ts - entry.start_ts,
+                    entry_ids: vec![entry.id],
+                });
+            }
+        }
+    }
+    if let Some(run) = current {
+        if longest.as_ref().map_or(true, |best| run.seconds > best.seconds) {
+            longest = Some(run);
+        }
+    }
+    Ok(longest)
+}
+
+// Consecutive local days with at least one entry, ending today. A day without
+// entries yet doesn't break the streak until it is over.
+pub fn current_streak<Tz: TimeZone>(conn: &Connection, tz: &Tz, now: i64) -> Result<u32, String> {
+    let today = local_date(tz, now)?;
+    let mut stmt = conn
+        .prepare("SELECT start_ts FROM entries WHERE start_ts <= ?1 ORDER BY start_ts DESC")
+        .map_err(|e| e.to_string())?;
+    let mut rows = stmt.query(params![now]).map_err(|e| e.to_string())?;
+
+    let mut streak = 0;
+    let mut expected = today;
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        let date = local_date(tz, row.get(0).map_err(|e| e.to_string())?)?;
+        if date > expected {
+            continue;
+        }
+        if date == expected || (streak == 0 && Some(date) == today.pred_opt()) {
+            streak += 1;
+        } else {
+            break;
+        }
+        match date.pred_opt() {
+            Some(previous) => expected = previous,
+            None => break,
+        }
+    }
+    Ok(streak)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    use crate::{db, entries};
+
+    fn ts(y: i32, m: u32, d: u32, h: u32) -> i64 {
+        Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap().timestamp()
+    }
+
+    // 2025-01-05 is a Sunday and 2025-01-06 a Monday (start of ISO week 2)
+    #[test]
+    fn week_buckets_follow_configured_start_day() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let sat = ts(2025, 1, 4, 23);
+        let sun = ts(2025, 1, 5, 10);
+        let mon = ts(2025, 1, 6, 0);
+        for start in [sat, sun, mon] {
+            entries::insert_entry(&conn, "A", "", start, Some(start + 600)).unwrap();
+        }
+        let range = (ts(2025, 1, 1, 0), ts(2025, 1, 10, 0));
+        let keys = |conn: &Connection| {
+            get_stats(conn, &Utc, range.0, range.1, "week", 0)
+                .unwrap()
+                .buckets
+                .into_iter()
+                .map(|b| (b.key, b.entries))
+                .collect::<Vec<_>>()
+        };
+
+        // ISO/Monday weeks: Sat and Sun belong to the week of Dec 30, Mon starts a new one
+        assert_eq!(
+            keys(&conn),
+            vec![("2024-12-30".to_string(), 2), ("2025-01-06".to_string(), 1)]
+        );
+
+        // Sunday weeks: Sat closes the week of Dec 29, Sun and Mon share the week of Jan 5
+        set_week_start(&conn, "Sunday").unwrap();
+        assert_eq!(
+            keys(&conn),
+            vec![("2024-12-29".to_string(), 1), ("2025-01-05".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn start_of_week_is_identity_on_the_start_day() {
+        let sunday = NaiveDate::from_ymd_opt(2025, 1, 5).unwrap();
+        assert_eq!(start_of_week(sunday, Weekday::Sun), sunday);
+        assert_eq!(
+            start_of_week(sunday, Weekday::Mon),
+            NaiveDate::from_ymd_opt(2024, 12, 30).unwrap()
+        );
+    }
+
+    #[test]
+    fn timeline_clips_entries_crossing_midnight() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let tz: chrono_tz::Tz = "UTC".parse().unwrap();
+        let overnight = entries::insert_entry(&conn, "A", "", ts(2025, 1, 1, 22), Some(ts(2025, 1, 2, 2))).unwrap();
+        entries::insert_entry(&conn, "B", "", ts(2025, 1, 2, 9), None).unwrap();
+
+        let first = day_timeline(&conn, &tz, ts(2025, 1, 1, 12), ts(2025, 1, 2, 10)).unwrap();
+        assert_eq!(first.date, "2025-01-01");
+        assert_eq!(first.total_seconds, 2 * 3600);
+        assert!(first.items[0].continues_after);
+
+        let second = day_timeline(&conn, &tz, ts(2025, 1, 2, 12), ts(2025, 1, 2, 10)).unwrap();
+        let spans: Vec<(i64, i64, bool)> = second
+            .items
+            .iter()
+            .map(|item| (item.start_ts, item.end_ts, item.running))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (ts(2025, 1, 2, 0), ts(2025, 1, 2, 2), false),
+                (ts(2025, 1, 2, 9), ts(2025, 1, 2, 10), true),
+            ]
+        );
+        assert_eq!(second.items[0].entry.id, overnight.id);
+        assert!(second.items[0].continues_before);
+        assert_eq!(second.total_seconds, 3 * 3600);
+    }
+
+    #[test]
+    fn hourly_distribution_splits_entries_at_hour_boundaries() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        // 09:30 to 11:15, and 23:45 running past midnight until the range ends
+        entries::insert_entry(&conn, "A", "", ts(2025, 1, 2, 9) + 1_800, Some(ts(2025, 1, 2, 11) + 900)).unwrap();
+        entries::insert_entry(&conn, "A", "", ts(2025, 1, 2, 23) + 2_700, None).unwrap();
+
+        let hours = hourly_distribution(&conn, &Utc, ts(2025, 1, 2, 0), ts(2025, 1, 3, 0) + 600, ts(2025, 1, 3, 5)).unwrap();
+        assert_eq!((hours[9], hours[10], hours[11]), (1_800, 3_600, 900));
+        assert_eq!((hours[23], hours[0]), (900, 600));
+        assert_eq!(hours.iter().sum::<u64>(), 7_800);
+    }
+
+    #[test]
+    fn comparison_keeps_projects_missing_from_one_range() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        entries::insert_entry(&conn, "A", "", ts(2025, 1, 2, 9), Some(ts(2025, 1, 2, 12))).unwrap();
+        entries::insert_entry(&conn, "B", "", ts(2025, 1, 3, 9), Some(ts(2025, 1, 3, 10))).unwrap();
+        entries::insert_entry(&conn, "A", "", ts(2025, 1, 9, 9), Some(ts(2025, 1, 9, 11))).unwrap();
+        entries::insert_entry(&conn, "C", "", ts(2025, 1, 10, 9), Some(ts(2025, 1, 10, 10))).unwrap();
+
+        let this_week = (ts(2025, 1, 6, 0), ts(2025, 1, 13, 0));
+        let last_week = (ts(2024, 12, 30, 0), ts(2025, 1, 6, 0));
+        let comparison = compare_ranges(&conn, &Utc, this_week, last_week, ts(2025, 1, 13, 0)).unwrap();
+        let rows: Vec<(&str, i64, i64, Option<f64>)> = comparison
+            .projects
+            .iter()
+            .map(|p| (p.project.as_str(), p.a_seconds, p.b_seconds, p.percent_change.map(f64::round)))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("A", 7_200, 10_800, Some(-33.0)),
+                ("B", 0, 3_600, Some(-100.0)),
+                ("C", 3_600, 0, None),
+            ]
+        );
+        assert_eq!((comparison.a_total_seconds, comparison.b_total_seconds), (10_800, 14_400));
+        assert_eq!(comparison.percent_change.map(f64::round), Some(-25.0));
+    }
+
+    #[test]
+    fn longest_session_merges_short_pauses_only_when_asked() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let day = ts(2025, 1, 2, 0);
+        // A: 09:00-10:00, paused 5 minutes, 10:05-11:00; B 11:00-12:30
+        let a1 = entries::insert_entry(&conn, "A", "", day + 9 * 3600, Some(day + 10 * 3600)).unwrap();
+        let a2 = entries::insert_entry(&conn, "A", "", day + 10 * 3600 + 300, Some(day + 11 * 3600)).unwrap();
+        let b = entries::insert_entry(&conn, "B", "", day + 11 * 3600, Some(day + 12 * 3600 + 1_800)).unwrap();
+
+        let best = longest_session(&conn, day, day + 86_400, None, day + 86_400).unwrap().unwrap();
+        assert_eq!((best.project.as_str(), best.seconds, best.entry_ids), ("B", 5_400, vec![b.id]));
+
+        let best = longest_session(&conn, day, day + 86_400, Some(600), day + 86_400).unwrap().unwrap();
+        assert_eq!((best.project.as_str(), best.seconds), ("A", 6_900));
+        assert_eq!((best.start_ts, best.end_ts), (day + 9 * 3600, day + 11 * 3600));
+        assert_eq!(best.entry_ids, vec![a1.id, a2.id]);
+
+        assert_eq!(longest_session(&conn, day + 86_400, day + 2 * 86_400, None, day).unwrap(), None);
+    }
+
+    #[test]
+    fn heatmap_fills_empty_days_and_counts_the_streak() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        // Jan 2 and 3 tracked, Jan 4 empty, Jan 5 and 6 tracked
+        for (day, minutes) in [(2, 30), (3, 10), (3, 20), (5, 60), (6, 15)] {
+            let start = ts(2025, 1, day, 9);
+            entries::insert_entry(&conn, "A", "", start, Some(start + minutes * 60)).unwrap();
+        }
+
+        let heatmap = activity_heatmap(&conn, &Utc, ts(2025, 1, 1, 0), ts(2025, 1, 8, 0), ts(2025, 1, 7, 12)).unwrap();
+        let days: Vec<(&str, i64, i64)> = heatmap
+            .days
+            .iter()
+            .map(|d| (d.date.as_str(), d.seconds, d.entries))
+            .collect();
+        assert_eq!(
+            days,
+            vec![
+                ("2025-01-01", 0, 0),
+                ("2025-01-02", 1_800, 1),
+                ("2025-01-03", 1_800, 2),
+                ("2025-01-04", 0, 0),
+                ("2025-01-05", 3_600, 1),
+                ("2025-01-06", 900, 1),
+                ("2025-01-07", 0, 0),
+            ]
+        );
+        // Nothing tracked on Jan 7 yet, so the streak still counts Jan 5 and 6
+        assert_eq!(heatmap.current_streak, 2);
+        assert_eq!(current_streak(&conn, &Utc, ts(2025, 1, 8, 12)).unwrap(), 0);
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::{BTreeSet, HashMap};
+
+use rusqlite::{params, Connection};
+
+use crate::entries::{self, EntryFilter, EntrySort};
+
+// Entries carrying this tag count as billable
+pub const BILLABLE_TAG: &str = "billable";
+
+// Trimmed, lowercased and deduplicated; empty tags are dropped
+pub fn normalize(tags: &[String]) -> Vec<String> {
+    tags.iter()
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+pub fn entry_tags(conn: &Connection, entry_id: i64) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT tag FROM entry_tags WHERE entry_id = ?1 ORDER BY tag")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![entry_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())
+}
+
+// Tags of every entry starting in [from_ts, to_ts), sorted per entry
+pub fn tags_in_range(conn: &Connection, from_ts: i64, to_ts: i64) -> Result<HashMap<i64, Vec<String>>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.entry_id, t.tag FROM entry_tags t
+             JOIN entries e ON e.id = t.entry_id
+             WHERE e.start_ts >= ?1 AND e.start_ts < ?2
+             ORDER BY t.entry_id, t.tag",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_ts, to_ts], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+    for row in rows {
+        let (entry_id, tag) = row.map_err(|e| e.to_string())?;
+        tags.entry(entry_id).or_default().push(tag);
+    }
+    Ok(tags)
+}
+
+// Replace an entry's tags. Tags of deleted entries are left in place: entry
+// ids are never reused, and undoing the delete brings them back.
+pub fn set_entry_tags(conn: &mut Connection, entry_id: i64, tags: &[String]) -> Result<Vec<String>, String> {
+    let tags = normalize(tags);
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    entries::get_entry(&tx, entry_id)?;
+    tx.execute("DELETE FROM entry_tags WHERE entry_id = ?1", params![entry_id])
+        .map_err(|e| e.to_string())?;
+    add_tags(&tx, entry_id, &tags)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(tags)
+}
+
+// Add tags an entry doesn't have yet, keeping its others
+pub fn add_tags(conn: &Connection, entry_id: i64, tags: &[String]) -> Result<(), String> {
+    for tag in normalize(tags) {
+        conn.execute(
+            "INSERT OR IGNORE INTO entry_tags (entry_id, tag) VALUES (?1, ?2)",
+            params![entry_id, tag],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Add and remove tags on every entry starting in [from_ts, to_ts), optionally
+// for one project, in one transaction. Returns the previous tags of each entry
+// that changed, so the edit can be undone.
+pub fn tag_entries(
+    conn: &mut Connection,
+    from_ts: i64,
+    to_ts: i64,
+    project: Option<String>,
+    add: &[String],
+    remove: &[String],
+) -> Result<Vec<(i64, Vec<String>)>, String> {
+    if from_ts >= to_ts {
+        return Err(format!("empty time range {}..{}", from_ts, to_ts));
+    }
+    let (add, remove) = (normalize(add), normalize(remove));
+    if add.is_empty() && remove.is_empty() {
+        return Err("no tags to add or remove".to_string());
+    }
+    if let Some(tag) = add.iter().find(|tag| remove.contains(tag)) {
+        return Err(format!("tag '{}' is both added and removed", tag));
+    }
+    let filter = EntryFilter {
+        project,
+        from_ts: Some(from_ts),
+        to_ts: Some(to_ts),
+    };
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let matching = entries::list_entries(&tx, &filter, EntrySort::StartAsc, u32::MAX, 0)?.items;
+    let mut changed = Vec::new();
+    for entry in matching {
+        let before = entry_tags(&tx, entry.id)?;
+        let adds = add.iter().any(|tag| !before.contains(tag));
+        let removes = remove.iter().any(|tag| before.contains(tag));
+        if !adds && !removes {
+            continue;
+        }
+        add_tags(&tx, entry.id, &add)?;
+        for tag in &remove {
+            tx.execute(
+                "DELETE FROM entry_tags WHERE entry_id = ?1 AND tag = ?2",
+                params![entry.id, tag],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        changed.push((entry.id, before));
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(changed)
+}
+
+// Put back exactly these tags, as recorded by `tag_entries`
+pub fn restore_tags(conn: &Connection, entry_id: i64, tags: &[String]) -> Result<(), String> {
+    remove_tags(conn, entry_id)?;
+    add_tags(conn, entry_id, tags)
+}
+
+// Drop an entry's tags along with an entry that is deleted for good
+pub fn remove_tags(conn: &Connection, entry_id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM entry_tags WHERE entry_id = ?1", params![entry_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn tag_entries_only_touches_matching_entries_that_change() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let tagged = entries::insert_entry(&conn, "Acme", "", 100, Some(200)).unwrap();
+        let plain = entries::insert_entry(&conn, "Acme", "", 300, Some(400)).unwrap();
+        let other = entries::insert_entry(&conn, "Other", "", 300, Some(400)).unwrap();
+        let later = entries::insert_entry(&conn, "Acme", "", 5_000, Some(5_100)).unwrap();
+        add_tags(&conn, tagged.id, &["billable".to_string(), "draft".to_string()]).unwrap();
+
+        let billable = vec!["Billable".to_string()];
+        let changed = tag_entries(&mut conn, 0, 1_000, Some("Acme".to_string()), &billable, &[]).unwrap();
+        assert_eq!(changed, vec![(plain.id, vec![])]);
+        assert_eq!(entry_tags(&conn, plain.id).unwrap(), vec!["billable"]);
+        assert!(entry_tags(&conn, other.id).unwrap().is_empty());
+        assert!(entry_tags(&conn, later.id).unwrap().is_empty());
+
+        let changed = tag_entries(&mut conn, 0, 1_000, None, &[], &["draft".to_string()]).unwrap();
+        assert_eq!(changed, vec![(tagged.id, vec!["billable".to_string(), "draft".to_string()])]);
+        restore_tags(&conn, tagged.id, &changed[0].1).unwrap();
+        assert_eq!(entry_tags(&conn, tagged.id).unwrap(), vec!["billable", "draft"]);
+
+        assert!(tag_entries(&mut conn, 0, 1_000, None, &billable, &billable).is_err());
+    }
+}
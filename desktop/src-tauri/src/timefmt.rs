@@ -0,0 +1,83 @@
+use chrono::{DateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use rusqlite::Connection;
+
+use crate::settings;
+
+pub const TIMEZONE_SETTING: &str = "timezone";
+
+// Entries are stored as UTC epoch seconds; this module is the single place
+// that turns them into wall-clock time for reports and exports.
+
+// IANA zone of the operating system, UTC if it can't be determined
+pub fn system_zone() -> Tz {
+    iana_time_zone::get_timezone()
+        .ok()
+        .and_then(|name| name.parse().ok())
+        .unwrap_or(Tz::UTC)
+}
+
+pub fn parse_zone(name: &str) -> Result<Tz, String> {
+    name.trim()
+        .parse()
+        .map_err(|_| format!("unknown timezone '{}'", name))
+}
+
+// The user's override if set, otherwise the system zone
+pub fn configured_zone(conn: &Connection) -> Result<Tz, String> {
+    match settings::get::<String>(conn, TIMEZONE_SETTING)? {
+        Some(name) => parse_zone(&name),
+        None => Ok(system_zone()),
+    }
+}
+
+// `None` clears the override so the system zone is followed again
+pub fn set_timezone(conn: &Connection, name: Option<&str>) -> Result<(), String> {
+    match name {
+        Some(name) => {
+            let zone = parse_zone(name)?;
+            settings::set(conn, TIMEZONE_SETTING, &zone.name())
+        }
+        None => settings::remove(conn, TIMEZONE_SETTING),
+    }
+}
+
+pub fn to_local(ts: i64, tz: &Tz) -> DateTime<Tz> {
+    Utc.timestamp_opt(ts, 0)
+        .single()
+        .unwrap_or_default()
+        .with_timezone(tz)
+}
+
+// ISO 8601 local time with its UTC offset, unambiguous across DST changes
+pub fn format_rfc3339(ts: i64, tz: &Tz) -> String {
+    to_local(ts, tz).to_rfc3339()
+}
+
+pub fn format_local(ts: i64, tz: &Tz, fmt: &str) -> String {
+    to_local(ts, tz).format(fmt).to_string()
+}
+
+// iCalendar UTC form, e.g. 20250330T013000Z
+pub fn format_ics_utc(ts: i64) -> String {
+    to_local(ts, &Tz::UTC).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Europe/Berlin springs forward on 2025-03-30 at 02:00 local (01:00 UTC)
+    #[test]
+    fn spring_forward_keeps_real_duration() {
+        let tz: Tz = "Europe/Berlin".parse().unwrap();
+        let start = Utc.with_ymd_and_hms(2025, 3, 30, 0, 30, 0).unwrap().timestamp();
+        let end = Utc.with_ymd_and_hms(2025, 3, 30, 1, 30, 0).unwrap().timestamp();
+
+        assert_eq!(format_rfc3339(start, &tz), "2025-03-30T01:30:00+01:00");
+        assert_eq!(format_rfc3339(end, &tz), "2025-03-30T03:30:00+02:00");
+        // The wall clock advanced two hours but only one hour elapsed
+        assert_eq!(end - start, 3600);
+        assert_eq!(format_ics_utc(start), "20250330T003000Z");
+    }
+}
@@ -0,0 +1,767 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use chrono::{DateTime, TimeZone};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::billing;
+use crate::entries::{self, Entry};
+use crate::settings;
+
+pub const MIN_ENTRY_SETTING: &str = "min_entry_seconds";
+pub const DEFAULT_MIN_ENTRY_SECONDS: u32 = 5;
+pub const NOTE_TEMPLATE_SETTING: &str = "note_template";
+// Projects in the order timers were last started on them, newest first
+pub const RECENT_PROJECTS_SETTING: &str = "recent_projects";
+const MAX_RECENT_PROJECTS: usize = 20;
+// Timers running longer than this are stopped by the tick loop; unset by default
+pub const MAX_ENTRY_SETTING: &str = "max_entry_seconds";
+// How the tray and widget show elapsed time: "clock" or "decimal" hours
+pub const DURATION_FORMAT_SETTING: &str = "duration_format";
+
+pub type TimerId = u64;
+
+// One running timer. While running, the current segment is stored as an open
+// entry (end_ts NULL) so a crash never loses its start time. Pausing closes the
+// segment; resuming opens a new one under the same project.
+#[derive(Debug, Clone)]
+pub struct RunningTimer {
+    pub project: String,
+    pub note: String,
+    pub entry_id: Option<i64>,
+    pub segment_start: i64,
+    pub accumulated: i64,
+    pub paused: bool,
+    pub target_minutes: Option<u32>,
+    // Whether the preset's target was already announced for this timer
+    pub target_reached: bool,
+}
+
+impl RunningTimer {
+    pub fn elapsed(&self, now: i64) -> i64 {
+        if self.paused {
+            self.accumulated
+        } else {
+            self.accumulated + (now - self.segment_start).max(0)
+        }
+    }
+}
+
+// All running timers. Ids only ever increase, so the primary timer (the one
+// single-timer callers act on) is simply the longest-running one.
+#[derive(Debug, Default)]
+pub struct TimerState {
+    running: HashMap<TimerId, RunningTimer>,
+    next_id: TimerId,
+}
+
+impl TimerState {
+    pub fn is_empty(&self) -> bool {
+        self.running.is_empty()
+    }
+
+    pub fn primary(&self) -> Option<TimerId> {
+        self.running.keys().min().copied()
+    }
+
+    pub fn get(&self, id: TimerId) -> Option<&RunningTimer> {
+        self.running.get(&id)
+    }
+
+    // Running timers in start order
+    pub fn iter(&self) -> impl Iterator<Item = (TimerId, &RunningTimer)> {
+        let mut ids: Vec<TimerId> = self.running.keys().copied().collect();
+        ids.sort_unstable();
+        ids.into_iter().map(|id| (id, &self.running[&id]))
+    }
+
+    // Whether any timer is running and not paused
+    pub fn any_active(&self) -> bool {
+        self.running.values().any(|timer| !timer.paused)
+    }
+
+    // Open entries of all running timers
+    pub fn open_entries(&self) -> Vec<i64> {
+        self.running.values().filter_map(|timer| timer.entry_id).collect()
+    }
+
+    // The given timer, or the primary one when no id is passed
+    pub fn resolve(&self, id: Option<TimerId>) -> Result<TimerId, String> {
+        match id {
+            Some(id) if self.running.contains_key(&id) => Ok(id),
+            Some(id) => Err(format!("no timer with id {} is running", id)),
+            None => self.primary().ok_or_else(|| "no timer is running".to_string()),
+        }
+    }
+
+    // Forget every timer without touching the database, e.g. after a reset.
+    // Ids keep counting up so stale ones held by the UI never match.
+    pub fn clear(&mut self) {
+        self.running.clear();
+    }
+
+    // Follow a project rename in the running timers
+    pub fn rename_project(&mut self, old: &str, new: &str) {
+        for timer in self.running.values_mut().filter(|timer| timer.project == old) {
+            timer.project = new.to_string();
+        }
+    }
+
+    // Projects of timers that have just run past their preset's target.
+    // Each timer is reported once.
+    pub fn reached_targets(&mut self, now: i64) -> Vec<String> {
+        let mut reached = Vec::new();
+        for timer in self.running.values_mut().filter(|timer| !timer.target_reached) {
+            if let Some(minutes) = timer.target_minutes {
+                if timer.elapsed(now) >= minutes as i64 * 60 {
+                    timer.target_reached = true;
+                    reached.push(timer.project.clone());
+                }
+            }
+        }
+        reached
+    }
+
+    fn get_mut(&mut self, id: TimerId) -> Result<&mut RunningTimer, String> {
+        self.running
+            .get_mut(&id)
+            .ok_or_else(|| format!("no timer with id {} is running", id))
+    }
+}
+
+#[derive(Default)]
+pub struct Timer {
+    state: Mutex<TimerState>,
+    last_discarded: AtomicBool,
+}
+
+impl Timer {
+    pub fn lock(&self) -> Result<MutexGuard<'_, TimerState>, String> {
+        self.state.lock().map_err(|_| "timer lock poisoned".to_string())
+    }
+
+    // Whether the most recent stop threw away a too-short entry
+    pub fn last_discarded(&self) -> bool {
+        self.last_discarded.load(Ordering::Relaxed)
+    }
+
+    pub fn set_last_discarded(&self, discarded: bool) {
+        self.last_discarded.store(discarded, Ordering::Relaxed);
+    }
+}
+
+// Status of one timer (the primary one unless asked otherwise), plus a
+// summary of all running timers for the tray
+#[derive(Debug, Clone, Serialize)]
+pub struct TimerStatus {
+    pub id: Option<TimerId>,
+    pub running: bool,
+    pub paused: bool,
+    pub project: Option<String>,
+    pub note: Option<String>,
+    pub elapsed_seconds: i64,
+    pub target_minutes: Option<u32>,
+    pub last_entry_discarded: bool,
+    pub running_count: usize,
+    pub total_elapsed_seconds: i64,
+}
+
+pub fn status(timers: &TimerState, id: Option<TimerId>, now: i64) -> TimerStatus {
+    let id = id.or_else(|| timers.primary());
+    let timer = id.and_then(|id| timers.get(id));
+    TimerStatus {
+        id: timer.and(id),
+        running: timer.is_some(),
+        paused: timer.is_some_and(|timer| timer.paused),
+        project: timer.map(|timer| timer.project.clone()),
+        note: timer.map(|timer| timer.note.clone()),
+        elapsed_seconds: timer.map_or(0, |timer| timer.elapsed(now)),
+        target_minutes: timer.and_then(|timer| timer.target_minutes),
+        last_entry_discarded: false,
+        running_count: timers.running.len(),
+        total_elapsed_seconds: timers.running.values().map(|timer| timer.elapsed(now)).sum(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunningTimerInfo {
+    pub id: TimerId,
+    pub primary: bool,
+    pub project: String,
+    pub note: String,
+    pub paused: bool,
+    pub elapsed_seconds: i64,
+    pub target_minutes: Option<u32>,
+}
+
+pub fn list_running(timers: &TimerState, now: i64) -> Vec<RunningTimerInfo> {
+    let primary = timers.primary();
+    timers
+        .iter()
+        .map(|(id, timer)| RunningTimerInfo {
+            id,
+            primary: Some(id) == primary,
+            project: timer.project.clone(),
+            note: timer.note.clone(),
+            paused: timer.paused,
+            elapsed_seconds: timer.elapsed(now),
+            target_minutes: timer.target_minutes,
+        })
+        .collect()
+}
+
+fn close_entry(conn: &Connection, id: i64, now: i64) -> Result<Entry, String> {
+    conn.execute(
+        "UPDATE entries SET end_ts = ?1 WHERE id = ?2",
+        params![now, id],
+    )
+    .map_err(|e| e.to_string())?;
+    entries::get_entry(conn, id)
+}
+
+pub fn start(
+    conn: &Connection,
+    timers: &mut TimerState,
+    project: String,
+    note: String,
+    target_minutes: Option<u32>,
+    now: i64,
+) -> Result<TimerId, String> {
+    let entry = entries::insert_entry(conn, &project, &note, now, None)?;
+    record_recent(conn, &project)?;
+    timers.next_id += 1;
+    let id = timers.next_id;
+    timers.running.insert(
+        id,
+        RunningTimer {
+            project,
+            note,
+            entry_id: Some(entry.id),
+            segment_start: now,
+            accumulated: 0,
+            paused: false,
+            target_minutes,
+            target_reached: false,
+        },
+    );
+    Ok(id)
+}
+
+// Move `project` to the front of the recent list. Only starting a timer
+// counts; editing or importing entries doesn't reorder it.
+fn record_recent(conn: &Connection, project: &str) -> Result<(), String> {
+    let mut recent: Vec<String> = settings::get(conn, RECENT_PROJECTS_SETTING)?.unwrap_or_default();
+    recent.retain(|name| name != project);
+    recent.insert(0, project.to_string());
+    recent.truncate(MAX_RECENT_PROJECTS);
+    settings::set(conn, RECENT_PROJECTS_SETTING, &recent)
+}
+
+// Follow a project rename (or merge) in the recent list
+pub fn rename_recent(conn: &Connection, old: &str, new: &str) -> Result<(), String> {
+    let Some(recent) = settings::get::<Vec<String>>(conn, RECENT_PROJECTS_SETTING)? else {
+        return Ok(());
+    };
+    let mut renamed: Vec<String> = Vec::with_capacity(recent.len());
+    for name in recent {
+        let name = if name == old { new.to_string() } else { name };
+        if !renamed.contains(&name) {
+            renamed.push(name);
+        }
+    }
+    settings::set(conn, RECENT_PROJECTS_SETTING, &renamed)
+}
+
+// Up to `limit` projects, most recently started first. Before any timer has
+// been started the entries' creation order stands in.
+pub fn recent_projects(conn: &Connection, limit: usize) -> Result<Vec<String>, String> {
+    let mut recent: Vec<String> = settings::get(conn, RECENT_PROJECTS_SETTING)?.unwrap_or_default();
+    if recent.is_empty() {
+        let mut stmt = conn
+            .prepare(
+                "SELECT project FROM entries WHERE project != ''
+                 GROUP BY project ORDER BY MAX(id) DESC LIMIT ?1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![MAX_RECENT_PROJECTS as i64], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        recent = rows
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| e.to_string())?;
+    }
+    recent.truncate(limit);
+    Ok(recent)
+}
+
+// Take over an open entry left behind by a previous session as a running timer
+pub fn adopt(timers: &mut TimerState, entry: &Entry) -> TimerId {
+    timers.next_id += 1;
+    let id = timers.next_id;
+    timers.running.insert(
+        id,
+        RunningTimer {
+            project: entry.project.clone(),
+            note: entry.note.clone(),
+            entry_id: Some(entry.id),
+            segment_start: entry.start_ts,
+            accumulated: 0,
+            paused: false,
+            target_minutes: None,
+            target_reached: false,
+        },
+    );
+    id
+}
+
+// Move running segments (and their open entries) by `seconds` so elapsed
+// time is unaffected by a wall-clock jump. Returns the shifted entry ids.
+pub fn shift_running(conn: &Connection, timers: &mut TimerState, seconds: i64) -> Result<Vec<i64>, String> {
+    let mut shifted = Vec::new();
+    // Only a clock set back is undone; a forward jump may be real time spent
+    // asleep with the timer running
+    if seconds >= 0 {
+        return Ok(shifted);
+    }
+    for timer in timers.running.values_mut().filter(|timer| !timer.paused) {
+        timer.segment_start += seconds;
+        if let Some(entry_id) = timer.entry_id {
+            conn.execute(
+                "UPDATE entries SET start_ts = start_ts + ?1 WHERE id = ?2",
+                params![seconds, entry_id],
+            )
+            .map_err(|e| e.to_string())?;
+            shifted.push(entry_id);
+        }
+    }
+    Ok(shifted)
+}
+
+pub fn pause(conn: &Connection, timers: &mut TimerState, id: TimerId, now: i64) -> Result<(), String> {
+    let timer = timers.get_mut(id)?;
+    if timer.paused {
+        return Ok(());
+    }
+    if let Some(entry_id) = timer.entry_id.take() {
+        close_entry(conn, entry_id, now)?;
+    }
+    timer.accumulated = timer.elapsed(now);
+    timer.paused = true;
+    Ok(())
+}
+
+pub fn resume(conn: &Connection, timers: &mut TimerState, id: TimerId, now: i64) -> Result<(), String> {
+    let timer = timers.get_mut(id)?;
+    if !timer.paused {
+        return Ok(());
+    }
+    let entry = entries::insert_entry(conn, &timer.project, &timer.note, now, None)?;
+    timer.entry_id = Some(entry.id);
+    timer.segment_start = now;
+    timer.paused = false;
+    Ok(())
+}
+
+// Template used to pre-fill the note of a timer started without one. Empty
+// (the default) leaves such notes empty.
+pub fn note_template(conn: &Connection) -> Result<String, String> {
+    Ok(settings::get(conn, NOTE_TEMPLATE_SETTING)?.unwrap_or_default())
+}
+
+// Fill `{date}`, `{time}`, `{project}` and `{weekday}` from the local start time.
+// Unknown placeholders are left as written.
+pub fn render_note<Tz: TimeZone>(template: &str, project: &str, start: &DateTime<Tz>) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    template
+        .replace("{date}", &start.format("%Y-%m-%d").to_string())
+        .replace("{time}", &start.format("%H:%M").to_string())
+        .replace("{weekday}", &start.format("%A").to_string())
+        .replace("{project}", project)
+}
+
+// Entries shorter than this are discarded on stop, e.g. after a double toggle
+pub fn min_entry_seconds(conn: &Connection) -> Result<u32, String> {
+    Ok(settings::get(conn, MIN_ENTRY_SETTING)?.unwrap_or(DEFAULT_MIN_ENTRY_SECONDS))
+}
+
+pub fn max_entry_seconds(conn: &Connection) -> Result<Option<u64>, String> {
+    settings::get(conn, MAX_ENTRY_SETTING)
+}
+
+// `None` removes the cap
+pub fn set_max_entry_seconds(conn: &Connection, seconds: Option<u64>) -> Result<(), String> {
+    match seconds {
+        Some(0) => Err("the maximum entry duration must be positive".to_string()),
+        Some(seconds) => settings::set(conn, MAX_ENTRY_SETTING, &seconds),
+        None => settings::remove(conn, MAX_ENTRY_SETTING),
+    }
+}
+
+// A timer stopped by the tick loop, for running past the maximum entry
+// duration or the end of the working hours
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Capped {
+    pub timer_id: TimerId,
+    pub project: String,
+    // Where the open entry started, i.e. when the user meant to begin
+    pub start_ts: i64,
+    pub entry: Entry,
+}
+
+// Stop every unpaused timer whose elapsed time exceeds `max_seconds`, ending
+// its entry at the moment it reached the cap rather than at `now`
+pub fn cap_overlong(
+    conn: &Connection,
+    timers: &mut TimerState,
+    max_seconds: u64,
+    now: i64,
+) -> Result<Vec<Capped>, String> {
+    let max_seconds = max_seconds as i64;
+    let over: Vec<TimerId> = timers
+        .running
+        .iter()
+        .filter(|(_, timer)| !timer.paused && timer.elapsed(now) > max_seconds)
+        .map(|(id, _)| *id)
+        .collect();
+    let mut capped = Vec::new();
+    for id in over {
+        let timer = timers.running.remove(&id).expect("collected above");
+        let Some(entry_id) = timer.entry_id else {
+            continue;
+        };
+        let end_ts = timer.segment_start + (max_seconds - timer.accumulated).max(0);
+        capped.push(Capped {
+            timer_id: id,
+            project: timer.project,
+            start_ts: timer.segment_start,
+            entry: close_entry(conn, entry_id, end_ts)?,
+        });
+    }
+    Ok(capped)
+}
+
+// Stop every unpaused timer, ending its entry at `end_ts`. Timers whose
+// segment began after `end_ts` keep what they tracked and end at `now`.
+pub fn stop_active_at(
+    conn: &Connection,
+    timers: &mut TimerState,
+    end_ts: i64,
+    now: i64,
+) -> Result<Vec<Capped>, String> {
+    let active: Vec<TimerId> = timers
+        .running
+        .iter()
+        .filter(|(_, timer)| !timer.paused)
+        .map(|(id, _)| *id)
+        .collect();
+    let mut stopped = Vec::new();
+    for id in active {
+        let timer = timers.running.remove(&id).expect("collected above");
+        let Some(entry_id) = timer.entry_id else {
+            continue;
+        };
+        let end_ts = if end_ts > timer.segment_start { end_ts } else { now };
+        stopped.push(Capped {
+            timer_id: id,
+            project: timer.project,
+            start_ts: timer.segment_start,
+            entry: close_entry(conn, entry_id, end_ts)?,
+        });
+    }
+    Ok(stopped)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopOutcome {
+    Saved(Entry),
+    // The open segment was shorter than the minimum and was deleted
+    Discarded,
+    // The timer was paused, so its segments were already closed
+    Paused,
+}
+
+// Stop a timer and close its open segment, unless that segment lasted less
+// than `min_seconds`, in which case it is removed instead of saved
+pub fn stop(
+    conn: &Connection,
+    timers: &mut TimerState,
+    id: TimerId,
+    min_seconds: u32,
+    now: i64,
+) -> Result<StopOutcome, String> {
+    let timer = timers
+        .running
+        .remove(&id)
+        .ok_or_else(|| format!("no timer with id {} is running", id))?;
+    let Some(entry_id) = timer.entry_id else {
+        return Ok(StopOutcome::Paused);
+    };
+    if now - timer.segment_start < min_seconds as i64 {
+        conn.execute("DELETE FROM entries WHERE id = ?1", params![entry_id])
+            .map_err(|e| e.to_string())?;
+        return Ok(StopOutcome::Discarded);
+    }
+    close_entry(conn, entry_id, now).map(StopOutcome::Saved)
+}
+
+// Close a timer's current entry and open one for `new_project` at the same
+// instant. Runs in one transaction so the boundary is neither lost nor
+// double-counted; a paused timer resumes under the new project.
+pub fn switch_project(
+    conn: &mut Connection,
+    timers: &mut TimerState,
+    id: TimerId,
+    new_project: String,
+    now: i64,
+) -> Result<(), String> {
+    let timer = timers.get_mut(id)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    if let Some(entry_id) = timer.entry_id {
+        close_entry(&tx, entry_id, now)?;
+    }
+    let entry = entries::insert_entry(&tx, &new_project, "", now, None)?;
+    record_recent(&tx, &new_project)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    *timer = RunningTimer {
+        project: new_project,
+        note: String::new(),
+        entry_id: Some(entry.id),
+        segment_start: now,
+        accumulated: 0,
+        paused: false,
+        target_minutes: None,
+        target_reached: false,
+    };
+    Ok(())
+}
+
+// Format seconds as HH:MM:SS for the tray title
+pub fn format_elapsed(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds % 3600) / 60,
+        seconds % 60
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DurationFormat {
+    #[default]
+    Clock,
+    Decimal,
+}
+
+impl DurationFormat {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format {
+            "clock" => Ok(Self::Clock),
+            "decimal" => Ok(Self::Decimal),
+            other => Err(format!("unknown duration format '{}', expected clock or decimal", other)),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Clock => "clock",
+            Self::Decimal => "decimal",
+        }
+    }
+}
+
+// Elapsed time as displayed: rounded up like billing, then as HH:MM:SS or
+// decimal hours such as "1.25"
+pub fn format_display(seconds: i64, rounding_minutes: u32, format: DurationFormat) -> String {
+    let seconds = billing::round_up(seconds.max(0), rounding_minutes);
+    match format {
+        DurationFormat::Clock => format_elapsed(seconds),
+        DurationFormat::Decimal => format!("{:.2}", seconds as f64 / 3600.0),
+    }
+}
+
+// Rounding and format for displayed elapsed times, kept in memory so the
+// tray can render every tick without touching the database
+#[derive(Debug, Default)]
+pub struct DurationDisplay(Mutex<(u32, DurationFormat)>);
+
+impl DurationDisplay {
+    // Falls back to the defaults while the database is locked
+    pub fn reload(&self, conn: &Connection) {
+        let rounding = billing::rounding_minutes(conn).unwrap_or(0);
+        let format = settings::get::<String>(conn, DURATION_FORMAT_SETTING)
+            .ok()
+            .flatten()
+            .and_then(|format| DurationFormat::parse(&format).ok())
+            .unwrap_or_default();
+        if let Ok(mut display) = self.0.lock() {
+            *display = (rounding, format);
+        }
+    }
+
+    pub fn set_rounding(&self, minutes: u32) {
+        if let Ok(mut display) = self.0.lock() {
+            display.0 = minutes;
+        }
+    }
+
+    pub fn set_format(&self, format: DurationFormat) {
+        if let Ok(mut display) = self.0.lock() {
+            display.1 = format;
+        }
+    }
+
+    pub fn format(&self, seconds: i64) -> String {
+        let (rounding, format) = self.0.lock().map(|display| *display).unwrap_or_default();
+        format_display(seconds, rounding, format)
+    }
+}
+
+pub fn set_duration_format(conn: &Connection, format: DurationFormat) -> Result<(), String> {
+    settings::set(conn, DURATION_FORMAT_SETTING, &format.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn display_rounds_up_before_formatting() {
+        assert_eq!(format_display(3570, 0, DurationFormat::Clock), "00:59:30");
+        assert_eq!(format_display(3570, 15, DurationFormat::Clock), "01:00:00");
+        assert_eq!(format_display(4500, 0, DurationFormat::Decimal), "1.25");
+        assert_eq!(format_display(3570, 6, DurationFormat::Decimal), "1.00");
+        assert!(DurationFormat::parse("hours").is_err());
+    }
+
+    #[test]
+    fn render_note_fills_placeholders() {
+        let tz: chrono_tz::Tz = "Europe/Berlin".parse().unwrap();
+        let start = crate::timefmt::to_local(1743294600, &tz);
+        let template = "Daily standup — {date} {time} ({weekday}, {project}) {other}";
+        assert_eq!(
+            render_note(template, "Acme", &start),
+            "Daily standup — 2025-03-30 01:30 (Sunday, Acme) {other}"
+        );
+    }
+
+    #[test]
+    fn stop_discards_entries_below_minimum() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let mut timers = TimerState::default();
+
+        let id = start(&conn, &mut timers, "A".to_string(), String::new(), None, 100).unwrap();
+        assert_eq!(stop(&conn, &mut timers, id, 5, 104).unwrap(), StopOutcome::Discarded);
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+
+        let id = start(&conn, &mut timers, "A".to_string(), String::new(), None, 200).unwrap();
+        match stop(&conn, &mut timers, id, 5, 205).unwrap() {
+            StopOutcome::Saved(entry) => assert_eq!(entry.end_ts, Some(205)),
+            other => panic!("expected a saved entry, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn concurrent_timers_keep_the_oldest_as_primary() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let mut timers = TimerState::default();
+
+        let a = start(&conn, &mut timers, "A".to_string(), String::new(), None, 100).unwrap();
+        let b = start(&conn, &mut timers, "B".to_string(), String::new(), None, 160).unwrap();
+        assert_ne!(a, b);
+        pause(&conn, &mut timers, b, 220).unwrap();
+
+        let summary = status(&timers, None, 400);
+        assert_eq!(summary.id, Some(a));
+        assert_eq!(summary.project.as_deref(), Some("A"));
+        assert_eq!((summary.running_count, summary.total_elapsed_seconds), (2, 300 + 60));
+        assert_eq!(timers.open_entries().len(), 1);
+
+        stop(&conn, &mut timers, a, 5, 400).unwrap();
+        assert_eq!(timers.resolve(None).unwrap(), b);
+        assert!(timers.resolve(Some(a)).is_err());
+        let listed = list_running(&timers, 400);
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].primary && listed[0].paused);
+    }
+
+    #[test]
+    fn preset_targets_are_reported_once() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let mut timers = TimerState::default();
+
+        start(&conn, &mut timers, "A".to_string(), String::new(), Some(25), 100).unwrap();
+        start(&conn, &mut timers, "B".to_string(), String::new(), None, 100).unwrap();
+        assert!(timers.reached_targets(100 + 25 * 60 - 1).is_empty());
+        assert_eq!(timers.reached_targets(100 + 25 * 60), vec!["A"]);
+        assert!(timers.reached_targets(100 + 60 * 60).is_empty());
+    }
+
+    #[test]
+    fn recent_projects_follow_timer_starts() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        entries::insert_entry(&conn, "Old", "", 0, Some(60)).unwrap();
+        entries::insert_entry(&conn, "Older", "", 100, Some(160)).unwrap();
+        assert_eq!(recent_projects(&conn, 5).unwrap(), vec!["Older", "Old"]);
+
+        let mut timers = TimerState::default();
+        for project in ["Acme", "Beta", "Acme"] {
+            start(&conn, &mut timers, project.to_string(), String::new(), None, 1_000).unwrap();
+        }
+        // A later manual entry doesn't count as recent activity
+        entries::insert_entry(&conn, "Manual", "", 2_000, Some(2_100)).unwrap();
+        assert_eq!(recent_projects(&conn, 5).unwrap(), vec!["Acme", "Beta"]);
+        assert_eq!(recent_projects(&conn, 1).unwrap(), vec!["Acme"]);
+    }
+
+    #[test]
+    fn overlong_timers_end_at_the_cap() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let mut timers = TimerState::default();
+        let forgotten = start(&conn, &mut timers, "Acme".to_string(), String::new(), None, 1_000).unwrap();
+        let recent = start(&conn, &mut timers, "Other".to_string(), String::new(), None, 50_000).unwrap();
+
+        assert!(cap_overlong(&conn, &mut timers, 36_000, 30_000).unwrap().is_empty());
+        let capped = cap_overlong(&conn, &mut timers, 36_000, 60_000).unwrap();
+        assert_eq!(capped.len(), 1);
+        assert_eq!((capped[0].timer_id, capped[0].start_ts), (forgotten, 1_000));
+        assert_eq!(capped[0].entry.end_ts, Some(37_000));
+        assert!(timers.get(forgotten).is_none());
+        assert!(timers.get(recent).is_some());
+        assert!(set_max_entry_seconds(&conn, Some(0)).is_err());
+    }
+
+    #[test]
+    fn shifting_keeps_elapsed_across_a_clock_jump() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let mut timers = TimerState::default();
+        let id = start(&conn, &mut timers, "Acme".to_string(), String::new(), None, 10_000).unwrap();
+
+        // Twenty real minutes pass, but the clock is set an hour back
+        let now = 10_000 + 1_200 - 3_600;
+        assert_eq!(shift_running(&conn, &mut timers, -3_600).unwrap().len(), 1);
+        assert_eq!(status(&timers, Some(id), now).elapsed_seconds, 1_200);
+        let entry_id = timers.get(id).unwrap().entry_id.unwrap();
+        assert_eq!(entries::get_entry(&conn, entry_id).unwrap().start_ts, 6_400);
+
+        // Forward jumps are left alone
+        assert!(shift_running(&conn, &mut timers, 3_600).unwrap().is_empty());
+        assert_eq!(entries::get_entry(&conn, entry_id).unwrap().start_ts, 6_400);
+    }
+}
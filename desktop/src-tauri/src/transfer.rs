@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const IMPORT_PROGRESS_EVENT: &str = "import-progress";
+pub const EXPORT_PROGRESS_EVENT: &str = "export-progress";
+pub const CANCELLED_ERROR: &str = "cancelled";
+
+// Progress events are sent at most this often, plus once at the end
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Progress {
+    pub processed: u64,
+    pub total: u64,
+}
+
+// Cancellation flag for the running import, backup or restore
+#[derive(Default)]
+pub struct Transfer {
+    cancelled: AtomicBool,
+}
+
+impl Transfer {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    // Clear a cancel left over from an earlier transfer
+    fn begin(&self) {
+        self.cancelled.store(false, Ordering::Relaxed);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+// Throttled progress reporting for one transfer. `report` fails once the user
+// cancels, so the loop calling it stops and its transaction rolls back.
+pub struct Reporter<'a> {
+    app: &'a AppHandle,
+    event: &'static str,
+    last: Option<Instant>,
+}
+
+impl<'a> Reporter<'a> {
+    pub fn new(app: &'a AppHandle, event: &'static str) -> Self {
+        app.state::<Transfer>().begin();
+        Self { app, event, last: None }
+    }
+
+    pub fn report(&mut self, processed: u64, total: u64) -> Result<(), String> {
+        if self.app.state::<Transfer>().is_cancelled() {
+            return Err(CANCELLED_ERROR.to_string());
+        }
+        let due = self.last.map_or(true, |at| at.elapsed() >= REPORT_INTERVAL);
+        if due || processed >= total {
+            let _ = self.app.emit(self.event, Progress { processed, total });
+            self.last = Some(Instant::now());
+        }
+        Ok(())
+    }
+}
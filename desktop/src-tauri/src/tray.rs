@@ -0,0 +1,574 @@
+use crate::timer::{self, TimerStatus};
+
+#[cfg(desktop)]
+use std::sync::atomic::{AtomicI64, Ordering};
+
+#[cfg(desktop)]
+use tauri::{
+    image::Image,
+    menu::{Menu, MenuItem, Submenu},
+    tray::TrayIconId,
+    Manager, Runtime, Theme,
+};
+
+#[cfg(desktop)]
+use tauri::Emitter;
+
+#[cfg(desktop)]
+use crate::{
+    db::{self, Database},
+    i18n::I18n,
+    presets, settings, stats, timefmt,
+    timer::Timer,
+    widget,
+};
+
+// How long the "not saved" notice stays in the tray after a discarded stop
+#[cfg(desktop)]
+const DISCARDED_NOTICE: std::time::Duration = std::time::Duration::from_secs(3);
+
+pub const TRAY_ID: &str = "main-tray";
+
+// When a tray title was last set successfully, for widget diagnostics; 0 means never
+#[cfg(desktop)]
+static LAST_TITLE_UPDATE: AtomicI64 = AtomicI64::new(0);
+
+// Menu ids of preset items are prefixed so the handler can recover the name
+pub const PRESET_ITEM_PREFIX: &str = "preset:";
+
+// Source for the tray glyph; only its alpha channel is used
+#[cfg(desktop)]
+static TRAY_ICON_PNG: &[u8] = include_bytes!("../icons/icon.png");
+
+pub const CLICK_ACTION_SETTING: &str = "tray_click_action";
+// Unset (the default) means double-clicks are just two single clicks
+pub const DOUBLE_CLICK_ACTION_SETTING: &str = "tray_double_click_action";
+pub const EMOJI_SETTING: &str = "tray_emoji";
+pub const DEFAULT_EMOJI: &str = "⏱";
+
+// Prefix of the running title, kept in memory since the title updates every second
+#[cfg(desktop)]
+pub struct TrayEmoji(std::sync::Mutex<String>);
+
+#[cfg(desktop)]
+impl TrayEmoji {
+    pub fn new(emoji: String) -> Self {
+        Self(std::sync::Mutex::new(emoji))
+    }
+
+    pub fn set(&self, emoji: String) {
+        if let Ok(mut current) = self.0.lock() {
+            *current = emoji;
+        }
+    }
+
+    fn get(&self) -> String {
+        self.0
+            .lock()
+            .map(|emoji| emoji.clone())
+            .unwrap_or_else(|_| DEFAULT_EMOJI.to_string())
+    }
+}
+
+// A single grapheme (so "🟢" or "👩‍💻" but not "⏱⏱"), or empty for no emoji
+pub fn validate_emoji(emoji: &str) -> Result<String, String> {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    let emoji = emoji.trim();
+    if emoji.graphemes(true).count() > 1 {
+        return Err(format!("'{}' is more than one character", emoji));
+    }
+    Ok(emoji.to_string())
+}
+
+#[cfg(desktop)]
+pub fn set_emoji(app: &tauri::AppHandle, emoji: &str) -> Result<(), String> {
+    let emoji = validate_emoji(emoji)?;
+    settings::set(&*app.state::<Database>().lock()?, EMOJI_SETTING, &emoji)?;
+    *app.state::<TrayEmoji>().0.lock().map_err(|_| "tray emoji lock poisoned")? = emoji;
+    Ok(())
+}
+
+pub const IDLE_MODE_SETTING: &str = "tray_idle_mode";
+
+// What the tray title shows while no timer runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdleMode {
+    #[default]
+    AppName,
+    TodayTotal,
+    LastProject,
+}
+
+impl IdleMode {
+    pub fn parse(mode: &str) -> Result<Self, String> {
+        match mode {
+            "app_name" => Ok(Self::AppName),
+            "today_total" => Ok(Self::TodayTotal),
+            "last_project" => Ok(Self::LastProject),
+            other => Err(format!(
+                "unknown idle tray mode '{}', expected app_name, today_total or last_project",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::AppName => "app_name",
+            Self::TodayTotal => "today_total",
+            Self::LastProject => "last_project",
+        }
+    }
+}
+
+// "1:05" for an hour and five minutes; seconds would go stale without a ticker
+fn format_total(seconds: i64) -> String {
+    format!("{}:{:02}", seconds / 3600, seconds % 3600 / 60)
+}
+
+// Idle title for the saved mode, read straight from the database so it is
+// right before the UI loads. Falls back to the app name while locked.
+#[cfg(desktop)]
+fn idle_title(app: &tauri::AppHandle) -> String {
+    let i18n = app.state::<I18n>();
+    let Some(db) = app.try_state::<Database>() else {
+        return i18n.t("tray.title");
+    };
+    let Ok(conn) = db.lock() else {
+        return i18n.t("tray.title");
+    };
+    let mode = settings::get::<String>(&conn, IDLE_MODE_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|mode| IdleMode::parse(&mode).ok())
+        .unwrap_or_default();
+    let title = match mode {
+        IdleMode::AppName => None,
+        IdleMode::TodayTotal => timefmt::configured_zone(&conn)
+            .and_then(|tz| stats::today_seconds(&conn, &tz, db::now_ts()))
+            .map_err(|e| log::warn!("failed to total today: {}", e))
+            .ok()
+            .map(|seconds| i18n.t("tray.today_total").replace("{total}", &format_total(seconds))),
+        IdleMode::LastProject => timer::recent_projects(&conn, 1)
+            .map_err(|e| log::warn!("failed to read recent projects: {}", e))
+            .ok()
+            .and_then(|recent| recent.into_iter().next())
+            .map(|project| i18n.t("tray.last_project").replace("{project}", &project)),
+    };
+    title.unwrap_or_else(|| i18n.t("tray.title"))
+}
+
+#[cfg(desktop)]
+pub fn set_idle_mode(app: &tauri::AppHandle, mode: IdleMode) -> Result<(), String> {
+    settings::set(&*app.state::<Database>().lock()?, IDLE_MODE_SETTING, &mode.as_str())?;
+    let idle = app
+        .state::<Timer>()
+        .lock()
+        .map(|timers| timers.is_empty())
+        .unwrap_or(false);
+    if idle {
+        set_title(app, "", "")?;
+    }
+    Ok(())
+}
+
+#[cfg(mobile)]
+pub fn set_idle_mode(_app: &tauri::AppHandle, _mode: IdleMode) -> Result<(), String> {
+    Ok(())
+}
+
+// What a left click on the tray icon does; right click always opens the menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClickAction {
+    #[default]
+    Widget,
+    MainWindow,
+    Menu,
+    ToggleTimer,
+}
+
+impl ClickAction {
+    pub fn parse(action: &str) -> Result<Self, String> {
+        match action {
+            "widget" => Ok(Self::Widget),
+            "main_window" => Ok(Self::MainWindow),
+            "menu" => Ok(Self::Menu),
+            "toggle_timer" => Ok(Self::ToggleTimer),
+            other => Err(format!(
+                "unknown tray click action '{}', expected widget, main_window, menu or toggle_timer",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Widget => "widget",
+            Self::MainWindow => "main_window",
+            Self::Menu => "menu",
+            Self::ToggleTimer => "toggle_timer",
+        }
+    }
+}
+
+// Saved left-click action; falls back to the widget while the database is locked
+#[cfg(desktop)]
+pub fn click_action<R: Runtime, M: Manager<R>>(app: &M) -> ClickAction {
+    app.try_state::<Database>()
+        .and_then(|db| {
+            db.lock()
+                .ok()
+                .and_then(|conn| settings::get::<String>(&conn, CLICK_ACTION_SETTING).ok())
+        })
+        .flatten()
+        .and_then(|action| ClickAction::parse(&action).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(desktop)]
+pub fn set_click_action(app: &tauri::AppHandle, action: ClickAction) -> Result<(), String> {
+    settings::set(&*app.state::<Database>().lock()?, CLICK_ACTION_SETTING, &action.as_str())?;
+    if let Some(tray) = app.tray_by_id(&TrayIconId::new(TRAY_ID)) {
+        tray.set_show_menu_on_left_click(action == ClickAction::Menu)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(mobile)]
+pub fn set_click_action(_app: &tauri::AppHandle, _action: ClickAction) -> Result<(), String> {
+    Ok(())
+}
+
+// A second click within this long makes a double-click
+#[cfg(desktop)]
+const DOUBLE_CLICK_WINDOW: std::time::Duration = std::time::Duration::from_millis(300);
+
+// A single click waiting out the double-click window before it runs
+#[cfg(desktop)]
+#[derive(Default)]
+pub struct PendingClick(std::sync::Mutex<Option<(std::time::Instant, tauri::async_runtime::JoinHandle<()>)>>);
+
+// Saved double-click action, if any; `Menu` is never stored
+#[cfg(desktop)]
+fn double_click_action(app: &tauri::AppHandle) -> Option<ClickAction> {
+    app.try_state::<Database>()
+        .and_then(|db| {
+            db.lock()
+                .ok()
+                .and_then(|conn| settings::get::<String>(&conn, DOUBLE_CLICK_ACTION_SETTING).ok())
+        })
+        .flatten()
+        .and_then(|action| ClickAction::parse(&action).ok())
+}
+
+// `None` turns double-click handling off
+#[cfg(desktop)]
+pub fn set_double_click_action(app: &tauri::AppHandle, action: Option<ClickAction>) -> Result<(), String> {
+    let conn = app.state::<Database>().lock()?;
+    match action {
+        Some(ClickAction::Menu) => Err("the tray menu can't be a double-click action".to_string()),
+        Some(action) => settings::set(&conn, DOUBLE_CLICK_ACTION_SETTING, &action.as_str()),
+        None => settings::remove(&conn, DOUBLE_CLICK_ACTION_SETTING),
+    }
+}
+
+#[cfg(mobile)]
+pub fn set_double_click_action(_app: &tauri::AppHandle, _action: Option<ClickAction>) -> Result<(), String> {
+    Ok(())
+}
+
+// Tell single from double clicks. Without a double-click action single clicks
+// run right away; with one they wait out the double-click window, and a
+// second click inside it runs only the double-click action.
+#[cfg(desktop)]
+pub fn handle_left_click(app: &tauri::AppHandle) {
+    let Some(double) = double_click_action(app) else {
+        run_click_action(app, click_action(app));
+        return;
+    };
+    let pending = app.state::<PendingClick>();
+    let Ok(mut pending) = pending.0.lock() else {
+        return;
+    };
+    if let Some((at, task)) = pending.take() {
+        if at.elapsed() < DOUBLE_CLICK_WINDOW {
+            task.abort();
+            run_click_action(app, double);
+            return;
+        }
+    }
+    let app_handle = app.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(DOUBLE_CLICK_WINDOW).await;
+        run_click_action(&app_handle, click_action(&app_handle));
+    });
+    *pending = Some((std::time::Instant::now(), task));
+}
+
+// Run a click action. `Menu` is handled natively by the tray.
+#[cfg(desktop)]
+fn run_click_action(app: &tauri::AppHandle, action: ClickAction) {
+    match action {
+        ClickAction::Widget => widget::toggle(app),
+        ClickAction::MainWindow => crate::menubar::show_main(app),
+        ClickAction::Menu => {}
+        // The frontend owns project selection, so it decides whether to start or stop
+        ClickAction::ToggleTimer => {
+            let _ = app.emit("toggle-timer", ());
+        }
+    }
+}
+
+// Build the tray menu, including a submenu with one item per saved preset
+#[cfg(desktop)]
+pub fn build_menu<R: Runtime, M: Manager<R>>(app: &M) -> tauri::Result<Menu<R>> {
+    let i18n = app.state::<I18n>();
+    let timer_item = MenuItem::with_id(app, "tray_timer", i18n.t("tray.quick_timer"), true, None::<&str>)?;
+    let show_item = MenuItem::with_id(app, "tray_show", i18n.t("tray.show_main"), true, None::<&str>)?;
+    let quit_item = MenuItem::with_id(app, "tray_quit", i18n.t("tray.quit"), true, None::<&str>)?;
+
+    let saved = app
+        .try_state::<Database>()
+        .and_then(|db| db.lock().ok().and_then(|conn| presets::list_presets(&conn).ok()))
+        .unwrap_or_default();
+    let presets_menu = Submenu::new(app, i18n.t("tray.start_preset"), !saved.is_empty())?;
+    for preset in &saved {
+        let id = format!("{}{}", PRESET_ITEM_PREFIX, preset.name);
+        presets_menu.append(&MenuItem::with_id(app, id, &preset.name, true, None::<&str>)?)?;
+    }
+
+    Menu::with_items(app, &[&timer_item, &presets_menu, &show_item, &quit_item])
+}
+
+// Rebuild the tray menu after presets change
+#[cfg(desktop)]
+pub fn refresh_menu(app: &tauri::AppHandle) -> Result<(), String> {
+    if let Some(tray) = app.tray_by_id(&TrayIconId::new(TRAY_ID)) {
+        let menu = build_menu(app).map_err(|e| e.to_string())?;
+        tray.set_menu(Some(menu)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(mobile)]
+pub fn refresh_menu(_app: &tauri::AppHandle) -> Result<(), String> {
+    Ok(())
+}
+
+// Tray glyph for an appearance: white on dark menu bars, black on light ones.
+// macOS recolors template icons itself, but Windows and Linux do not.
+#[cfg(desktop)]
+pub fn themed_icon(theme: Theme) -> tauri::Result<Image<'static>> {
+    let base = Image::from_bytes(TRAY_ICON_PNG)?;
+    let shade = if theme == Theme::Dark { 255 } else { 0 };
+    let mut rgba = base.rgba().to_vec();
+    for pixel in rgba.chunks_exact_mut(4) {
+        pixel[..3].fill(shade);
+    }
+    Ok(Image::new_owned(rgba, base.width(), base.height()))
+}
+
+#[cfg(desktop)]
+pub fn apply_theme(app: &tauri::AppHandle, theme: Theme) -> Result<(), String> {
+    if let Some(tray) = app.tray_by_id(&TrayIconId::new(TRAY_ID)) {
+        let icon = themed_icon(theme).map_err(|e| e.to_string())?;
+        tray.set_icon(Some(icon)).map_err(|e| e.to_string())?;
+        tray.set_icon_as_template(true).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Whether the platform's tray shows a text title next to the icon, probed
+// once at startup so unsupported trays aren't retried every tick
+#[cfg(desktop)]
+pub struct TitleSupport(pub bool);
+
+#[cfg(desktop)]
+pub fn detect_title_support(app: &tauri::AppHandle) -> bool {
+    // Windows notification-area icons have no title at all
+    let supported = !cfg!(target_os = "windows")
+        && app.tray_by_id(&TrayIconId::new(TRAY_ID)).is_some_and(|tray| {
+            tray.set_title(Some(idle_title(app))).is_ok()
+        });
+    if !supported {
+        log::warn!("the system tray does not support titles; elapsed time is left to the widget");
+    }
+    app.manage(TitleSupport(supported));
+    supported
+}
+
+#[cfg(desktop)]
+pub fn supports_title(app: &tauri::AppHandle) -> bool {
+    app.try_state::<TitleSupport>().map_or(true, |support| support.0)
+}
+
+#[cfg(mobile)]
+pub fn supports_title(_app: &tauri::AppHandle) -> bool {
+    false
+}
+
+// Set the tray title from an elapsed string and project name
+#[cfg(desktop)]
+pub fn set_title(app: &tauri::AppHandle, elapsed: &str, project: &str) -> Result<(), String> {
+    if !supports_title(app) {
+        return Ok(());
+    }
+    if let Some(tray) = app.tray_by_id(&TrayIconId::new(TRAY_ID)) {
+        let emoji = app
+            .try_state::<TrayEmoji>()
+            .map(|emoji| emoji.get())
+            .unwrap_or_else(|| DEFAULT_EMOJI.to_string());
+        let prefix = if emoji.is_empty() { String::new() } else { format!("{} ", emoji) };
+        let title = if !elapsed.is_empty() && !project.is_empty() {
+            format!("{}{} • {}", prefix, elapsed, project)
+        } else if !elapsed.is_empty() {
+            format!("{}{}", prefix, elapsed)
+        } else {
+            // Callers such as stop_timer still hold the database here, so
+            // the idle title is looked up once they release it
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move { show_idle_title(&app) });
+            return Ok(());
+        };
+
+        tray.set_title(Some(&title))
+            .map_err(|e| e.to_string())?;
+        title_updated();
+    } else {
+        log::warn!("tray icon not found; title not updated");
+    }
+    Ok(())
+}
+
+#[cfg(desktop)]
+fn show_idle_title(app: &tauri::AppHandle) {
+    let running = app
+        .state::<Timer>()
+        .lock()
+        .map(|timers| !timers.is_empty())
+        .unwrap_or(false);
+    if running {
+        return;
+    }
+    let title = idle_title(app);
+    if let Some(tray) = app.tray_by_id(&TrayIconId::new(TRAY_ID)) {
+        match tray.set_title(Some(&title)) {
+            Ok(()) => title_updated(),
+            Err(e) => log::warn!("failed to set idle tray title: {}", e),
+        }
+    }
+}
+
+#[cfg(desktop)]
+fn title_updated() {
+    LAST_TITLE_UPDATE.store(db::now_ts(), Ordering::Relaxed);
+}
+
+#[cfg(desktop)]
+pub fn last_title_update() -> Option<i64> {
+    Some(LAST_TITLE_UPDATE.load(Ordering::Relaxed)).filter(|ts| *ts > 0)
+}
+
+#[cfg(mobile)]
+pub fn set_title(_app: &tauri::AppHandle, _elapsed: &str, _project: &str) -> Result<(), String> {
+    Ok(())
+}
+
+// Show `text` as the tray title as-is, e.g. a rotation item
+#[cfg(desktop)]
+pub fn set_text(app: &tauri::AppHandle, text: &str) -> Result<(), String> {
+    if !supports_title(app) {
+        return Ok(());
+    }
+    if let Some(tray) = app.tray_by_id(&TrayIconId::new(TRAY_ID)) {
+        tray.set_title(Some(text)).map_err(|e| e.to_string())?;
+        title_updated();
+    }
+    Ok(())
+}
+
+// Briefly tell the user a too-short entry was thrown away, then fall back to
+// the idle title unless a new timer started in the meantime
+#[cfg(desktop)]
+pub fn show_discarded(app: &tauri::AppHandle) {
+    if !supports_title(app) {
+        return;
+    }
+    if let Some(tray) = app.tray_by_id(&TrayIconId::new(TRAY_ID)) {
+        let _ = tray.set_title(Some(app.state::<I18n>().t("tray.entry_discarded")));
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(DISCARDED_NOTICE).await;
+        let idle = app
+            .state::<Timer>()
+            .lock()
+            .map(|timers| timers.is_empty())
+            .unwrap_or(false);
+        if idle {
+            let _ = set_title(&app, "", "");
+        }
+    });
+}
+
+#[cfg(mobile)]
+pub fn show_discarded(_app: &tauri::AppHandle) {}
+
+// Elapsed time formatted the way `get_display_elapsed` gives it to the widget
+fn display_elapsed(app: &tauri::AppHandle, seconds: i64) -> String {
+    use tauri::Manager;
+    app.try_state::<timer::DurationDisplay>()
+        .map(|display| display.format(seconds))
+        .unwrap_or_else(|| timer::format_elapsed(seconds))
+}
+
+// Render the Rust-side timer status into the tray, matching the frontend format
+pub fn show_status(app: &tauri::AppHandle, status: &TimerStatus) -> Result<(), String> {
+    #[cfg(desktop)]
+    if crate::rotation::owns_title(app, status) {
+        return Ok(());
+    }
+    if !status.running {
+        return set_title(app, "", "");
+    }
+    // Several timers are summarized as "⏱ 2 timers • 01:23:45"
+    if status.running_count > 1 {
+        use tauri::Manager;
+        let count = app
+            .state::<crate::i18n::I18n>()
+            .t("tray.running_timers")
+            .replace("{count}", &status.running_count.to_string());
+        return set_title(app, &count, &display_elapsed(app, status.total_elapsed_seconds));
+    }
+    let mut elapsed = display_elapsed(app, status.elapsed_seconds);
+    if status.paused {
+        elapsed.push_str(" ⏸");
+    }
+    set_title(app, &elapsed, status.project.as_deref().unwrap_or(""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tray_emoji_must_be_one_grapheme() {
+        assert_eq!(validate_emoji(" 🟢 ").unwrap(), "🟢");
+        // ZWJ sequences and flags are a single grapheme
+        assert!(validate_emoji("👩‍💻").is_ok());
+        assert!(validate_emoji("🇩🇪").is_ok());
+        assert_eq!(validate_emoji("").unwrap(), "");
+        assert!(validate_emoji("⏱⏱").is_err());
+        assert!(validate_emoji("ab").is_err());
+    }
+
+    #[test]
+    fn idle_total_drops_seconds() {
+        assert_eq!(format_total(0), "0:00");
+        assert_eq!(format_total(3_959), "1:05");
+        assert_eq!(IdleMode::parse("today_total").unwrap().as_str(), "today_total");
+        assert!(IdleMode::parse("clock").is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use std::sync::{Mutex, MutexGuard};
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::billing;
+use crate::budget;
+use crate::colors;
+use crate::entries::Entry;
+use crate::presets::Preset;
+use crate::projects::ProjectSettings;
+use crate::recurrence::Recurrence;
+use crate::tags;
+
+// How many bulk operations can be undone
+const MAX_DEPTH: usize = 20;
+
+// A step that puts the database back the way it was before an operation
+#[derive(Debug, Clone)]
+pub enum UndoOp {
+    // Write these rows back with their original ids, recreating deleted
+    // entries and reverting modified ones
+    RestoreEntries(Vec<Entry>),
+    RestorePresets(Vec<Preset>),
+    // Recurrences with their (recurrence_id, occurrence_ts) runs, so restored
+    // recurrences don't recreate entries that were already materialized
+    RestoreRecurrences(Vec<Recurrence>, Vec<(i64, i64)>),
+    // (entry_id, tags) pairs, each entry getting exactly these tags back
+    RestoreTags(Vec<(i64, Vec<String>)>),
+    // Put back whichever of a project's color, rate and budget were set
+    RestoreProjectSettings(ProjectSettings),
+}
+
+#[derive(Debug, Clone)]
+pub struct UndoAction {
+    pub label: String,
+    pub ops: Vec<UndoOp>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Undone {
+    pub label: String,
+    pub entry_ids: Vec<i64>,
+}
+
+// In-memory history of reversible bulk operations, newest last. Lock after the database.
+#[derive(Default)]
+pub struct UndoStack(Mutex<Vec<UndoAction>>);
+
+impl UndoStack {
+    fn lock(&self) -> Result<MutexGuard<'_, Vec<UndoAction>>, String> {
+        self.0.lock().map_err(|_| "undo stack lock poisoned".to_string())
+    }
+
+    pub fn push(&self, label: impl Into<String>, ops: Vec<UndoOp>) -> Result<(), String> {
+        let mut stack = self.lock()?;
+        stack.push(UndoAction {
+            label: label.into(),
+            ops,
+        });
+        if stack.len() > MAX_DEPTH {
+            stack.remove(0);
+        }
+        Ok(())
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        self.lock()?.clear();
+        Ok(())
+    }
+
+    // Label of the operation `undo` would reverse
+    pub fn peek(&self) -> Result<Option<String>, String> {
+        Ok(self.lock()?.last().map(|action| action.label.clone()))
+    }
+
+    // Reverse the newest operation in one transaction. It stays on the stack
+    // if reverting fails so it can be retried.
+    pub fn undo(&self, conn: &mut Connection) -> Result<Option<Undone>, String> {
+        let mut stack = self.lock()?;
+        let Some(action) = stack.last() else {
+            return Ok(None);
+        };
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let mut entry_ids = Vec::new();
+        for op in action.ops.iter().rev() {
+            match op {
+                UndoOp::RestoreEntries(rows) => {
+                    for row in rows {
+                        tx.execute(
+                            "INSERT OR REPLACE INTO entries (id, project, note, start_ts, end_ts)
+                             VALUES (?1, ?2, ?3, ?4, ?5)",
+                            params![row.id, row.project, row.note, row.start_ts, row.end_ts],
+                        )
+                        .map_err(|e| e.to_string())?;
+                        entry_ids.push(row.id);
+                    }
+                }
+                UndoOp::RestorePresets(presets) => {
+                    for preset in presets {
+                        tx.execute(
+                            "INSERT OR REPLACE INTO presets (name, project, default_note, target_minutes)
+                             VALUES (?1, ?2, ?3, ?4)",
+                            params![preset.name, preset.project, preset.default_note, preset.target_minutes],
+                        )
+                        .map_err(|e| e.to_string())?;
+                    }
+                }
+                UndoOp::RestoreRecurrences(recurrences, runs) => {
+                    for r in recurrences {
+                        tx.execute(
+                            "INSERT OR REPLACE INTO recurrences
+                             (id, project, note, weekday, start_hhmm, duration_min, created_ts)
+                             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                            params![r.id, r.project, r.note, r.weekday, r.start_hhmm, r.duration_min, r.created_ts],
+                        )
+                        .map_err(|e| e.to_string())?;
+                    }
+                    for (recurrence_id, occurrence_ts) in runs {
+                        tx.execute(
+                            "INSERT OR IGNORE INTO recurrence_runs (recurrence_id, occurrence_ts) VALUES (?1, ?2)",
+                            params![recurrence_id, occurrence_ts],
+                        )
+                        .map_err(|e| e.to_string())?;
+                    }
+                }
+                UndoOp::RestoreTags(entry_tags) => {
+                    for (entry_id, tags) in entry_tags {
+                        tags::restore_tags(&tx, *entry_id, tags)?;
+                        entry_ids.push(*entry_id);
+                    }
+                }
+                UndoOp::RestoreProjectSettings(settings) => {
+                    if let Some(color) = &settings.color {
+                        colors::set_project_color(&tx, &settings.project, Some(color.as_str()))?;
+                    }
+                    if let Some(rate) = settings.rate {
+                        billing::set_rate(&tx, &settings.project, Some(rate))?;
+                    }
+                    if let Some(seconds) = settings.budget {
+                        budget::set_budget(&tx, &settings.project, Some(seconds as u64))?;
+                    }
+                }
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        let action = stack.pop().expect("checked above");
+        Ok(Some(Undone {
+            label: action.label,
+            entry_ids,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::entries::{self, EntryFilter};
+
+    #[test]
+    fn undo_restores_bulk_deleted_entries() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&conn).unwrap();
+        let kept = entries::insert_entry(&conn, "keep", "", 100, Some(200)).unwrap();
+        let gone = entries::insert_entry(&conn, "test", "n", 150, Some(250)).unwrap();
+
+        let filter = EntryFilter {
+            project: Some("test".to_string()),
+            from_ts: Some(0),
+            to_ts: Some(1000),
+        };
+        let deleted = entries::delete_entries(&mut conn, &filter).unwrap();
+        assert_eq!(deleted, vec![gone.clone()]);
+
+        let stack = UndoStack::default();
+        stack.push("delete 1 entries", vec![UndoOp::RestoreEntries(deleted)]).unwrap();
+        let undone = stack.undo(&mut conn).unwrap().unwrap();
+        assert_eq!(undone.entry_ids, vec![gone.id]);
+        assert_eq!(entries::get_entry(&conn, gone.id).unwrap(), gone);
+        assert_eq!(entries::get_entry(&conn, kept.id).unwrap(), kept);
+        assert!(stack.undo(&mut conn).unwrap().is_none());
+    }
+}
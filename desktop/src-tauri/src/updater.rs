@@ -0,0 +1,73 @@
+use serde::Serialize;
+use tauri::{async_runtime, AppHandle, Emitter};
+use tauri_plugin_updater::UpdaterExt;
+
+pub const CHECK_ON_LAUNCH_SETTING: &str = "check_updates_on_launch";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub available: bool,
+    pub current_version: String,
+    pub version: Option<String>,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+}
+
+pub async fn check(app: &AppHandle) -> Result<UpdateInfo, String> {
+    let updater = app
+        .updater()
+        .map_err(|e| format!("updater unavailable: {}", e))?;
+    let update = updater
+        .check()
+        .await
+        .map_err(|e| format!("update check failed: {}", e))?;
+    Ok(match update {
+        Some(update) => UpdateInfo {
+            available: true,
+            current_version: update.current_version.clone(),
+            version: Some(update.version.clone()),
+            notes: update.body.clone(),
+            date: update.date.map(|date| date.to_string()),
+        },
+        None => UpdateInfo {
+            available: false,
+            current_version: app.package_info().version.to_string(),
+            version: None,
+            notes: None,
+            date: None,
+        },
+    })
+}
+
+// Download and install the pending update, then restart into it
+pub async fn install(app: &AppHandle) -> Result<(), String> {
+    let updater = app
+        .updater()
+        .map_err(|e| format!("updater unavailable: {}", e))?;
+    let update = updater
+        .check()
+        .await
+        .map_err(|e| format!("update check failed: {}", e))?
+        .ok_or("no update available")?;
+    update
+        .download_and_install(|_, _| {}, || {})
+        .await
+        .map_err(|e| format!("update install failed: {}", e))?;
+    app.restart()
+}
+
+// Check in the background (menu item or launch) and report the outcome to the
+// UI, so failures are visible instead of silently dropped
+pub fn spawn_check(app: AppHandle) {
+    async_runtime::spawn(async move {
+        match check(&app).await {
+            Ok(info) => {
+                let _ = app.emit("update-status", info);
+            }
+            Err(e) => {
+                log::warn!("{}", e);
+                let _ = app.emit("update-error", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use hmac::{Hmac, Mac};
+use rusqlite::Connection;
+use serde::Serialize;
+use sha2::Sha256;
+
+use crate::settings;
+
+pub const URL_SETTING: &str = "webhook_url";
+pub const SECRET_SETTING: &str = "webhook_secret";
+
+// Hex HMAC-SHA256 of the raw body, sent as `sha256=<hex>`
+pub const SIGNATURE_HEADER: &str = "X-TimeGrid-Signature";
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookResult {
+    pub status: u16,
+    // Whether the endpoint answered with a 2xx status
+    pub ok: bool,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub url: String,
+    pub secret: Option<String>,
+}
+
+pub fn config(conn: &Connection) -> Result<Option<WebhookConfig>, String> {
+    let Some(url) = settings::get::<String>(conn, URL_SETTING)? else {
+        return Ok(None);
+    };
+    let secret = settings::get::<String>(conn, SECRET_SETTING)?.filter(|secret| !secret.is_empty());
+    Ok(Some(WebhookConfig { url, secret }))
+}
+
+// `None` removes the webhook; an empty secret sends unsigned requests
+pub fn set_config(conn: &Connection, url: Option<&str>, secret: Option<&str>) -> Result<(), String> {
+    let Some(url) = url.map(str::trim).filter(|url| !url.is_empty()) else {
+        settings::remove(conn, URL_SETTING)?;
+        return settings::remove(conn, SECRET_SETTING);
+    };
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid webhook URL '{}': {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("webhook URL '{}' must use http or https", url));
+    }
+    settings::set(conn, URL_SETTING, &url)?;
+    match secret.filter(|secret| !secret.is_empty()) {
+        Some(secret) => settings::set(conn, SECRET_SETTING, &secret),
+        None => settings::remove(conn, SECRET_SETTING),
+    }
+}
+
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// POST a synthetic `test` event and time the round trip. Touches no entries.
+pub async fn send_test(config: &WebhookConfig, now: i64) -> Result<WebhookResult, String> {
+    let body = serde_json::to_vec(&serde_json::json!({
+        "event": "test",
+        "sent_at": now,
+        "data": {},
+    }))
+    .map_err(|e| e.to_string())?;
+
+    let client = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut request = client
+        .post(&config.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json");
+    if let Some(secret) = &config.secret {
+        request = request.header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, &body)));
+    }
+
+    let started = Instant::now();
+    let response = request.body(body).send().await.map_err(|e| {
+        if e.is_timeout() {
+            format!("webhook {} did not answer within {}s", config.url, TIMEOUT.as_secs())
+        } else if e.is_connect() {
+            format!("could not connect to webhook {}: {}", config.url, e)
+        } else {
+            format!("webhook request to {} failed: {}", config.url, e)
+        }
+    })?;
+    let status = response.status();
+    Ok(WebhookResult {
+        status: status.as_u16(),
+        ok: status.is_success(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_matches_rfc_4231_vector() {
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
@@ -0,0 +1,517 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tauri::async_runtime::{self, JoinHandle};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, LogicalSize, Manager, WebviewWindow};
+
+#[cfg(desktop)]
+use tauri::{tray::TrayIconId, Emitter, PhysicalPosition, Position, Size};
+
+use crate::db::Database;
+use crate::settings;
+#[cfg(desktop)]
+use crate::tray;
+
+pub const WIDGET_LABEL: &str = "timer-widget";
+pub const AUTOHIDE_SETTING: &str = "widget_autohide_ms";
+pub const VISIBLE_SETTING: &str = "widget_visible";
+pub const SIZE_SETTING: &str = "widget_size";
+pub const NONACTIVATING_SETTING: &str = "widget_nonactivating";
+// Where the user dragged the widget, relative to its spot under the tray
+pub const OFFSET_SETTING: &str = "widget_offset";
+// Sent with `true`/`false` when the main window enters or leaves fullscreen
+pub const FULLSCREEN_EVENT: &str = "fullscreen-changed";
+
+// Bounds for `set_widget_size`, in logical pixels
+const MIN_SIZE: (f64, f64) = (240.0, 160.0);
+const MAX_SIZE: (f64, f64) = (640.0, 900.0);
+
+// Toggles arriving closer together than this (double clicks, a held
+// shortcut) are ignored so they can't race each other
+const TOGGLE_DEBOUNCE: Duration = Duration::from_millis(300);
+
+// How often the tray icon's position is checked while the widget is open
+#[cfg(desktop)]
+const TRAY_RECT_POLL: Duration = Duration::from_secs(1);
+
+// Drags are saved once the widget has been still this long
+#[cfg(desktop)]
+const OFFSET_SAVE_DELAY: Duration = Duration::from_millis(500);
+
+// Tray icon bounds as (x, y, width, height) in physical pixels
+type TrayRect = (f64, f64, f64, f64);
+
+// Widget dimensions; the default matches the window config
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WidgetSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Default for WidgetSize {
+    fn default() -> Self {
+        Self {
+            width: 320.0,
+            height: 440.0,
+        }
+    }
+}
+
+impl WidgetSize {
+    pub fn new(width: f64, height: f64) -> Result<Self, String> {
+        let in_bounds = |value: f64, min: f64, max: f64| value.is_finite() && (min..=max).contains(&value);
+        if !in_bounds(width, MIN_SIZE.0, MAX_SIZE.0) || !in_bounds(height, MIN_SIZE.1, MAX_SIZE.1) {
+            return Err(format!(
+                "widget size must be between {}x{} and {}x{}",
+                MIN_SIZE.0, MIN_SIZE.1, MAX_SIZE.0, MAX_SIZE.1
+            ));
+        }
+        Ok(Self { width, height })
+    }
+}
+
+// Widget behavior shared between the window event handlers and commands
+pub struct WidgetState {
+    autohide_ms: AtomicU64,
+    pending_hide: Mutex<Option<JoinHandle<()>>>,
+    last_toggle: Mutex<Option<Instant>>,
+    // Where the tray icon was when the widget was last positioned
+    tray_rect: Mutex<Option<TrayRect>>,
+    size: Mutex<WidgetSize>,
+    nonactivating: AtomicBool,
+    // Whether the main window is fullscreen, which makes the widget
+    // non-activating regardless of the setting
+    main_fullscreen: AtomicBool,
+    // Dragged offset from the tray-derived position, in physical pixels
+    offset: Mutex<Option<(f64, f64)>>,
+    // The tray-derived position and where the widget was actually placed,
+    // so our own moves aren't mistaken for drags
+    anchor: Mutex<Option<((f64, f64), (i32, i32))>>,
+    pending_offset_save: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl WidgetState {
+    pub fn new(autohide_ms: u64, size: WidgetSize, offset: Option<(f64, f64)>) -> Self {
+        Self {
+            autohide_ms: AtomicU64::new(autohide_ms),
+            pending_hide: Mutex::new(None),
+            last_toggle: Mutex::new(None),
+            tray_rect: Mutex::new(None),
+            size: Mutex::new(size),
+            nonactivating: AtomicBool::new(false),
+            main_fullscreen: AtomicBool::new(false),
+            offset: Mutex::new(offset),
+            anchor: Mutex::new(None),
+            pending_offset_save: Mutex::new(None),
+        }
+    }
+
+    // Record a toggle, returning false if it follows the previous one too closely
+    fn accept_toggle(&self) -> bool {
+        let Ok(mut last) = self.last_toggle.lock() else {
+            return false;
+        };
+        let now = Instant::now();
+        if last.is_some_and(|at| now.duration_since(at) < TOGGLE_DEBOUNCE) {
+            return false;
+        }
+        *last = Some(now);
+        true
+    }
+
+    pub fn set_autohide_ms(&self, ms: u64) {
+        self.autohide_ms.store(ms, Ordering::Relaxed);
+    }
+
+    // Shown without taking focus, by setting or while the main window is fullscreen
+    fn is_nonactivating(&self) -> bool {
+        self.nonactivating.load(Ordering::Relaxed) || self.main_fullscreen.load(Ordering::Relaxed)
+    }
+
+    pub fn size(&self) -> WidgetSize {
+        self.size.lock().map(|size| *size).unwrap_or_default()
+    }
+
+    fn offset(&self) -> Option<(f64, f64)> {
+        self.offset.lock().ok().and_then(|offset| *offset)
+    }
+
+    // Abort a deferred hide, e.g. because the widget regained focus
+    pub fn cancel_hide(&self) {
+        if let Ok(mut pending) = self.pending_hide.lock() {
+            if let Some(task) = pending.take() {
+                task.abort();
+            }
+        }
+    }
+}
+
+#[cfg(desktop)]
+fn tray_rect(app: &AppHandle) -> Option<TrayRect> {
+    let tray = app.tray_by_id(&TrayIconId::new(tray::TRAY_ID))?;
+    let rect = tray.rect().ok().flatten()?;
+
+    // Extract physical positions from Tauri Position/Size enums
+    let (x, y) = match rect.position {
+        Position::Physical(pos) => (pos.x as f64, pos.y as f64),
+        Position::Logical(pos) => (pos.x, pos.y),
+    };
+    let (width, height) = match rect.size {
+        Size::Physical(size) => (size.width as f64, size.height as f64),
+        Size::Logical(size) => (size.width, size.height),
+    };
+    Some((x, y, width, height))
+}
+
+// Keep a window of `size` at `position` fully inside `area` (x, y, width,
+// height), pinning it to the top-left corner if it is larger than the area
+#[cfg(desktop)]
+fn clamp_to_area(position: (f64, f64), size: (f64, f64), area: (f64, f64, f64, f64)) -> (f64, f64) {
+    let (x, y, width, height) = area;
+    (
+        position.0.min(x + width - size.0).max(x),
+        position.1.min(y + height - size.1).max(y),
+    )
+}
+
+// Helper function to position widget window below tray icon
+#[cfg(desktop)]
+pub fn position_widget_window(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(widget) = app.get_webview_window(WIDGET_LABEL) {
+        if let Some(rect) = tray_rect(app) {
+            let (tray_x, tray_y, tray_width, tray_height) = rect;
+            let state = app.state::<WidgetState>();
+            let monitor = widget
+                .monitor_from_point(tray_x, tray_y)?
+                .or(widget.primary_monitor()?);
+            // The tray rect is physical pixels but the widget size is logical
+            let scale = monitor.as_ref().map_or(1.0, |monitor| monitor.scale_factor());
+            let window_width = state.size().width * scale;
+
+            // Position window below the tray icon, centered horizontally
+            let x = tray_x + (tray_width / 2.0) - (window_width / 2.0);
+            let y = tray_y + tray_height + 8.0 * scale; // 8px gap below tray
+
+            // Apply the dragged offset, but never past the edge of the tray's display
+            let (dx, dy) = state.offset().unwrap_or_default();
+            let mut position = (x + dx, y + dy);
+            if let Some(monitor) = monitor {
+                let (origin, size) = (monitor.position(), monitor.size());
+                let window = widget.outer_size()?;
+                position = clamp_to_area(
+                    position,
+                    (window.width as f64, window.height as f64),
+                    (origin.x as f64, origin.y as f64, size.width as f64, size.height as f64),
+                );
+            }
+
+            let placed = (position.0 as i32, position.1 as i32);
+            if let Ok(mut anchor) = state.anchor.lock() {
+                *anchor = Some(((x, y), placed));
+            }
+            widget.set_position(PhysicalPosition::new(placed.0, placed.1))?;
+            if let Ok(mut last) = state.tray_rect.lock() {
+                *last = Some(rect);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Remember where the user dragged the widget relative to its spot under the
+// tray. Saving waits until the drag settles rather than writing every step.
+#[cfg(desktop)]
+pub fn on_moved(app: &AppHandle, position: PhysicalPosition<i32>) {
+    let state = app.state::<WidgetState>();
+    let Some(((x, y), placed)) = state.anchor.lock().ok().and_then(|anchor| *anchor) else {
+        return;
+    };
+    if (position.x, position.y) == placed {
+        return;
+    }
+    let offset = (position.x as f64 - x, position.y as f64 - y);
+    if let Ok(mut current) = state.offset.lock() {
+        *current = Some(offset);
+    }
+    let Ok(mut pending) = state.pending_offset_save.lock() else {
+        return;
+    };
+    if let Some(task) = pending.take() {
+        task.abort();
+    }
+    let app = app.clone();
+    *pending = Some(async_runtime::spawn(async move {
+        tokio::time::sleep(OFFSET_SAVE_DELAY).await;
+        let result = app
+            .state::<Database>()
+            .lock()
+            .and_then(|conn| settings::set(&conn, OFFSET_SETTING, &offset));
+        if let Err(e) = result {
+            log::warn!("failed to save widget position: {}", e);
+        }
+    }));
+}
+
+// Forget the dragged offset and go back under the tray icon
+#[cfg(desktop)]
+pub fn reset_position(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<WidgetState>();
+    if let Ok(mut pending) = state.pending_offset_save.lock() {
+        if let Some(task) = pending.take() {
+            task.abort();
+        }
+    }
+    if let Ok(mut offset) = state.offset.lock() {
+        *offset = None;
+    }
+    settings::remove(&*app.state::<Database>().lock()?, OFFSET_SETTING)?;
+    position_widget_window(app).map_err(|e| e.to_string())
+}
+
+// Follow the tray icon when it moves (menu bar moved, display connected)
+// while the widget is open, instead of only placing it on toggle
+#[cfg(desktop)]
+pub fn spawn_tray_watcher(app: AppHandle) {
+    async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TRAY_RECT_POLL);
+        loop {
+            interval.tick().await;
+            let visible = app
+                .get_webview_window(WIDGET_LABEL)
+                .is_some_and(|widget| widget.is_visible().unwrap_or(false));
+            if !visible {
+                continue;
+            }
+            let current = tray_rect(&app);
+            let last = app
+                .state::<WidgetState>()
+                .tray_rect
+                .lock()
+                .ok()
+                .and_then(|last| *last);
+            if current.is_some() && current != last {
+                if let Err(e) = position_widget_window(&app) {
+                    log::warn!("failed to reposition widget: {}", e);
+                }
+            }
+        }
+    });
+}
+
+// Resize the widget window, remember the size for next launch and re-center
+// it under the tray icon
+pub fn set_size(app: &AppHandle, size: WidgetSize) -> Result<(), String> {
+    if let Some(db) = app.try_state::<Database>() {
+        settings::set(&*db.lock()?, SIZE_SETTING, &size)?;
+    }
+    apply_size(app, size)?;
+    #[cfg(desktop)]
+    position_widget_window(app).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Size the window without persisting, e.g. to restore the saved size on launch
+pub fn apply_size(app: &AppHandle, size: WidgetSize) -> Result<(), String> {
+    if let Ok(mut current) = app.state::<WidgetState>().size.lock() {
+        *current = size;
+    }
+    if let Some(widget) = app.get_webview_window(WIDGET_LABEL) {
+        widget
+            .set_size(LogicalSize::new(size.width, size.height))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Show the widget without taking focus, like a non-activating panel, so it
+// opens over a fullscreen Space instead of switching away from it. Without
+// focus it can't lose it either, so auto-hide on blur doesn't apply.
+#[cfg(desktop)]
+pub fn set_nonactivating(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    app.state::<WidgetState>().nonactivating.store(enabled, Ordering::Relaxed);
+    apply_presentation(app)
+}
+
+#[cfg(desktop)]
+fn apply_presentation(app: &AppHandle) -> Result<(), String> {
+    let enabled = app.state::<WidgetState>().is_nonactivating();
+    if let Some(widget) = app.get_webview_window(WIDGET_LABEL) {
+        widget.set_focusable(!enabled).map_err(|e| e.to_string())?;
+        widget
+            .set_visible_on_all_workspaces(enabled)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Called when the main window resizes, which is how fullscreen changes
+// arrive. While it is fullscreen the widget opens without taking focus so
+// toggling it doesn't leave the fullscreen Space.
+#[cfg(desktop)]
+pub fn on_main_resized(app: &AppHandle, main: &WebviewWindow) {
+    let Ok(fullscreen) = main.is_fullscreen() else {
+        return;
+    };
+    let state = app.state::<WidgetState>();
+    if state.main_fullscreen.swap(fullscreen, Ordering::Relaxed) == fullscreen {
+        return;
+    }
+    if let Err(e) = apply_presentation(app) {
+        log::warn!("failed to update widget presentation: {}", e);
+    }
+    let _ = app.emit(FULLSCREEN_EVENT, fullscreen);
+}
+
+// Persist the last visibility so the widget can be reopened on next launch
+#[cfg(desktop)]
+fn remember_visibility(app: &AppHandle, visible: bool) {
+    if let Some(db) = app.try_state::<Database>() {
+        if let Ok(conn) = db.lock() {
+            let _ = settings::set(&conn, VISIBLE_SETTING, &visible);
+        }
+    }
+}
+
+// Save whether the widget is open for the next launch. Only toggles and
+// quitting save it, so an auto-hide isn't taken as the user closing it.
+#[cfg(desktop)]
+pub fn save_visibility(app: &AppHandle) {
+    let visible = app
+        .get_webview_window(WIDGET_LABEL)
+        .is_some_and(|widget| widget.is_visible().unwrap_or(false));
+    remember_visibility(app, visible);
+}
+
+#[cfg(desktop)]
+pub fn show(app: &AppHandle) {
+    present(app, !app.state::<WidgetState>().is_nonactivating());
+}
+
+#[cfg(desktop)]
+fn present(app: &AppHandle, focus: bool) {
+    if let Some(widget) = app.get_webview_window(WIDGET_LABEL) {
+        let _ = position_widget_window(app);
+        let _ = widget.show();
+        if focus {
+            let _ = widget.set_focus();
+        }
+    }
+}
+
+pub fn hide(widget: &WebviewWindow) {
+    let _ = widget.hide();
+}
+
+// Show the widget under the tray icon, or hide it if already visible. Every
+// tray and menu toggle goes through here so repeated presses are debounced.
+#[cfg(desktop)]
+pub fn toggle(app: &AppHandle) {
+    if !app.state::<WidgetState>().accept_toggle() {
+        return;
+    }
+    if let Some(widget) = app.get_webview_window(WIDGET_LABEL) {
+        let visible = widget.is_visible().unwrap_or(false);
+        if visible {
+            hide(&widget);
+        } else {
+            show(app);
+        }
+        remember_visibility(app, !visible);
+    }
+}
+
+// Reopen the widget on launch if it was visible when the app last quit,
+// without taking focus from whatever the user is doing
+#[cfg(desktop)]
+pub fn restore_visibility(app: &AppHandle) -> Result<(), String> {
+    let visible = match app.try_state::<Database>() {
+        Some(db) if !db.is_locked()? => settings::get(&*db.lock()?, VISIBLE_SETTING)?.unwrap_or(false),
+        _ => false,
+    };
+    if visible {
+        present(app, false);
+    }
+    Ok(())
+}
+
+// Hide the widget after it loses focus, waiting the configured delay first.
+// A delay of 0 hides immediately. The widget stays pinned during focus mode.
+pub fn schedule_hide(widget: &WebviewWindow) {
+    let state = widget.state::<WidgetState>();
+    state.cancel_hide();
+
+    #[cfg(desktop)]
+    if widget.state::<crate::focus::FocusMode>().is_active() {
+        return;
+    }
+
+    let delay = state.autohide_ms.load(Ordering::Relaxed);
+    if delay == 0 {
+        hide(widget);
+        return;
+    }
+
+    let widget = widget.clone();
+    let task = async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+        hide(&widget);
+    });
+    if let Ok(mut pending) = state.pending_hide.lock() {
+        *pending = Some(task);
+    }
+}
+
+// Snapshot of the widget's state for bug reports when it fails to show or update
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WidgetDiag {
+    // False as well when the widget window doesn't exist
+    pub visible: bool,
+    // Outer position and size in physical pixels
+    pub position: Option<(i32, i32)>,
+    pub size: Option<(u32, u32)>,
+    // Whether focus mode is on, which keeps the widget from auto-hiding
+    pub focus_mode: bool,
+    pub nonactivating: bool,
+    pub last_tray_rect: Option<TrayRect>,
+    pub last_title_update_ts: Option<i64>,
+}
+
+#[cfg(desktop)]
+pub fn diagnostics(app: &AppHandle) -> WidgetDiag {
+    let state = app.state::<WidgetState>();
+    let widget = app.get_webview_window(WIDGET_LABEL);
+    WidgetDiag {
+        visible: widget.as_ref().is_some_and(|widget| widget.is_visible().unwrap_or(false)),
+        position: widget
+            .as_ref()
+            .and_then(|widget| widget.outer_position().ok())
+            .map(|position| (position.x, position.y)),
+        size: widget
+            .as_ref()
+            .and_then(|widget| widget.outer_size().ok())
+            .map(|size| (size.width, size.height)),
+        focus_mode: app.state::<crate::focus::FocusMode>().is_active(),
+        nonactivating: state.is_nonactivating(),
+        last_tray_rect: state.tray_rect.lock().ok().and_then(|rect| *rect),
+        last_title_update_ts: tray::last_title_update(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(desktop)]
+    fn clamping_keeps_the_widget_on_its_display() {
+        let display = (0.0, 0.0, 1920.0, 1080.0);
+        let size = (320.0, 440.0);
+        assert_eq!(clamp_to_area((100.0, 40.0), size, display), (100.0, 40.0));
+        assert_eq!(clamp_to_area((1800.0, -50.0), size, display), (1600.0, 0.0));
+        assert_eq!(clamp_to_area((-10.0, 900.0), size, display), (0.0, 640.0));
+        // Too big to fit: pinned to the top-left corner
+        assert_eq!(clamp_to_area((50.0, 50.0), size, (0.0, 0.0, 200.0, 300.0)), (0.0, 0.0));
+    }
+}
@@ -14,13 +14,15 @@ import { AuthProvider } from "@/contexts/AuthContext";
 import { ProtectedRoute } from "@/components/ProtectedRoute";
 import { Suspense, lazy } from "react";
 import { storage } from "@/lib/storage";
-import { useEffect, useState } from "react";
+import { useCallback, useEffect, useState } from "react";
 import { formatDurationShort } from "@/lib/utils-time";
 import { initializeApp } from "@/lib/init";
 import { useAuth } from "@/contexts/useAuth";
 import { Button } from "@/components/ui/button";
 import { LogOut, Loader2, X } from "lucide-react";
 import { useKeyboardShortcuts } from "@/hooks/useKeyboardShortcuts";
+import { useEntriesChanged } from "@/hooks/useTauriEvents";
+import { ConfirmQuitDialog } from "@/components/ConfirmQuitDialog";
 import { KeyboardShortcutsDialog } from "@/components/KeyboardShortcutsDialog";
 import { useIsMobile } from "@/hooks/use-mobile";
 import { MobileTabBar } from "@/components/MobileTabBar";
@@ -212,10 +214,19 @@ const AppContent = () => {
     };
   }, []);
   
+  // Keep both windows in sync when the desktop app changes entries
+  const handleEntriesChanged = useCallback(() => {
+    queryClient.invalidateQueries({ queryKey: ['time-entries'] });
+    queryClient.refetchQueries({ queryKey: ['time-entries'] });
+  }, []);
+  useEntriesChanged(handleEntriesChanged);
+
   return (
     <>
       {!isWidgetRoute && <Toaster />}
       {!isWidgetRoute && <Sonner />}
+      {/* Rust picks the window that asks, so the prompt appears once */}
+      <ConfirmQuitDialog />
       <Routes>
         <Route path="/login" element={<Suspense fallback={<PageLoader />}><LoginPage /></Suspense>} />
         <Route path="/signup" element={<Suspense fallback={<PageLoader />}><SignUpPage /></Suspense>} />
@@ -0,0 +1,64 @@
+import { useEffect, useState } from 'react';
+import { Button } from '@/components/ui/button';
+import { Copy } from 'lucide-react';
+import { toast } from 'sonner';
+
+interface AppInfoData {
+  version: string;
+  commit: string | null;
+  tauri_version: string;
+  os: string;
+  arch: string;
+  webview_version: string | null;
+}
+
+const isTauri = () => typeof window !== 'undefined' && '__TAURI__' in window;
+
+// Desktop only: version, build and platform details for the About card,
+// with a copy button for bug reports
+export function AppInfo() {
+  const [info, setInfo] = useState<AppInfoData | null>(null);
+
+  useEffect(() => {
+    if (!isTauri()) return;
+    import('@tauri-apps/api/core')
+      .then(({ invoke }) => invoke<AppInfoData>('get_app_info'))
+      .then(setInfo)
+      .catch(() => setInfo(null));
+  }, []);
+
+  if (!info) return null;
+
+  const rows: [string, string][] = [
+    ['Version', info.commit ? `${info.version} (${info.commit})` : info.version],
+    ['Platform', `${info.os} ${info.arch}`],
+    ['Tauri', info.tauri_version],
+    ['Webview', info.webview_version ?? 'unknown'],
+  ];
+
+  const handleCopy = async () => {
+    try {
+      await navigator.clipboard.writeText(rows.map(([label, value]) => `${label}: ${value}`).join('\n'));
+      toast.success('Copied to clipboard');
+    } catch (error) {
+      toast.error(String(error));
+    }
+  };
+
+  return (
+    <div className="pt-2 space-y-2">
+      <dl className="grid grid-cols-[auto_1fr] gap-x-4 gap-y-1">
+        {rows.map(([label, value]) => (
+          <div key={label} className="contents">
+            <dt>{label}</dt>
+            <dd className="font-mono text-foreground">{value}</dd>
+          </div>
+        ))}
+      </dl>
+      <Button variant="outline" size="sm" onClick={handleCopy}>
+        <Copy className="h-4 w-4 mr-2" />
+        Copy details
+      </Button>
+    </div>
+  );
+}
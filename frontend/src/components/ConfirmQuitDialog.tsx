@@ -0,0 +1,48 @@
+import { toast } from 'sonner';
+import {
+  AlertDialog,
+  AlertDialogAction,
+  AlertDialogCancel,
+  AlertDialogContent,
+  AlertDialogDescription,
+  AlertDialogFooter,
+  AlertDialogHeader,
+  AlertDialogTitle,
+} from '@/components/ui/alert-dialog';
+import { useConfirmQuit, type QuitAnswer } from '@/hooks/useTauriEvents';
+
+// Asked when quitting while a timer runs: save it, drop it, or keep working
+export function ConfirmQuitDialog() {
+  const { open, answer } = useConfirmQuit();
+
+  const respond = async (choice: QuitAnswer) => {
+    try {
+      await answer(choice);
+    } catch (error) {
+      toast.error(`Failed to quit: ${error}`);
+    }
+  };
+
+  return (
+    <AlertDialog open={open} onOpenChange={(isOpen) => !isOpen && respond('cancel')}>
+      <AlertDialogContent>
+        <AlertDialogHeader>
+          <AlertDialogTitle>A timer is still running</AlertDialogTitle>
+          <AlertDialogDescription>
+            Stop and save it before quitting?
+          </AlertDialogDescription>
+        </AlertDialogHeader>
+        <AlertDialogFooter>
+          <AlertDialogCancel>Cancel</AlertDialogCancel>
+          <AlertDialogAction
+            onClick={() => respond('discard')}
+            className="bg-destructive text-destructive-foreground hover:bg-destructive/90"
+          >
+            Quit without saving
+          </AlertDialogAction>
+          <AlertDialogAction onClick={() => respond('save')}>Save and quit</AlertDialogAction>
+        </AlertDialogFooter>
+      </AlertDialogContent>
+    </AlertDialog>
+  );
+}
@@ -0,0 +1,121 @@
+import { useCallback, useEffect, useState } from 'react';
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from '@/components/ui/card';
+import { Button } from '@/components/ui/button';
+import { Loader2, Wrench } from 'lucide-react';
+import { toast } from 'sonner';
+
+interface CompactReport {
+  integrity_ok: boolean;
+  integrity: string;
+  size_before: number;
+  size_after: number;
+}
+
+interface StorageInfo {
+  database_bytes: number;
+  data_dir_bytes: number;
+  entry_count: number;
+}
+
+const isTauri = () => typeof window !== 'undefined' && '__TAURI__' in window;
+
+const formatBytes = (bytes: number): string => {
+  if (bytes < 1024) return bytes + ' B';
+  if (bytes < 1024 * 1024) return (bytes / 1024).toFixed(1) + ' KB';
+  if (bytes < 1024 * 1024 * 1024) return (bytes / (1024 * 1024)).toFixed(1) + ' MB';
+  return (bytes / (1024 * 1024 * 1024)).toFixed(2) + ' GB';
+};
+
+// Desktop only: disk usage, integrity check + VACUUM of the local SQLite database
+export function DatabaseMaintenance() {
+  const [running, setRunning] = useState(false);
+  const [report, setReport] = useState<CompactReport | null>(null);
+  const [storage, setStorage] = useState<StorageInfo | null>(null);
+
+  const loadStorage = useCallback(async () => {
+    if (!isTauri()) return;
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      setStorage(await invoke<StorageInfo>('get_storage_info'));
+    } catch {
+      // Unavailable while the database is locked
+      setStorage(null);
+    }
+  }, []);
+
+  useEffect(() => {
+    loadStorage();
+  }, [loadStorage]);
+
+  if (!isTauri()) return null;
+
+  const handleCompact = async () => {
+    setRunning(true);
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      const result = await invoke<CompactReport>('compact_database');
+      setReport(result);
+      if (result.integrity_ok) {
+        toast.success(`Database compacted: ${formatBytes(result.size_before)} → ${formatBytes(result.size_after)}`);
+      } else {
+        toast.error('Integrity check found problems; the database was not compacted');
+      }
+    } catch (error) {
+      toast.error(String(error));
+    } finally {
+      setRunning(false);
+      loadStorage();
+    }
+  };
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle>Maintenance</CardTitle>
+        <CardDescription>Check the local database and reclaim unused space</CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-4">
+        {storage && (
+          <div className="grid grid-cols-3 gap-4 text-sm">
+            <div>
+              <div className="text-muted-foreground">Database</div>
+              <div className="font-medium">{formatBytes(storage.database_bytes)}</div>
+            </div>
+            <div>
+              <div className="text-muted-foreground">Data folder</div>
+              <div className="font-medium">{formatBytes(storage.data_dir_bytes)}</div>
+            </div>
+            <div>
+              <div className="text-muted-foreground">Entries</div>
+              <div className="font-medium">{storage.entry_count.toLocaleString()}</div>
+            </div>
+          </div>
+        )}
+        <div className="flex items-center justify-between">
+          <div>
+            <div className="font-medium">Compact Database</div>
+            <div className="text-sm text-muted-foreground">
+              Runs an integrity check, then VACUUM
+            </div>
+          </div>
+          <Button variant="outline" onClick={handleCompact} disabled={running}>
+            {running ? (
+              <Loader2 className="h-4 w-4 mr-2 animate-spin" />
+            ) : (
+              <Wrench className="h-4 w-4 mr-2" />
+            )}
+            Compact
+          </Button>
+        </div>
+        {report && (
+          <div className="text-sm text-muted-foreground">
+            <p>Integrity: {report.integrity_ok ? 'ok' : report.integrity}</p>
+            <p>
+              Size: {formatBytes(report.size_before)} → {formatBytes(report.size_after)}
+            </p>
+          </div>
+        )}
+      </CardContent>
+    </Card>
+  );
+}
@@ -0,0 +1,44 @@
+import { useEffect, useState } from 'react';
+import { Switch } from '@/components/ui/switch';
+import { toast } from 'sonner';
+
+const isTauri = () => typeof window !== 'undefined' && '__TAURI__' in window;
+
+// Desktop only: run from the tray and widget alone, with the main window
+// hidden. Turning it off shows the main window again.
+export function MenubarOnlyToggle() {
+  const [enabled, setEnabled] = useState<boolean | null>(null);
+
+  useEffect(() => {
+    if (!isTauri()) return;
+    import('@tauri-apps/api/core')
+      .then(({ invoke }) => invoke<boolean>('get_menubar_only'))
+      .then(setEnabled)
+      .catch(() => setEnabled(false));
+  }, []);
+
+  if (!isTauri() || enabled === null) return null;
+
+  const handleChange = async (next: boolean) => {
+    setEnabled(next);
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      await invoke('set_menubar_only', { enabled: next });
+    } catch (error) {
+      setEnabled(!next);
+      toast.error(String(error));
+    }
+  };
+
+  return (
+    <div className="flex items-center justify-between gap-4">
+      <div>
+        <div className="font-medium">Menu bar only</div>
+        <div className="text-sm text-muted-foreground">
+          Hide the main window and use the tray and widget instead
+        </div>
+      </div>
+      <Switch checked={enabled} onCheckedChange={handleChange} />
+    </div>
+  );
+}
@@ -0,0 +1,86 @@
+import { useCallback, useEffect, useState } from 'react';
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from '@/components/ui/card';
+import { Button } from '@/components/ui/button';
+import { RefreshCw } from 'lucide-react';
+
+interface WidgetDiag {
+  visible: boolean;
+  position: [number, number] | null;
+  size: [number, number] | null;
+  focus_mode: boolean;
+  nonactivating: boolean;
+  last_tray_rect: [number, number, number, number] | null;
+  last_title_update_ts: number | null;
+}
+
+const isTauri = () => typeof window !== 'undefined' && '__TAURI__' in window;
+
+const show = (value: unknown): string => {
+  if (value === null || value === undefined) return '—';
+  if (Array.isArray(value)) return value.map((n) => Math.round(Number(n))).join(', ');
+  return String(value);
+};
+
+// Desktop only, hidden behind Ctrl+Shift+D on the settings page: what the
+// widget window and tray looked like, to paste into bug reports
+export function WidgetDiagnostics() {
+  const [diag, setDiag] = useState<WidgetDiag | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const load = useCallback(async () => {
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      setDiag(await invoke<WidgetDiag>('get_widget_diagnostics'));
+      setError(null);
+    } catch (e) {
+      setError(String(e));
+    }
+  }, []);
+
+  useEffect(() => {
+    if (isTauri()) load();
+  }, [load]);
+
+  if (!isTauri()) return null;
+
+  const rows: [string, unknown][] = diag
+    ? [
+        ['Visible', diag.visible],
+        ['Position', diag.position],
+        ['Size', diag.size],
+        ['Focus mode', diag.focus_mode],
+        ['Non-activating', diag.nonactivating],
+        ['Tray icon rect', diag.last_tray_rect],
+        [
+          'Last tray title update',
+          diag.last_title_update_ts && new Date(diag.last_title_update_ts * 1000).toLocaleString(),
+        ],
+      ]
+    : [];
+
+  return (
+    <Card>
+      <CardHeader className="flex flex-row items-center justify-between space-y-0">
+        <div>
+          <CardTitle>Widget Diagnostics</CardTitle>
+          <CardDescription>Include this when reporting a widget that won't show or update</CardDescription>
+        </div>
+        <Button variant="outline" size="icon" onClick={load}>
+          <RefreshCw className="h-4 w-4" />
+          <span className="sr-only">Refresh</span>
+        </Button>
+      </CardHeader>
+      <CardContent className="text-sm">
+        {error && <p className="text-destructive">{error}</p>}
+        <dl className="grid grid-cols-2 gap-x-4 gap-y-1">
+          {rows.map(([label, value]) => (
+            <div key={label} className="contents">
+              <dt className="text-muted-foreground">{label}</dt>
+              <dd className="font-mono">{show(value)}</dd>
+            </div>
+          ))}
+        </dl>
+      </CardContent>
+    </Card>
+  );
+}
@@ -1,4 +1,4 @@
-import { useEffect } from 'react';
+import { useCallback, useEffect, useState } from 'react';
 
 // Check if we're running in Tauri
 const isTauri = () => {
@@ -33,3 +33,131 @@ export const useTauriEvents = (onToggleTimer?: () => void) => {
 
   return { isTauri: isTauri() };
 };
+
+export interface EntriesChanged {
+  change: 'insert' | 'update' | 'delete' | 'split';
+  ids: number[];
+}
+
+// Fired by the Rust side after any command that modifies entries, so every
+// window (main and widget) can refresh instead of showing a stale list
+export const useEntriesChanged = (onChange: (event: EntriesChanged) => void) => {
+  useEffect(() => {
+    if (!isTauri()) return;
+
+    let unlisten: (() => void) | undefined;
+    let cancelled = false;
+
+    const setupListener = async () => {
+      try {
+        const { event } = await import('@tauri-apps/api');
+        const stop = await event.listen<EntriesChanged>('entries-changed', ({ payload }) => {
+          onChange(payload);
+        });
+        if (cancelled) {
+          stop();
+        } else {
+          unlisten = stop;
+        }
+      } catch (error) {
+        // Silent fail if Tauri API not available
+      }
+    };
+
+    setupListener();
+
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, [onChange]);
+};
+
+// Fired when quitting with a running timer and the confirm-quit setting is on.
+// Rust sends it only to the window that is the UI (the widget in
+// menu-bar-only mode), so every window can listen.
+// OK stops and saves the timer before quitting; Cancel keeps the app open.
+export type QuitAnswer = 'save' | 'discard' | 'cancel';
+
+// Open while Rust waits for an answer to quitting with a timer running.
+// Cancelling just closes the prompt; the app stays open.
+export const useConfirmQuit = () => {
+  const [open, setOpen] = useState(false);
+
+  useEffect(() => {
+    if (!isTauri()) return;
+
+    let unlisten: (() => void) | undefined;
+    let cancelled = false;
+
+    const setupListener = async () => {
+      try {
+        const { webviewWindow } = await import('@tauri-apps/api');
+        const stop = await webviewWindow.getCurrentWebviewWindow().listen('confirm-quit', () => {
+          setOpen(true);
+        });
+        if (cancelled) {
+          stop();
+        } else {
+          unlisten = stop;
+        }
+      } catch (error) {
+        // Silent fail if Tauri API not available
+      }
+    };
+
+    setupListener();
+
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, []);
+
+  const answer = useCallback(async (choice: QuitAnswer) => {
+    setOpen(false);
+    if (choice === 'cancel') return;
+    const { core } = await import('@tauri-apps/api');
+    await core.invoke('confirm_quit', { save: choice === 'save' });
+  }, []);
+
+  return { open, answer };
+};
+
+export type MenubarAction = 'settings' | 'new_entry';
+
+// Fired at the widget in menu-bar-only mode when a tray or app menu item
+// would otherwise have opened the main window on a page
+export const useMenubarAction = (onAction: (action: MenubarAction) => void) => {
+  useEffect(() => {
+    if (!isTauri()) return;
+
+    let unlisten: (() => void) | undefined;
+    let cancelled = false;
+
+    const setupListener = async () => {
+      try {
+        const { webviewWindow } = await import('@tauri-apps/api');
+        const stop = await webviewWindow
+          .getCurrentWebviewWindow()
+          .listen<MenubarAction>('menubar-action', ({ payload }) => {
+            onAction(payload);
+          });
+        if (cancelled) {
+          stop();
+        } else {
+          unlisten = stop;
+        }
+      } catch (error) {
+        // Silent fail if Tauri API not available
+      }
+    };
+
+    setupListener();
+
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, [onAction]);
+};
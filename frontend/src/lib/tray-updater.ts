@@ -7,6 +7,8 @@ function isTauri() {
 }
 
 let updateInterval: number | undefined;
+// Probed once; trays without titles (Windows, some Linux panels) are skipped
+let traySupportsTitle: Promise<boolean> | null = null;
 let projectsCache: { id: string; name: string }[] = [];
 
 // Function to get projects - will be set by the app
@@ -16,10 +18,20 @@ export function setProjectsGetter(getter: () => Promise<{ id: string; name: stri
   getProjects = getter;
 }
 
+async function supportsTitle(): Promise<boolean> {
+  if (!traySupportsTitle) {
+    traySupportsTitle = import('@tauri-apps/api/core')
+      .then(({ invoke }) => invoke<boolean>('tray_supports_title'))
+      .catch(() => true);
+  }
+  return traySupportsTitle;
+}
+
 async function updateTrayTitle() {
   if (!isTauri()) return;
 
   try {
+    if (!(await supportsTitle())) return;
     const { invoke } = await import('@tauri-apps/api/core');
     const timerState = storage.getTimerState();
     
@@ -10,6 +10,11 @@ import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from "@
 import IcalConnect from "@/components/integrations/IcalConnect";
 import CalendarFeeds from "@/components/integrations/CalendarFeeds";
 import CalendarsManager from "@/components/integrations/CalendarsManager";
+import { DatabaseMaintenance } from "@/components/DatabaseMaintenance";
+import { MenubarOnlyToggle } from "@/components/MenubarOnlyToggle";
+import { WidgetDiagnostics } from "@/components/WidgetDiagnostics";
+import { AppInfo } from "@/components/AppInfo";
+import { useKeyboardShortcuts } from "@/hooks/useKeyboardShortcuts";
 
 const PERSONAL_FEATURES = {
   clients: true,
@@ -30,6 +35,18 @@ export default function SettingsPage() {
   const [theme, setTheme] = useState<'light' | 'dark'>('dark');
   const [timeFormat, setTimeFormat] = useState<'12h' | '24h'>('12h');
   const [hasChanges, setHasChanges] = useState(false);
+  const [showDiagnostics, setShowDiagnostics] = useState(false);
+
+  // Deliberately left out of the shortcuts dialog
+  useKeyboardShortcuts([
+    {
+      key: 'd',
+      ctrl: true,
+      shift: true,
+      callback: () => setShowDiagnostics((shown) => !shown),
+      description: 'Toggle widget diagnostics',
+    },
+  ]);
 
   // Load settings from Supabase when available
   useEffect(() => {
@@ -237,6 +254,8 @@ export default function SettingsPage() {
                 </SelectContent>
               </Select>
             </div>
+
+            <MenubarOnlyToggle />
           </CardContent>
         </Card>
 
@@ -279,6 +298,11 @@ export default function SettingsPage() {
           </CardContent>
         </Card>
 
+        {/* Maintenance (desktop app only) */}
+        <DatabaseMaintenance />
+
+        {showDiagnostics && <WidgetDiagnostics />}
+
         {/* About */}
         <Card>
           <CardHeader>
@@ -287,6 +311,7 @@ export default function SettingsPage() {
           <CardContent className="text-sm text-muted-foreground space-y-1">
             <p>TimeGrid - Modern Time Tracking</p>
             <p>Built with React, TypeScript, Tailwind CSS, and Supabase</p>
+            <AppInfo />
             <p className="mt-4 text-xs">
               {hasChanges && "⚠️ Don't forget to save your changes!"}
             </p>
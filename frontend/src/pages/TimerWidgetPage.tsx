@@ -1,5 +1,35 @@
+import { useCallback, useState } from "react";
+import { X } from "lucide-react";
 import { TimerWidget } from "@/components/TimerWidget";
+import { MenubarOnlyToggle } from "@/components/MenubarOnlyToggle";
+import { Button } from "@/components/ui/button";
+import { MenubarAction, useMenubarAction } from "@/hooks/useTauriEvents";
 
 export default function TimerWidgetPage() {
+  const [showSettings, setShowSettings] = useState(false);
+
+  // In menu-bar-only mode the widget stands in for the main window's pages
+  const handleMenubarAction = useCallback((action: MenubarAction) => {
+    setShowSettings(action === "settings");
+  }, []);
+  useMenubarAction(handleMenubarAction);
+
+  if (showSettings) {
+    return (
+      <div className="h-screen w-full bg-transparent p-2 flex flex-col">
+        <div className="backdrop-blur-2xl bg-background/80 dark:bg-background/60 border border-white/20 dark:border-white/10 rounded-2xl shadow-2xl overflow-hidden flex flex-col h-full p-4 gap-4">
+          <div className="flex items-center justify-between">
+            <span className="font-semibold">Settings</span>
+            <Button variant="ghost" size="icon" className="h-8 w-8" onClick={() => setShowSettings(false)}>
+              <X className="h-4 w-4" />
+              <span className="sr-only">Close settings</span>
+            </Button>
+          </div>
+          <MenubarOnlyToggle />
+        </div>
+      </div>
+    );
+  }
+
   return <TimerWidget />;
 }